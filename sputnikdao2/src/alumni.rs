@@ -0,0 +1,208 @@
+//! Alumni registry: members removed from group roles keep a record of their tenure and activity,
+//! preserving institutional history on-chain for reputation and re-admission decisions.
+
+use std::cmp::min;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Activity of a member of group roles over all their tenures.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MemberActivity {
+    pub proposals_authored: u64,
+    /// Number of votes to approve.
+    pub approvals: u64,
+}
+
+/// Record of a member removed from a role.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AlumniRecord {
+    pub account_id: AccountId,
+    /// Role the member was removed from.
+    pub role: String,
+    /// Group roles the member held before the removal, including `role`.
+    pub roles_held: Vec<String>,
+    /// When the member was added to the role by a proposal. None for members of the role since its creation.
+    pub joined_at: Option<U64>,
+    pub removed_at: U64,
+    /// Activity of the member when removed.
+    pub activity: MemberActivity,
+}
+
+/// Returns true if given account is a member of any group role of the policy.
+fn is_member(policy: &Policy, account_id: &AccountId) -> bool {
+    policy
+        .roles
+        .iter()
+        .any(|role| policy.is_group_member(&role.name, account_id))
+}
+
+impl Contract {
+    /// Records when given member joined given role.
+    pub(crate) fn internal_record_joined(&mut self, member_id: &AccountId, role: &str) {
+        self.member_since.insert(
+            &(role.to_string(), member_id.clone()),
+            &env::block_timestamp(),
+        );
+    }
+
+    /// Counts proposal authored by given account, if it's a member of group roles.
+    pub(crate) fn internal_record_authored(&mut self, policy: &Policy, account_id: &AccountId) {
+        if is_member(policy, account_id) {
+            let mut activity = self.member_activity.get(account_id).unwrap_or_default();
+            activity.proposals_authored += 1;
+            self.member_activity.insert(account_id, &activity);
+        }
+    }
+
+    /// Counts vote of given account, if it's an approval by a member of group roles.
+    pub(crate) fn internal_record_approval(
+        &mut self,
+        policy: &Policy,
+        account_id: &AccountId,
+        vote: &Vote,
+    ) {
+        if vote == &Vote::Approve && is_member(policy, account_id) {
+            let mut activity = self.member_activity.get(account_id).unwrap_or_default();
+            activity.approvals += 1;
+            self.member_activity.insert(account_id, &activity);
+        }
+    }
+
+    /// Archives given member removed from given role, `policy` being the policy before the removal.
+    pub(crate) fn internal_archive_member(
+        &mut self,
+        policy: &Policy,
+        member_id: &AccountId,
+        role: &str,
+    ) {
+        let record = AlumniRecord {
+            account_id: member_id.clone(),
+            role: role.to_string(),
+            roles_held: policy
+                .roles
+                .iter()
+                .filter(|r| policy.is_group_member(&r.name, member_id))
+                .map(|r| r.name.clone())
+                .collect(),
+            joined_at: self
+                .member_since
+                .remove(&(role.to_string(), member_id.clone()))
+                .map(U64),
+            removed_at: U64::from(env::block_timestamp()),
+            activity: self.member_activity.get(member_id).unwrap_or_default(),
+        };
+        let mut indices = self.alumni_by_account.get(member_id).unwrap_or_default();
+        indices.push(self.alumni.len());
+        self.alumni_by_account.insert(member_id, &indices);
+        self.alumni.push(&record);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns records of removed members, in order of removal.
+    pub fn get_alumni(&self, from_index: u64, limit: u64) -> Vec<AlumniRecord> {
+        (from_index..min(self.alumni.len(), from_index + limit))
+            .filter_map(|index| self.alumni.get(index))
+            .collect()
+    }
+
+    /// Returns records of all removals of given account.
+    pub fn get_alumni_records(&self, account_id: AccountId) -> Vec<AlumniRecord> {
+        self.alumni_by_account
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|index| self.alumni.get(index))
+            .collect()
+    }
+
+    /// Returns activity of given member of group roles.
+    pub fn get_member_activity(&self, account_id: AccountId) -> MemberActivity {
+        self.member_activity.get(&account_id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn change_member(context: &mut VMContextBuilder, contract: &mut Contract, kind: ProposalKind) {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_alumni() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.block_timestamp(100).build());
+        change_member(
+            &mut context,
+            &mut contract,
+            ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+        );
+        testing_env!(context.block_timestamp(200).build());
+        change_member(
+            &mut context,
+            &mut contract,
+            ProposalKind::RemoveMemberFromRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+        );
+        // Vote of the removed member is still needed, council had 2 members.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(1, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_alumni_records(accounts(2)),
+            vec![AlumniRecord {
+                account_id: accounts(2),
+                role: "council".to_string(),
+                roles_held: vec!["council".to_string()],
+                joined_at: Some(U64(100)),
+                removed_at: U64(200),
+                activity: MemberActivity {
+                    proposals_authored: 0,
+                    approvals: 1,
+                },
+            }]
+        );
+        assert_eq!(contract.get_alumni(0, 10).len(), 1);
+        assert_eq!(
+            contract.get_member_activity(accounts(1)),
+            MemberActivity {
+                proposals_authored: 2,
+                approvals: 2,
+            }
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Applicants from outside the DAO, e.g. grant applicants: with `applicants` in the policy, anyone can register
+//! into its propose-only role by paying for the storage. Proposals of applicants are rate limited, and wait in
+//! `AwaitingSponsor` until a member of a sponsor role co-signs them with `sponsor_proposal`.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Registration of applicants and sponsorship of their proposals.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Applicants {
+    /// Group role applicants are registered into. Its permissions should only allow adding proposals.
+    pub role: String,
+    /// Roles whose members can sponsor proposals of applicants.
+    pub sponsor_roles: Vec<String>,
+    /// Minimum time between proposals of an applicant, in `period_unit` of the policy.
+    pub cooldown: U64,
+}
+
+/// Returns true if given account is a member of the applicant role and of no other group role.
+pub(crate) fn is_applicant(policy: &Policy, account_id: &AccountId) -> bool {
+    match &policy.applicants {
+        Some(applicants) => policy.roles.iter().all(|role| {
+            policy.is_group_member(&role.name, account_id) == (role.name == applicants.role)
+        }),
+        None => false,
+    }
+}
+
+impl Contract {
+    /// Checks that given proposer can add a proposal, if it's an applicant. Returns true if the proposal
+    /// needs a sponsor.
+    pub(crate) fn internal_rate_limit_applicant(
+        &mut self,
+        policy: &Policy,
+        proposer: &AccountId,
+    ) -> bool {
+        if !is_applicant(policy, proposer) {
+            return false;
+        }
+        assert!(
+            !self.internal_is_rate_limited(policy, proposer),
+            "ERR_APPLICANT_RATE_LIMITED"
+        );
+        self.applicant_last_proposal.insert(proposer, &policy.now());
+        true
+    }
+
+    /// Returns true if given proposer is an applicant whose last proposal was added within the cooldown.
+    pub(crate) fn internal_is_rate_limited(&self, policy: &Policy, proposer: &AccountId) -> bool {
+        if !is_applicant(policy, proposer) {
+            return false;
+        }
+        let cooldown = policy.applicants.as_ref().unwrap().cooldown.0;
+        match self.applicant_last_proposal.get(proposer) {
+            Some(last_proposal_at) => policy.now() < last_proposal_at + cooldown,
+            None => false,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Registers the caller into the applicant role of the policy.
+    /// Storage is paid from storage sponsored for the caller or the attached deposit, see `internal_pay_storage`.
+    #[payable]
+    pub fn register_applicant(&mut self) {
+        self.assert_not_read_only();
+        let mut policy = self.policy.get().unwrap().to_policy();
+        let role = policy
+            .applicants
+            .as_ref()
+            .expect("ERR_NO_APPLICANTS")
+            .role
+            .clone();
+        let account_id = env::predecessor_account_id();
+        assert!(!policy.is_member(&account_id), "ERR_ALREADY_MEMBER");
+        let storage_before = env::storage_usage();
+        policy.add_member_to_role(&role, &account_id);
+        assert!(
+            policy.is_group_member(&role, &account_id),
+            "ERR_INVALID_APPLICANT_ROLE"
+        );
+        self.policy.set(&VersionedPolicy::Current(policy));
+        self.internal_pay_storage(&account_id, storage_before);
+    }
+
+    /// Co-signs given proposal of an applicant, moving it to voting. Caller must be a member of a sponsor role.
+    pub fn sponsor_proposal(&mut self, id: u64) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::AwaitingSponsor,
+            "ERR_PROPOSAL_NOT_AWAITING_SPONSOR"
+        );
+        let policy = self.policy.get().unwrap().to_policy();
+        let sponsor_id = env::predecessor_account_id();
+        assert!(
+            policy
+                .applicants
+                .as_ref()
+                .expect("ERR_NO_APPLICANTS")
+                .sponsor_roles
+                .iter()
+                .any(|role| policy.is_group_member(role, &sponsor_id)),
+            "ERR_NOT_SPONSOR"
+        );
+        proposal.sponsor = Some(sponsor_id);
+        // Voting period starts once the proposal is sponsored.
+        proposal.status = ProposalStatus::InProgress;
+        proposal.submission_time = U64::from(env::block_timestamp());
+        proposal.submission_block = U64::from(env::block_height());
+        self.internal_start_proposal(&policy, &mut proposal, id);
+        self.internal_record_transition(id, &proposal.status);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Config, RoleKind, RolePermission};
+
+    use super::*;
+
+    fn contract_with_applicant(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles.push(RolePermission {
+            name: "applicants".to_string(),
+            kind: RoleKind::Group(Default::default()),
+            permissions: vec!["transfer:AddProposal".to_string()]
+                .into_iter()
+                .collect(),
+            vote_policy: Default::default(),
+        });
+        policy_mut.applicants = Some(Applicants {
+            role: "applicants".to_string(),
+            sponsor_roles: vec!["council".to_string()],
+            cooldown: U64(100),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.register_applicant();
+        contract
+    }
+
+    fn apply(context: &mut VMContextBuilder, contract: &mut Contract) -> u64 {
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.add_proposal(ProposalInput {
+            description: "grant".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        })
+    }
+
+    #[test]
+    fn test_sponsor_applicant_proposal() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_applicant(&mut context);
+        let id = apply(&mut context, &mut contract);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::AwaitingSponsor
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        contract.sponsor_proposal(id);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.sponsor, Some(accounts(1)));
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+
+        testing_env!(context.block_timestamp(100).build());
+        apply(&mut context, &mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_APPLICANT_RATE_LIMITED")]
+    fn test_applicant_rate_limited() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_applicant(&mut context);
+        apply(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(99).build());
+        apply(&mut context, &mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_SPONSOR")]
+    fn test_applicant_cannot_sponsor() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_applicant(&mut context);
+        let id = apply(&mut context, &mut contract);
+        contract.sponsor_proposal(id);
+    }
+}
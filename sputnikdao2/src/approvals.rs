@@ -0,0 +1,256 @@
+//! Proposals pre-approved by members off-chain: ed25519 signatures of registered signing keys over the proposal
+//! are counted as initial approve votes, so a proposal everyone already agreed on passes in a single transaction.
+
+use std::collections::HashSet;
+
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, CryptoHash, PublicKey};
+
+use crate::bounties::filter_bounty_reviewer_role;
+use crate::commit_reveal::filter_commit_reveal_roles;
+use crate::emergency::verify_ed25519;
+use crate::policy::UserInfo;
+use crate::reviewers::filter_reviewer_role;
+use crate::*;
+
+/// Signature of the approval message of a proposal by the signing key of a member.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalApproval {
+    pub account_id: AccountId,
+    pub signature: Base64VecU8,
+}
+
+/// Message members sign to approve proposal with given id, description and kind:
+/// sha256 of borsh serialized account of this DAO, id, description and kind.
+fn approval_message(proposal_id: u64, description: &str, kind: &ProposalKind) -> CryptoHash {
+    env::sha256_array(
+        &(env::current_account_id(), proposal_id, description, kind)
+            .try_to_vec()
+            .unwrap(),
+    )
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the key the caller signs proposal approvals with. Caller must be a member of a group role.
+    /// Storage is paid from storage sponsored for the caller or the attached deposit, see `internal_pay_storage`.
+    #[payable]
+    pub fn set_signing_key(&mut self, public_key: PublicKey) {
+        self.assert_not_read_only();
+        let account_id = env::predecessor_account_id();
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(policy.is_member(&account_id), "ERR_NOT_MEMBER");
+        let storage_before = env::storage_usage();
+        self.signing_keys.insert(&account_id, &public_key);
+        self.internal_pay_storage(&account_id, storage_before);
+    }
+
+    /// Returns the key given account signs proposal approvals with.
+    pub fn get_signing_key(&self, account_id: AccountId) -> Option<PublicKey> {
+        self.signing_keys.get(&account_id)
+    }
+
+    /// Returns the message to sign for approving given proposal, if it's added next.
+    pub fn get_approval_message(&self, proposal: ProposalInput) -> Base58CryptoHash {
+        approval_message(self.last_proposal_id, &proposal.description, &proposal.kind).into()
+    }
+
+    /// Add proposal, counting given signatures of members as their approve votes.
+    /// Each signer must be allowed to vote to approve this kind of proposal.
+    #[payable]
+    pub fn add_proposal_with_approvals(
+        &mut self,
+        proposal: ProposalInput,
+        approvals: Vec<ProposalApproval>,
+    ) -> u64 {
+        self.assert_not_read_only();
+        let expected_id = self.last_proposal_id;
+        let message = approval_message(expected_id, &proposal.description, &proposal.kind);
+        let id = self.add_proposal(proposal);
+        if id != expected_id {
+            // Resubmission with the same idempotency key.
+            return id;
+        }
+        let mut proposal: Proposal = self.proposals.get(&id).unwrap().into();
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::InProgress,
+            "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
+        );
+        let policy = self.policy.get().unwrap().to_policy();
+        let mut voted_roles = HashSet::new();
+        for approval in approvals {
+            let public_key = self
+                .signing_keys
+                .get(&approval.account_id)
+                .expect("ERR_NO_SIGNING_KEY");
+            assert!(
+                verify_ed25519(&public_key, &message, &approval.signature.0),
+                "ERR_INVALID_SIGNATURE"
+            );
+            let user = UserInfo {
+                amount: self.internal_vote_weight(id, &proposal, &approval.account_id),
+                account_id: approval.account_id.clone(),
+            };
+            let (mut roles, allowed) =
+                policy.can_execute_action(user, &proposal.kind, &Action::VoteApprove);
+            assert!(allowed, "ERR_PERMISSION_DENIED");
+            filter_reviewer_role(&policy, &proposal, &approval.account_id, &mut roles);
+            filter_bounty_reviewer_role(&self.bounty_config, &proposal, &mut roles);
+            filter_commit_reveal_roles(&policy, &mut roles);
+            let counts_before = proposal.vote_counts.clone();
+            proposal.update_votes(
+                &approval.account_id,
+                &roles,
+                Vote::Approve,
+                &policy,
+                self.internal_vote_weight(id, &proposal, &approval.account_id),
+                &self.internal_resolve_delegators(id, &proposal, &approval.account_id),
+            );
+            self.internal_record_approval(&policy, &approval.account_id, &Vote::Approve);
+            self.internal_record_delegate_vote(&approval.account_id, id, Vote::Approve);
+            self.internal_log_vote(
+                id,
+                &proposal,
+                &approval.account_id,
+                &roles,
+                &counts_before,
+                None,
+            );
+            voted_roles.extend(roles);
+        }
+        proposal.status = policy.proposal_status(
+            &proposal,
+            voted_roles.into_iter().collect(),
+            self.internal_total_weight(&proposal),
+        );
+        if proposal.status == ProposalStatus::Approved {
+            self.internal_approve_proposal(&policy, &mut proposal, id);
+        }
+        let closed = proposal.status != ProposalStatus::InProgress;
+        if closed {
+            self.internal_record_transition(id, &proposal.status);
+        }
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        if closed {
+            self.internal_close_proposal();
+        }
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk::CurveType;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    fn input() -> ProposalInput {
+        ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(4),
+                amount: U128(to_yocto("1")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        }
+    }
+
+    /// Council of three with registered signing keys.
+    fn contract_with_keys(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        for seed in 1..4 {
+            testing_env!(context
+                .predecessor_account_id(accounts(seed as usize))
+                .attached_deposit(to_yocto("1"))
+                .build());
+            let mut bytes = vec![CurveType::ED25519 as u8];
+            bytes.extend_from_slice(keypair(seed).public.as_bytes());
+            contract.set_signing_key(PublicKey::try_from(bytes).unwrap());
+        }
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract
+    }
+
+    fn approve(contract: &Contract, seed: u8) -> ProposalApproval {
+        let message: CryptoHash = contract.get_approval_message(input()).into();
+        ProposalApproval {
+            account_id: accounts(seed as usize),
+            signature: Base64VecU8(keypair(seed).sign(&message).to_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_add_proposal_with_approvals() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_keys(&mut context);
+        let approvals = vec![approve(&contract, 1), approve(&contract, 2)];
+        let id = contract.add_proposal_with_approvals(input(), approvals);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.votes.len(), 2);
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+
+        // Single approval doesn't reach 1/2 of the council.
+        let approvals = vec![approve(&contract, 3)];
+        let id = contract.add_proposal_with_approvals(input(), approvals);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_MEMBER")]
+    fn test_set_signing_key_not_member() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_keys(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut bytes = vec![CurveType::ED25519 as u8];
+        bytes.extend_from_slice(keypair(4).public.as_bytes());
+        contract.set_signing_key(PublicKey::try_from(bytes).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_SIGNATURE")]
+    fn test_add_proposal_with_stale_approvals() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_keys(&mut context);
+        let approvals = vec![approve(&contract, 1)];
+        contract.add_proposal(input());
+        // Signed for proposal 0, can't be used for the next one.
+        contract.add_proposal_with_approvals(input(), approvals);
+    }
+}
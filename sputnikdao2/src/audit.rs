@@ -0,0 +1,228 @@
+//! Self-audit of the contract state: recomputes accounting invariants over a page of records and reports
+//! mismatches, so operators can detect drift before it leads to loss of funds.
+
+use std::cmp::min;
+
+use near_sdk::json_types::U128;
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Record whose stored state doesn't match the recomputed one.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuditMismatch {
+    /// Id of the proposal or bounty, None for mismatches of the contract totals.
+    pub id: Option<u64>,
+    pub detail: String,
+    pub expected: U128,
+    pub actual: U128,
+}
+
+/// Result of auditing a page of records of one section.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuditReport {
+    pub section: String,
+    /// Number of records checked.
+    pub checked: u64,
+    pub mismatches: Vec<AuditMismatch>,
+}
+
+impl Contract {
+    /// Bonds of closed proposals must be settled, and locked bonds of all proposals can't exceed `locked_amount`,
+    /// which also holds bounty claim bonds and sponsored storage.
+    fn internal_audit_bonds(&self, from_index: u64, limit: u64) -> AuditReport {
+        let to_index = min(self.last_proposal_id, from_index + limit);
+        let mut mismatches = vec![];
+        let mut locked_bonds = 0;
+        for id in from_index..to_index {
+            let proposal: Proposal = match self.proposals.get(&id) {
+                Some(proposal) => proposal.into(),
+                None => continue,
+            };
+            if proposal.bond_state != BondState::Locked {
+                continue;
+            }
+            locked_bonds += proposal.bond.0;
+            if matches!(
+                proposal.status,
+                ProposalStatus::Rejected
+                    | ProposalStatus::Expired
+                    | ProposalStatus::Removed
+                    | ProposalStatus::Revoked
+                    | ProposalStatus::Vetoed
+            ) {
+                mismatches.push(AuditMismatch {
+                    id: Some(id),
+                    detail: "ERR_UNSETTLED_BOND".to_string(),
+                    expected: U128(0),
+                    actual: proposal.bond,
+                });
+            }
+        }
+        // Totals can only be compared once the page covers all proposals.
+        if from_index == 0 && to_index == self.last_proposal_id && locked_bonds > self.locked_amount
+        {
+            mismatches.push(AuditMismatch {
+                id: None,
+                detail: "ERR_LOCKED_AMOUNT".to_string(),
+                expected: U128(locked_bonds),
+                actual: U128(self.locked_amount),
+            });
+        }
+        AuditReport {
+            section: "bonds".to_string(),
+            checked: to_index.saturating_sub(from_index),
+            mismatches,
+        }
+    }
+
+    /// Counts of role weighted votes can't exceed the number of stored votes of the same kind.
+    fn internal_audit_votes(&self, from_index: u64, limit: u64) -> AuditReport {
+        let policy = self.policy.get().unwrap().to_policy();
+        let to_index = min(self.last_proposal_id, from_index + limit);
+        let mut mismatches = vec![];
+        for id in from_index..to_index {
+            let proposal: Proposal = match self.proposals.get(&id) {
+                Some(proposal) => proposal.into(),
+                None => continue,
+            };
+            let label = proposal.kind.to_policy_label().to_string();
+            let mut votes = [0u128; 4];
+            for vote in proposal.votes.values() {
+                votes[vote.clone() as usize] += 1;
+            }
+            for (role, counts) in proposal.vote_counts.iter() {
+                if !policy.roles.iter().any(|r| &r.name == role)
+                    || policy.is_token_weighted(role, &label)
+                {
+                    continue;
+                }
+                for (count, stored) in counts.iter().zip(votes.iter()) {
+                    if count > stored {
+                        mismatches.push(AuditMismatch {
+                            id: Some(id),
+                            detail: format!("ERR_VOTE_COUNT:{}", role),
+                            expected: U128(*stored),
+                            actual: U128(*count),
+                        });
+                    }
+                }
+            }
+        }
+        AuditReport {
+            section: "votes".to_string(),
+            checked: to_index.saturating_sub(from_index),
+            mismatches,
+        }
+    }
+
+    /// Claim counts of bounties can't exceed the number of times bounties can be claimed.
+    fn internal_audit_claims(&self, from_index: u64, limit: u64) -> AuditReport {
+        let to_index = min(self.last_bounty_id, from_index + limit);
+        let mut mismatches = vec![];
+        for id in from_index..to_index {
+            let bounty: Bounty = match self.bounties.get(&id) {
+                Some(bounty) => bounty.into(),
+                None => continue,
+            };
+            let claims_count = self.bounty_claims_count.get(&id).unwrap_or_default();
+            if claims_count > bounty.times {
+                mismatches.push(AuditMismatch {
+                    id: Some(id),
+                    detail: "ERR_CLAIMS_COUNT".to_string(),
+                    expected: U128(bounty.times as u128),
+                    actual: U128(claims_count as u128),
+                });
+            }
+        }
+        AuditReport {
+            section: "claims".to_string(),
+            checked: to_index.saturating_sub(from_index),
+            mismatches,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Recomputes invariants of given section over a page of its records and reports mismatches.
+    /// Sections: "bonds" and "votes" page over proposals, "claims" pages over bounties.
+    pub fn audit_state(&self, section: String, from_index: u64, limit: u64) -> AuditReport {
+        match section.as_str() {
+            "bonds" => self.internal_audit_bonds(from_index, limit),
+            "votes" => self.internal_audit_votes(from_index, limit),
+            "claims" => self.internal_audit_claims(from_index, limit),
+            _ => env::panic_str("ERR_UNKNOWN_AUDIT_SECTION"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_audit_state() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteReject, None);
+        for section in ["bonds", "votes", "claims"] {
+            assert!(contract
+                .audit_state(section.to_string(), 0, 10)
+                .mismatches
+                .is_empty());
+        }
+
+        // Drift in the accounting is reported.
+        contract.locked_amount = 0;
+        let mut proposal: Proposal = contract.proposals.get(&id).unwrap().into();
+        proposal
+            .vote_counts
+            .insert("council".to_string(), [0, 2, 0, 0]);
+        contract
+            .proposals
+            .insert(&id, &VersionedProposal::V2(proposal));
+        assert_eq!(
+            contract.audit_state("bonds".to_string(), 0, 10).mismatches,
+            vec![AuditMismatch {
+                id: None,
+                detail: "ERR_LOCKED_AMOUNT".to_string(),
+                expected: U128(to_yocto("1")),
+                actual: U128(0),
+            }]
+        );
+        assert_eq!(
+            contract.audit_state("votes".to_string(), 0, 10).mismatches,
+            vec![AuditMismatch {
+                id: Some(id),
+                detail: "ERR_VOTE_COUNT:council".to_string(),
+                expected: U128(1),
+                actual: U128(2),
+            }]
+        );
+    }
+}
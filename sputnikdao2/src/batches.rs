@@ -0,0 +1,300 @@
+//! Batches of approved `Transfer` proposals with the same `batch` key, for payroll: the first transfer
+//! of a batch that is ready to execute opens the `transfer_batch_window` of the policy, and once it closes
+//! `execute_transfer_batch` executes all of them together, with one promise per token (or per receiver of $NEAR).
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::json;
+use near_sdk::{near_bindgen, AccountId, Gas, Promise, PromiseResult};
+
+use crate::types::{convert_old_to_new_token, GAS_FOR_FT_TRANSFER, ONE_YOCTO_NEAR};
+use crate::*;
+
+/// Gas for settling each proposal of a batch in the callback.
+const GAS_FOR_BATCH_CALLBACK: Gas = Gas(5_000_000_000_000);
+
+/// Approved transfers waiting for the batch window to close.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferBatch {
+    /// When the batch can be executed, in `period_unit` of the policy.
+    pub closes_at: U64,
+    pub proposal_ids: Vec<u64>,
+}
+
+/// Returns batch key of given proposal, if it's a batched transfer.
+fn batch_key(proposal: &Proposal) -> Option<&String> {
+    match &proposal.kind {
+        ProposalKind::Transfer { batch, .. } => batch.as_ref(),
+        _ => None,
+    }
+}
+
+impl Contract {
+    /// Queues transfer that is ready to execute in its batch, opening the batch window if it's the first one.
+    /// Returns false if the transfer is not batched.
+    pub(crate) fn internal_batch_transfer(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) -> bool {
+        let (window, key) = match (&policy.transfer_batch_window, batch_key(proposal)) {
+            (Some(window), Some(key)) => (window.0, key.clone()),
+            _ => return false,
+        };
+        let mut batch = self.transfer_batches.get(&key).unwrap_or(TransferBatch {
+            closes_at: U64(policy.now() + window),
+            proposal_ids: vec![],
+        });
+        batch.proposal_ids.push(proposal_id);
+        proposal.status = ProposalStatus::Queued;
+        proposal.execution_after = Some(batch.closes_at);
+        self.transfer_batches.insert(&key, &batch);
+        true
+    }
+
+    /// Returns true if given proposal is queued in a transfer batch, so it's only executed with the batch.
+    pub(crate) fn internal_is_batched(&self, proposal: &Proposal, proposal_id: u64) -> bool {
+        match batch_key(proposal).and_then(|key| self.transfer_batches.get(key)) {
+            Some(batch) => batch.proposal_ids.contains(&proposal_id),
+            None => false,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Executes transfers of given batch once its window closed. Transfers of the same token are sent
+    /// in a single promise, as are $NEAR transfers to the same receiver. Callable by anyone.
+    pub fn execute_transfer_batch(&mut self, batch: String) {
+        self.assert_not_read_only();
+        self.assert_not_paused();
+        let policy = self.policy.get().unwrap().to_policy();
+        let transfer_batch = self.transfer_batches.get(&batch).expect("ERR_NO_BATCH");
+        assert!(
+            policy.now() >= transfer_batch.closes_at.0,
+            "ERR_BATCH_WINDOW_OPEN"
+        );
+        self.transfer_batches.remove(&batch);
+        // Proposals grouped by the receiver of their promise: the token, or the receiver of $NEAR.
+        let mut groups: Vec<(AccountId, Vec<(u64, Proposal)>)> = vec![];
+        for id in transfer_batch.proposal_ids {
+            let mut proposal: Proposal = match self.proposals.get(&id) {
+                Some(proposal) => proposal.into(),
+                None => continue,
+            };
+            // Revoked while in the batch.
+            if proposal.status != ProposalStatus::Queued {
+                continue;
+            }
+            if !self.internal_debit_committee_budget(&policy, &mut proposal) {
+                proposal.status = ProposalStatus::Failed;
+                self.internal_record_transition(id, &proposal.status);
+                self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+                continue;
+            }
+            let target = match &proposal.kind {
+                ProposalKind::Transfer {
+                    token_id,
+                    receiver_id,
+                    ..
+                } => convert_old_to_new_token(token_id).unwrap_or_else(|| receiver_id.clone()),
+                _ => continue,
+            };
+            match groups
+                .iter_mut()
+                .find(|(account_id, _)| account_id == &target)
+            {
+                Some((_, proposals)) => proposals.push((id, proposal)),
+                None => groups.push((target, vec![(id, proposal)])),
+            }
+        }
+        for (target, proposals) in groups {
+            let mut promise = Promise::new(target);
+            let mut proposal_ids = vec![];
+            for (id, mut proposal) in proposals {
+                if let ProposalKind::Transfer {
+                    token_id,
+                    receiver_id,
+                    amount,
+                    msg,
+                    ..
+                } = &proposal.kind
+                {
+                    promise = match (convert_old_to_new_token(token_id), msg) {
+                        (None, _) => promise.transfer(amount.0),
+                        (Some(_), None) => promise.function_call(
+                            "ft_transfer".to_string(),
+                            json!({
+                                "receiver_id": receiver_id,
+                                "amount": amount,
+                                "memo": proposal.description,
+                            })
+                            .to_string()
+                            .into_bytes(),
+                            ONE_YOCTO_NEAR,
+                            GAS_FOR_FT_TRANSFER,
+                        ),
+                        (Some(_), Some(msg)) => promise.function_call(
+                            "ft_transfer_call".to_string(),
+                            json!({
+                                "receiver_id": receiver_id,
+                                "amount": amount,
+                                "memo": proposal.description,
+                                "msg": msg,
+                            })
+                            .to_string()
+                            .into_bytes(),
+                            ONE_YOCTO_NEAR,
+                            GAS_FOR_FT_TRANSFER,
+                        ),
+                    };
+                }
+                proposal.status = ProposalStatus::Approved;
+                proposal.execution_started_at = Some(U64::from(env::block_timestamp()));
+                proposal.reconciled = false;
+                self.internal_query_payout_balance(id, &mut proposal);
+                self.internal_record_transition(id, &proposal.status);
+                self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+                proposal_ids.push(id);
+            }
+            let gas = Gas(GAS_FOR_BATCH_CALLBACK.0 * proposal_ids.len() as u64);
+            promise.then(ext_self::on_transfer_batch(
+                proposal_ids,
+                env::current_account_id(),
+                0,
+                gas,
+            ));
+        }
+    }
+
+    /// Returns transfers queued in given batch.
+    pub fn get_transfer_batch(&self, batch: String) -> Option<TransferBatch> {
+        self.transfer_batches.get(&batch)
+    }
+
+    /// Settles proposals of a batch sent in one promise: all succeeded or all failed.
+    #[private]
+    pub fn on_transfer_batch(&mut self, proposal_ids: Vec<u64>) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        let success = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        for id in proposal_ids {
+            let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+            if proposal.reconciled {
+                // Already settled by `reconcile_proposal`.
+                continue;
+            }
+            if success {
+                self.internal_callback_proposal_success(id, &mut proposal);
+            } else {
+                self.internal_callback_proposal_fail(&mut proposal);
+            }
+            self.internal_record_transition(id, &proposal.status);
+            self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn add_payroll(context: &mut VMContextBuilder, contract: &mut Contract, amount: &str) -> u64 {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "payroll".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: accounts(4).to_string(),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto(amount)),
+                msg: None,
+                category: None,
+                batch: Some("payroll".to_string()),
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        id
+    }
+
+    #[test]
+    fn test_transfer_batch() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().transfer_batch_window = Some(U64(1_000));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let first = add_payroll(&mut context, &mut contract, "1");
+        let second = add_payroll(&mut context, &mut contract, "2");
+        assert_eq!(
+            contract.get_proposal(first).proposal.status,
+            ProposalStatus::Queued
+        );
+        assert_eq!(
+            contract.get_transfer_batch("payroll".to_string()),
+            Some(TransferBatch {
+                closes_at: U64(1_000),
+                proposal_ids: vec![first, second],
+            })
+        );
+
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.execute_transfer_batch("payroll".to_string());
+        // Both transfers are sent to the token in a single receipt.
+        let receipts = get_created_receipts();
+        assert_eq!(
+            receipts
+                .iter()
+                .filter(|receipt| receipt.receiver_id == accounts(4)
+                    && receipt.actions.iter().any(|action| matches!(
+                        action,
+                        near_sdk::mock::VmAction::FunctionCall { function_name, .. }
+                            if function_name == "ft_transfer"
+                    )))
+                .count(),
+            1
+        );
+        assert_eq!(contract.get_transfer_batch("payroll".to_string()), None);
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_transfer_batch(vec![first, second]);
+        for id in [first, second] {
+            let proposal = contract.get_proposal(id).proposal;
+            assert_eq!(proposal.status, ProposalStatus::Approved);
+            assert!(proposal.executed_at.is_some());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BATCH_WINDOW_OPEN")]
+    fn test_transfer_batch_window_open() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().transfer_batch_window = Some(U64(1_000));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        add_payroll(&mut context, &mut contract, "1");
+        contract.execute_transfer_batch("payroll".to_string());
+    }
+}
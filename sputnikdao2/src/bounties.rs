@@ -1,11 +1,23 @@
+use near_contract_standards::storage_management::StorageBalance;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, Promise, PromiseOrValue};
+use near_sdk::serde_json::{self, json};
+use near_sdk::{env, near_bindgen, AccountId, Gas, Promise, PromiseOrValue};
 
-use crate::types::{convert_old_to_new_token, OldAccountId};
+use crate::legacy::{legacy_bounty_claimers, stored_policy, BountyV1};
+use crate::policy::UserInfo;
+use crate::types::{
+    convert_old_to_new_token, require, OldAccountId, GAS_FOR_FT_TRANSFER, ONE_YOCTO_NEAR,
+};
 use crate::*;
 
+const GAS_FOR_STORAGE_BALANCE_OF: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_ON_BOUNTY_STORAGE_CHECK: Gas = Gas(5_000_000_000_000);
+
+/// Permission of roles whose members can approve `BountyDone` proposals without a vote, see `bounty_review`.
+const BOUNTY_REVIEW_PERMISSION: &str = "bounty_review";
+
 /// Information recorded about claim of the bounty by given user.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -18,6 +30,50 @@ pub struct BountyClaim {
     deadline: U64,
     /// Completed?
     completed: bool,
+    /// Bond locked by the claimer.
+    bond: U128,
+    /// Number of milestones of the bounty paid out to the claimer.
+    #[serde(default)]
+    milestones_done: u32,
+    /// Total time the deadline was extended by, see `bounty_request_extension`.
+    #[serde(default)]
+    extended: Option<U64>,
+    /// Extension requested beyond `bounty_auto_extension` of the policy, waiting for approval of a bounty reviewer.
+    #[serde(default)]
+    extension_request: Option<U64>,
+}
+
+impl BountyClaim {
+    /// Extends the deadline of this claim by given time.
+    fn extend(&mut self, extra_time: u64) {
+        self.deadline = U64(self.deadline.0 + extra_time);
+        self.extended = Some(U64(
+            self.extended.map_or(0, |extended| extended.0) + extra_time
+        ));
+        self.extension_request = None;
+    }
+}
+
+/// Milestone of a bounty, paid out by its own `BountyDone` proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyMilestone {
+    pub description: String,
+    /// Amount paid out once the milestone is done.
+    pub amount: U128,
+    /// Deadline of the milestone from the start of the claim.
+    pub deadline: U64,
+}
+
+/// Share of a bounty payout going to a co-contributor of the claimer, who must hold a claim of the same bounty.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyShare {
+    pub account_id: AccountId,
+    /// Percentage of the payout. The claimer gets what is left after all shares.
+    pub percent: u32,
 }
 
 /// Bounty information.
@@ -33,22 +89,161 @@ pub struct Bounty {
     /// Amount to be paid out.
     pub amount: U128,
     /// How many times this bounty can be done.
+    /// If zero, `default_times` of the bounty config is used once the bounty is added.
     pub times: u32,
     /// Max deadline from claim that can be spend on this bounty.
     pub max_deadline: U64,
+    /// Name of the budget that funds payouts of this bounty.
+    /// If set, payouts are debited from it and fail while it's exhausted.
+    #[serde(default)]
+    pub budget: Option<String>,
+    /// If true, each account can claim this bounty only once, even if the claim was given up or expired.
+    #[serde(default)]
+    pub one_claim_per_account: bool,
+    /// Skill tags claimers should have, see `get_matching_claimers`.
+    #[serde(default)]
+    pub required_tags: Vec<String>,
+    /// Milestones paid out one by one, in order. Their amounts must add up to `amount`.
+    /// If empty, the bounty is paid out at once.
+    #[serde(default)]
+    pub milestones: Vec<BountyMilestone>,
+    /// If true, accounts apply with `bounty_apply` and can only claim the bounty once assigned with `bounty_assign`.
+    #[serde(default)]
+    pub requires_assignment: bool,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
-#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
+impl Bounty {
+    /// Returns amount paid out for given milestone, or for the whole bounty if None.
+    pub(crate) fn payout_amount(&self, milestone: Option<u32>) -> Balance {
+        match milestone {
+            Some(index) => self.milestones[index as usize].amount.0,
+            None => self.amount.0,
+        }
+    }
+
+    /// Returns true if the bounty is done with given milestone.
+    pub(crate) fn is_last_milestone(&self, milestone: Option<u32>) -> bool {
+        match milestone {
+            Some(index) => index as usize + 1 == self.milestones.len(),
+            None => true,
+        }
+    }
+}
+
+/// Checks that milestones of given bounty add up to its amount and fit in its max deadline.
+pub(crate) fn check_milestones(bounty: &Bounty) -> Result<(), &'static str> {
+    if bounty.milestones.is_empty() {
+        return Ok(());
+    }
+    require(
+        bounty
+            .milestones
+            .iter()
+            .map(|milestone| milestone.amount.0)
+            .sum::<Balance>()
+            == bounty.amount.0
+            && bounty
+                .milestones
+                .iter()
+                .all(|milestone| milestone.deadline.0 <= bounty.max_deadline.0),
+        "ERR_INVALID_MILESTONES",
+    )
+}
+
+/// Checks that given milestone is one of the bounty, if it has milestones, or None otherwise.
+pub(crate) fn check_milestone(bounty: &Bounty, milestone: Option<u32>) -> Result<(), &'static str> {
+    let valid = match milestone {
+        Some(index) => (index as usize) < bounty.milestones.len(),
+        None => bounty.milestones.is_empty(),
+    };
+    require(valid, "ERR_INVALID_MILESTONE")
+}
+
+/// Returns true if given user is a member of a role with `bounty_review` permission, whose term hasn't ended.
+pub(crate) fn is_bounty_reviewer(policy: &Policy, user: &UserInfo) -> bool {
+    policy.roles.iter().any(|role| {
+        role.kind.match_user(user)
+            && !policy.is_expired_member(&role.name, &user.account_id)
+            && role.permissions.contains(BOUNTY_REVIEW_PERMISSION)
+    })
+}
+
+/// Checks that given shares go to distinct co-contributors of the claimer and leave the claimer a part of the payout.
+pub(crate) fn check_shares(
+    receiver_id: &AccountId,
+    shares: &[BountyShare],
+) -> Result<(), &'static str> {
+    require(
+        shares.iter().enumerate().all(|(i, share)| {
+            share.percent > 0
+                && &share.account_id != receiver_id
+                && shares[..i]
+                    .iter()
+                    .all(|other| other.account_id != share.account_id)
+        }) && shares.iter().map(|share| share.percent).sum::<u32>() < 100,
+        "ERR_INVALID_BOUNTY_SHARES",
+    )
+}
+
+/// Splits given payout between co-contributors by their shares, the rest going to the receiver, which comes last.
+pub(crate) fn split_bounty_payout(
+    receiver_id: &AccountId,
+    amount: Balance,
+    shares: &[BountyShare],
+) -> Vec<(AccountId, Balance)> {
+    let mut payouts: Vec<(AccountId, Balance)> = shares
+        .iter()
+        .map(|share| {
+            (
+                share.account_id.clone(),
+                amount * share.percent as Balance / 100,
+            )
+        })
+        .collect();
+    let shared: Balance = payouts.iter().map(|(_, amount)| amount).sum();
+    payouts.push((receiver_id.clone(), amount - shared));
+    payouts
+}
+
+/// Returns given part of the bond of a removed claim to the claimer.
+fn refund_claim_bond(claimer_id: &AccountId, refund: Balance) -> PromiseOrValue<()> {
+    if refund > 0 {
+        Promise::new(claimer_id.clone()).transfer(refund).into()
+    } else {
+        PromiseOrValue::Value(())
+    }
+}
+
+/// Parameters of the bounty subsystem, set by `ChangeBountyConfig` proposals separately from the policy.
+/// Once set, they override `bounty_bond` and `bounty_forgiveness_period` of the policy.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
+pub struct BountyConfig {
+    /// Bond attached to claim a bounty.
+    pub bond: U128,
+    /// Period in which giving up on a bounty returns the bond.
+    pub forgiveness_period: U64,
+    /// Role whose members decide `BountyDone` proposals. If None, any role with permission votes.
+    pub reviewer_role: Option<String>,
+    /// Number of times bounties added with zero `times` can be done.
+    pub default_times: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedBounty {
-    Default(Bounty),
+    /// Stored before versioning, see `legacy`.
+    Default(BountyV1),
+    V2(Bounty),
 }
 
 impl From<VersionedBounty> for Bounty {
     fn from(v: VersionedBounty) -> Self {
         match v {
-            VersionedBounty::Default(b) => b,
+            VersionedBounty::Default(b) => b.into(),
+            VersionedBounty::V2(b) => b,
         }
     }
 }
@@ -58,97 +253,320 @@ impl Contract {
     /// Must not fail.
     pub(crate) fn internal_add_bounty(&mut self, bounty: &Bounty) -> u64 {
         let id = self.last_bounty_id;
-        self.bounties
-            .insert(&id, &VersionedBounty::Default(bounty.clone()));
+        let mut bounty = bounty.clone();
+        if let (0, Some(bounty_config)) = (bounty.times, &self.bounty_config) {
+            bounty.times = bounty_config.default_times;
+        }
+        self.internal_index_bounty_keywords(id, &bounty);
+        self.bounties.insert(&id, &VersionedBounty::V2(bounty));
         self.last_bounty_id += 1;
         id
     }
 
-    /// This must be called when proposal to payout bounty has been voted either successfully or not.
+    /// Debits the payout of given bounty from the budget that funds it.
+    /// Returns false if the budget can't cover the payout. Bounties without budget always succeed.
+    pub(crate) fn internal_debit_bounty_budget(&mut self, id: u64, milestone: Option<u32>) -> bool {
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        match &bounty.budget {
+            Some(name) => {
+                self.internal_debit_budget(name, &bounty.token, bounty.payout_amount(milestone))
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the payout of given bounty back to the budget that funds it.
+    pub(crate) fn internal_credit_bounty_budget(&mut self, id: u64, milestone: Option<u32>) {
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        if let Some(name) = &bounty.budget {
+            self.internal_credit_budget(name, bounty.payout_amount(milestone));
+        }
+    }
+
+    /// Pays out given bounty, or given milestone of it, to the receiver and its co-contributors by `shares`.
+    /// The claim is removed once the payout of the whole bounty or of its last milestone succeeds.
     pub(crate) fn internal_execute_bounty_payout(
         &mut self,
         id: u64,
         receiver_id: &AccountId,
-        success: bool,
+        milestone: Option<u32>,
+        shares: &[BountyShare],
     ) -> PromiseOrValue<()> {
         let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
-        self.internal_remove_claim(id, receiver_id);
-        if success {
-            self.internal_payout(
-                &convert_old_to_new_token(&bounty.token),
-                receiver_id,
-                bounty.amount.0,
-                format!("Bounty {} payout", id),
-                None,
-            )
-        } else {
-            PromiseOrValue::Value(())
+        let memo = match milestone {
+            Some(index) => format!("Bounty {} milestone {} payout", id, index),
+            None => format!("Bounty {} payout", id),
+        };
+        let mut payouts = split_bounty_payout(receiver_id, bounty.payout_amount(milestone), shares);
+        payouts.retain(|(_, amount)| *amount > 0);
+        match convert_old_to_new_token(&bounty.token) {
+            // Receivers must be registered in the token, otherwise the transfer would fail after the DAO paid for it.
+            Some(token_id) => payouts
+                .iter()
+                .map(|(account_id, _)| {
+                    Promise::new(token_id.clone()).function_call(
+                        "storage_balance_of".to_string(),
+                        json!({ "account_id": account_id }).to_string().into_bytes(),
+                        0,
+                        GAS_FOR_STORAGE_BALANCE_OF,
+                    )
+                })
+                .reduce(|checks, check| checks.and(check))
+                .unwrap()
+                .then(ext_self::on_bounty_storage_check(
+                    token_id,
+                    payouts
+                        .iter()
+                        .map(|(account_id, amount)| (account_id.clone(), U128(*amount)))
+                        .collect(),
+                    memo,
+                    env::current_account_id(),
+                    0,
+                    Gas(GAS_FOR_ON_BOUNTY_STORAGE_CHECK.0
+                        + GAS_FOR_FT_TRANSFER.0 * payouts.len() as u64),
+                ))
+                .into(),
+            // $NEAR goes in a promise per account. Co-contributors hold claims, so their accounts exist
+            // and transfers to them can't fail.
+            None => {
+                let (receiver_id, amount) = payouts.pop().unwrap();
+                for (account_id, amount) in payouts {
+                    Promise::new(account_id).transfer(amount);
+                }
+                Promise::new(receiver_id).transfer(amount).into()
+            }
         }
     }
 
-    fn internal_find_claim(&self, bounty_id: u64, claims: &[BountyClaim]) -> Option<usize> {
-        for i in 0..claims.len() {
-            if claims[i].bounty_id == bounty_id {
-                return Some(i);
+    /// Records payout of the next milestone of the claim of `claimer_id`, so the claimer can report the following one.
+    /// Claims of co-contributors of the payout are reopened too.
+    pub(crate) fn internal_complete_milestone(
+        &mut self,
+        bounty_id: u64,
+        claimer_id: &AccountId,
+        shares: &[BountyShare],
+    ) {
+        let (mut claims, claim_idx) = self.internal_get_claims(bounty_id, claimer_id);
+        claims[claim_idx].milestones_done += 1;
+        claims[claim_idx].completed = false;
+        self.bounty_claimers.insert(claimer_id, &claims);
+        self.internal_set_shares_completed(bounty_id, shares, false);
+    }
+
+    /// Marks claims of given co-contributors of a payout as completed or not.
+    fn internal_set_shares_completed(
+        &mut self,
+        bounty_id: u64,
+        shares: &[BountyShare],
+        completed: bool,
+    ) {
+        for share in shares {
+            let (mut claims, claim_idx) = self.internal_get_claims(bounty_id, &share.account_id);
+            if completed {
+                assert!(!claims[claim_idx].completed, "ERR_BOUNTY_CLAIM_COMPLETED");
             }
+            claims[claim_idx].completed = completed;
+            self.bounty_claimers.insert(&share.account_id, &claims);
         }
-        None
     }
-}
 
-#[near_bindgen]
-impl Contract {
-    /// Claim given bounty by caller with given expected duration to execute.
-    /// Bond must be attached to the claim.
+    /// Remove the claim of `claimer_id` from this bounty and unlock its bond.
+    /// The bond is returned to the claimer if `return_bond` is true, otherwise the DAO keeps it.
+    /// Since the claim is gone afterwards, its bond can't be settled twice.
+    pub(crate) fn internal_remove_claim(
+        &mut self,
+        bounty_id: u64,
+        claimer_id: &AccountId,
+        return_bond: bool,
+    ) -> PromiseOrValue<()> {
+        let claim = self.internal_take_claim(bounty_id, claimer_id);
+        refund_claim_bond(claimer_id, if return_bond { claim.bond.0 } else { 0 })
+    }
+
+    /// Remove the claim of `claimer_id` given up after the forgiveness period or past its deadline.
+    /// Returns the part of its bond not slashed by `bounty_forfeiture` of the policy, the DAO keeps the rest.
+    fn internal_forfeit_claim(
+        &mut self,
+        policy: &Policy,
+        bounty_id: u64,
+        claimer_id: &AccountId,
+    ) -> PromiseOrValue<()> {
+        let claim = self.internal_take_claim(bounty_id, claimer_id);
+        let forfeiture = policy
+            .bounty_forfeiture
+            .unwrap_or(BountyForfeiture::FullSlash);
+        refund_claim_bond(claimer_id, forfeiture.refund(claim.bond.0))
+    }
+
+    /// Remove the claim of `claimer_id` from this bounty and unlock its bond, returning the claim.
+    fn internal_take_claim(&mut self, bounty_id: u64, claimer_id: &AccountId) -> BountyClaim {
+        let (mut claims, claim_idx) = self.internal_get_claims(bounty_id, claimer_id);
+        let claim = claims.remove(claim_idx);
+        if claims.is_empty() {
+            self.bounty_claimers.remove(claimer_id);
+            legacy_bounty_claimers().remove(claimer_id);
+        } else {
+            self.bounty_claimers.insert(claimer_id, &claims);
+        }
+        let count = self.bounty_claims_count.get(&bounty_id).unwrap() - 1;
+        self.bounty_claims_count.insert(&bounty_id, &count);
+        let mut claimants = self.bounty_claimants.get(&bounty_id).unwrap_or_default();
+        // Account can hold several claims of the bounty, remove just one of them.
+        if let Some(index) = claimants
+            .iter()
+            .position(|account_id| account_id == claimer_id)
+        {
+            claimants.remove(index);
+        }
+        self.bounty_claimants.insert(&bounty_id, &claimants);
+        self.locked_amount -= claim.bond.0;
+        claim
+    }
+
+    /// Records claim of given bounty by `claimer_id` with `bond` locked.
     /// Fails if already claimed `times` times.
-    #[payable]
-    pub fn bounty_claim(&mut self, id: u64, deadline: U64) {
+    pub(crate) fn internal_add_claim(
+        &mut self,
+        id: u64,
+        claimer_id: &AccountId,
+        deadline: U64,
+        bond: Balance,
+        completed: bool,
+    ) {
         let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
-        let policy = self.policy.get().unwrap().to_policy();
-        assert_eq!(
-            env::attached_deposit(),
-            policy.bounty_bond.0,
-            "ERR_BOUNTY_WRONG_BOND"
-        );
         let claims_count = self.bounty_claims_count.get(&id).unwrap_or_default();
         assert!(claims_count < bounty.times, "ERR_BOUNTY_ALL_CLAIMED");
         assert!(
             deadline.0 <= bounty.max_deadline.0,
             "ERR_BOUNTY_WRONG_DEADLINE"
         );
+        if bounty.one_claim_per_account {
+            assert!(
+                self.bounty_past_claimers.insert(&(id, claimer_id.clone())),
+                "ERR_BOUNTY_ALREADY_CLAIMED_BY_ACCOUNT"
+            );
+        }
         self.bounty_claims_count.insert(&id, &(claims_count + 1));
-        let mut claims = self
-            .bounty_claimers
-            .get(&env::predecessor_account_id())
-            .unwrap_or_default();
+        let mut claims = self.internal_bounty_claims(claimer_id).unwrap_or_default();
         claims.push(BountyClaim {
             bounty_id: id,
             start_time: U64::from(env::block_timestamp()),
             deadline,
-            completed: false,
+            completed,
+            bond: U128(bond),
+            milestones_done: 0,
+            extended: None,
+            extension_request: None,
         });
-        self.bounty_claimers
-            .insert(&env::predecessor_account_id(), &claims);
-        self.locked_amount += env::attached_deposit();
+        self.bounty_claimers.insert(claimer_id, &claims);
+        self.locked_amount += bond;
+        let mut claimants = self.bounty_claimants.get(&id).unwrap_or_default();
+        claimants.push(claimer_id.clone());
+        self.bounty_claimants.insert(&id, &claimants);
     }
 
-    /// Remove the claim of `claimer_id` from this bounty.
-    fn internal_remove_claim(&mut self, bounty_id: u64, claimer_id: &AccountId) {
-        let (mut claims, claim_idx) = self.internal_get_claims(bounty_id, claimer_id);
-        claims.remove(claim_idx);
-        if claims.len() == 0 {
-            self.bounty_claimers.remove(claimer_id);
-        } else {
-            self.bounty_claimers.insert(claimer_id, &claims);
+    /// Removes given bounty, returning bonds of its claims and paying `kill_fee` to each claimer.
+    /// Kill fees are sent without waiting for the result. Returns false, without changes, if a claim was
+    /// reported done and waits for its `BountyDone` proposal.
+    pub(crate) fn internal_cancel_bounty(&mut self, id: u64, kill_fee: Balance) -> bool {
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        let claimants = self.bounty_claimants.get(&id).unwrap_or_default();
+        for claimer_id in &claimants {
+            let (claims, claim_idx) = self.internal_get_claims(id, claimer_id);
+            if claims[claim_idx].completed {
+                return false;
+            }
         }
-        let count = self.bounty_claims_count.get(&bounty_id).unwrap() - 1;
-        self.bounty_claims_count.insert(&bounty_id, &count);
+        let token_id = convert_old_to_new_token(&bounty.token);
+        for claimer_id in &claimants {
+            self.internal_remove_claim(id, claimer_id, true);
+            if kill_fee > 0 {
+                self.internal_payout(
+                    &token_id,
+                    claimer_id,
+                    kill_fee,
+                    format!("Bounty {} kill fee", id),
+                    None,
+                );
+            }
+        }
+        self.bounties.remove(&id);
+        self.bounty_claimants.remove(&id);
+        self.bounty_claims_count.remove(&id);
+        self.bounty_listings.remove(&id);
+        true
+    }
+
+    /// Returns claims of given account. Claims stored before versioning are converted when read,
+    /// with the `bounty_bond` of the current policy that was returned for them before.
+    pub(crate) fn internal_bounty_claims(
+        &self,
+        account_id: &AccountId,
+    ) -> Option<Vec<BountyClaim>> {
+        self.bounty_claimers.get(account_id).or_else(|| {
+            let claims = legacy_bounty_claimers().get(account_id)?;
+            let bond = stored_policy().bounty_bond;
+            Some(
+                claims
+                    .into_iter()
+                    .map(|claim| BountyClaim {
+                        bounty_id: claim.bounty_id,
+                        start_time: claim.start_time,
+                        deadline: claim.deadline,
+                        completed: claim.completed,
+                        bond,
+                        milestones_done: 0,
+                        extended: None,
+                        extension_request: None,
+                    })
+                    .collect(),
+            )
+        })
+    }
+
+    /// Returns bond and forgiveness period of bounty claims: from the bounty config, or the policy if not set.
+    fn internal_bounty_bond(&self, policy: &Policy) -> (Balance, u64) {
+        match &self.bounty_config {
+            Some(bounty_config) => (bounty_config.bond.0, bounty_config.forgiveness_period.0),
+            None => (policy.bounty_bond.0, policy.bounty_forgiveness_period.0),
+        }
+    }
+
+    fn internal_find_claim(&self, bounty_id: u64, claims: &[BountyClaim]) -> Option<usize> {
+        for i in 0..claims.len() {
+            if claims[i].bounty_id == bounty_id {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Claim given bounty by caller with given expected duration to execute.
+    /// Bond must be attached to the claim.
+    /// Fails if already claimed `times` times.
+    #[payable]
+    pub fn bounty_claim(&mut self, id: u64, deadline: U64) {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let (bond, _) = self.internal_bounty_bond(&policy);
+        assert_eq!(env::attached_deposit(), bond, "ERR_BOUNTY_WRONG_BOND");
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        self.internal_use_assignment(&bounty, id, &env::predecessor_account_id());
+        self.internal_add_claim(
+            id,
+            &env::predecessor_account_id(),
+            deadline,
+            env::attached_deposit(),
+            false,
+        );
     }
 
     fn internal_get_claims(&mut self, id: u64, sender_id: &AccountId) -> (Vec<BountyClaim>, usize) {
         let claims = self
-            .bounty_claimers
-            .get(&sender_id)
+            .internal_bounty_claims(sender_id)
             .expect("ERR_NO_BOUNTY_CLAIMS");
         let claim_idx = self
             .internal_find_claim(id, &claims)
@@ -159,14 +577,64 @@ impl Contract {
     /// Report that bounty is done. Creates a proposal to vote for paying out the bounty.
     /// Only creator of the claim can call `done` on bounty that is still in progress.
     /// On expired, anyone can call it to free up the claim slot.
+    /// Bounties with milestones are reported done by `milestone`, each one in order before its deadline.
+    #[payable]
+    pub fn bounty_done(
+        &mut self,
+        id: u64,
+        account_id: Option<AccountId>,
+        description: String,
+        milestone: Option<u32>,
+    ) {
+        self.assert_not_read_only();
+        self.internal_bounty_done(id, account_id, description, milestone, vec![]);
+    }
+
+    /// Report that bounty is done together with co-contributors holding claims of it.
+    /// The payout is split between them by `shares` once approved, and their claims are closed with the claim of the caller.
     #[payable]
-    pub fn bounty_done(&mut self, id: u64, account_id: Option<AccountId>, description: String) {
+    pub fn bounty_done_shared(
+        &mut self,
+        id: u64,
+        description: String,
+        milestone: Option<u32>,
+        shares: Vec<BountyShare>,
+    ) {
+        self.assert_not_read_only();
+        check_shares(&env::predecessor_account_id(), &shares)
+            .unwrap_or_else(|err| panic!("{}", err));
+        self.internal_bounty_done(id, None, description, milestone, shares);
+    }
+
+    fn internal_bounty_done(
+        &mut self,
+        id: u64,
+        account_id: Option<AccountId>,
+        description: String,
+        milestone: Option<u32>,
+        shares: Vec<BountyShare>,
+    ) {
         let sender_id = account_id.unwrap_or_else(|| env::predecessor_account_id());
         let (mut claims, claim_idx) = self.internal_get_claims(id, &sender_id);
         assert!(!claims[claim_idx].completed, "ERR_BOUNTY_CLAIM_COMPLETED");
+        if let Some(index) = milestone {
+            let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+            check_milestone(&bounty, milestone).unwrap_or_else(|err| panic!("{}", err));
+            assert_eq!(
+                index, claims[claim_idx].milestones_done,
+                "ERR_WRONG_MILESTONE"
+            );
+            assert!(
+                env::block_timestamp()
+                    <= claims[claim_idx].start_time.0
+                        + bounty.milestones[index as usize].deadline.0,
+                "ERR_MILESTONE_DEADLINE_PASSED"
+            );
+        }
         if env::block_timestamp() > claims[claim_idx].start_time.0 + claims[claim_idx].deadline.0 {
-            // Expired. Nothing to do.
-            self.internal_remove_claim(id, &sender_id);
+            // Expired. Bond is forfeited.
+            let policy = self.policy.get().unwrap().to_policy();
+            self.internal_forfeit_claim(&policy, id, &sender_id);
         } else {
             // Still under deadline. Only the user themself can call this.
             assert_eq!(
@@ -174,12 +642,18 @@ impl Contract {
                 env::predecessor_account_id(),
                 "ERR_BOUNTY_DONE_MUST_BE_SELF"
             );
+            self.internal_set_shares_completed(id, &shares, true);
             self.add_proposal(ProposalInput {
                 description,
                 kind: ProposalKind::BountyDone {
                     bounty_id: id,
                     receiver_id: sender_id.clone(),
+                    milestone,
+                    shares,
+                    dispute_of: None,
                 },
+                idempotency_key: None,
+                depends_on: None,
             });
             claims[claim_idx].completed = true;
             self.bounty_claimers.insert(&sender_id, &claims);
@@ -187,23 +661,233 @@ impl Contract {
     }
 
     /// Give up working on the bounty.
+    /// Not allowed once the bounty is reported as done, the claim is then resolved by the proposal.
     pub fn bounty_giveup(&mut self, id: u64) -> PromiseOrValue<()> {
+        self.assert_not_read_only();
         let policy = self.policy.get().unwrap().to_policy();
         let (claims, claim_idx) = self.internal_get_claims(id, &env::predecessor_account_id());
-        let result = if env::block_timestamp() - claims[claim_idx].start_time.0
-            > policy.bounty_forgiveness_period.0
-        {
-            // If user over the forgiveness period.
-            PromiseOrValue::Value(())
+        assert!(!claims[claim_idx].completed, "ERR_BOUNTY_CLAIM_COMPLETED");
+        // Bond is returned in full only within forgiveness period.
+        let (_, forgiveness_period) = self.internal_bounty_bond(&policy);
+        if env::block_timestamp() - claims[claim_idx].start_time.0 <= forgiveness_period {
+            self.internal_remove_claim(id, &env::predecessor_account_id(), true)
         } else {
-            // Within forgiveness period. Return bond.
-            self.locked_amount -= policy.bounty_bond.0;
-            Promise::new(env::predecessor_account_id())
-                .transfer(policy.bounty_bond.0)
-                .into()
-        };
-        self.internal_remove_claim(id, &env::predecessor_account_id());
-        result
+            self.internal_forfeit_claim(&policy, id, &env::predecessor_account_id())
+        }
+    }
+
+    /// Receiving storage balances of the receivers of a fungible token payout of a bounty.
+    /// Transfers the payout if all of them are registered in the token, in one promise so the transfers
+    /// succeed or fail together. Fails otherwise, so the `BountyDone` proposal fails with its claims and budget
+    /// left untouched, and can be finalized again once the receivers are registered.
+    #[private]
+    pub fn on_bounty_storage_check(
+        &mut self,
+        token_id: AccountId,
+        payouts: Vec<(AccountId, U128)>,
+        memo: String,
+    ) -> Promise {
+        assert_eq!(
+            env::promise_results_count(),
+            payouts.len() as u64,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        for i in 0..payouts.len() {
+            let registered = match env::promise_result(i as u64) {
+                PromiseResult::Successful(value) => {
+                    serde_json::from_slice::<Option<StorageBalance>>(&value)
+                        .ok()
+                        .flatten()
+                        .is_some()
+                }
+                _ => false,
+            };
+            assert!(registered, "ERR_BOUNTY_RECEIVER_NOT_REGISTERED");
+        }
+        payouts
+            .into_iter()
+            .fold(Promise::new(token_id), |promise, (account_id, amount)| {
+                promise.function_call(
+                    "ft_transfer".to_string(),
+                    json!({
+                        "receiver_id": account_id,
+                        "amount": amount,
+                        "memo": memo,
+                    })
+                    .to_string()
+                    .into_bytes(),
+                    ONE_YOCTO_NEAR,
+                    GAS_FOR_FT_TRANSFER,
+                )
+            })
+    }
+
+    /// Requests extension of the deadline of the claim of given bounty by the caller, before the deadline passes.
+    /// Granted right away while total extensions of the claim stay within `bounty_auto_extension` of the policy.
+    /// Otherwise waits for a bounty reviewer to approve it with `bounty_approve_extension`, replacing the previous
+    /// request. Returns true if granted.
+    pub fn bounty_request_extension(&mut self, id: u64, extra_time: U64) -> bool {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let claimer_id = env::predecessor_account_id();
+        let (mut claims, claim_idx) = self.internal_get_claims(id, &claimer_id);
+        let claim = &mut claims[claim_idx];
+        assert!(!claim.completed, "ERR_BOUNTY_CLAIM_COMPLETED");
+        assert!(
+            env::block_timestamp() <= claim.start_time.0 + claim.deadline.0,
+            "ERR_BOUNTY_CLAIM_EXPIRED"
+        );
+        assert!(extra_time.0 > 0, "ERR_INVALID_EXTRA_TIME");
+        let extended = claim.extended.map_or(0, |extended| extended.0);
+        let granted =
+            extended + extra_time.0 <= policy.bounty_auto_extension.map_or(0, |limit| limit.0);
+        if granted {
+            claim.extend(extra_time.0);
+        } else {
+            claim.extension_request = Some(extra_time);
+        }
+        self.bounty_claimers.insert(&claimer_id, &claims);
+        granted
+    }
+
+    /// Approves the extension requested for the claim of given bounty by `account_id`.
+    /// Caller must be a member of a role with `bounty_review` permission, other than the claimer.
+    pub fn bounty_approve_extension(&mut self, id: u64, account_id: AccountId) {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            account_id != env::predecessor_account_id()
+                && is_bounty_reviewer(&policy, &self.internal_user_info()),
+            "ERR_NOT_BOUNTY_REVIEWER"
+        );
+        let (mut claims, claim_idx) = self.internal_get_claims(id, &account_id);
+        let extra_time = claims[claim_idx]
+            .extension_request
+            .expect("ERR_NO_EXTENSION_REQUEST");
+        claims[claim_idx].extend(extra_time.0);
+        self.bounty_claimers.insert(&account_id, &claims);
+    }
+
+    /// Approves given `BountyDone` proposal without a vote. Caller must be a member of a role with `bounty_review`
+    /// permission and not one of the contributors paid by it.
+    /// The proposal is queued for `bounty_challenge_period` of the policy, in which it can be sent to a full vote
+    /// with `bounty_challenge`, and executed with `Finalize` after.
+    pub fn bounty_review(&mut self, id: u64) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let reviewer_id = env::predecessor_account_id();
+        match &proposal.kind {
+            ProposalKind::BountyDone {
+                receiver_id,
+                shares,
+                ..
+            } => assert!(
+                receiver_id != &reviewer_id
+                    && shares.iter().all(|share| share.account_id != reviewer_id),
+                "ERR_BOUNTY_REVIEWER_IS_CONTRIBUTOR"
+            ),
+            _ => env::panic_str("ERR_NOT_BOUNTY_DONE"),
+        }
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::InProgress,
+            "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
+        );
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            policy.voting_end(&proposal) >= policy.now(),
+            "ERR_PROPOSAL_EXPIRED"
+        );
+        assert!(
+            is_bounty_reviewer(&policy, &self.internal_user_info()),
+            "ERR_NOT_BOUNTY_REVIEWER"
+        );
+        proposal.reviewed_by = Some(reviewer_id);
+        proposal.status = ProposalStatus::Queued;
+        let challenge_period = policy.bounty_challenge_period.map_or(0, |period| period.0);
+        proposal.execution_after = Some(U64(policy.now() + challenge_period));
+        self.internal_record_transition(id, &proposal.status);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        self.internal_close_proposal();
+    }
+
+    /// Sends given `BountyDone` proposal approved by `bounty_review` to a full vote, within the challenge period.
+    /// Caller must be allowed to vote to reject it. Voting period starts again, or once the proposal leaves
+    /// the queue if `max_open_proposals` are in progress.
+    pub fn bounty_challenge(&mut self, id: u64) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            proposal.reviewed_by.is_some()
+                && proposal.status == ProposalStatus::Queued
+                && policy.now() < proposal.execution_after.unwrap().0,
+            "ERR_PROPOSAL_NOT_IN_CHALLENGE_PERIOD"
+        );
+        let (_, allowed) = policy.can_execute_action(
+            self.internal_voter_info(id, &proposal),
+            &proposal.kind,
+            &Action::VoteReject,
+        );
+        assert!(allowed, "ERR_PERMISSION_DENIED");
+        proposal.reviewed_by = None;
+        proposal.status = ProposalStatus::InProgress;
+        proposal.execution_after = None;
+        proposal.submission_time = U64::from(env::block_timestamp());
+        proposal.submission_block = U64::from(env::block_height());
+        self.internal_open_or_enqueue(&policy, &mut proposal, id);
+        self.internal_record_transition(id, &proposal.status);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+    }
+
+    /// Returns bounty config set by `ChangeBountyConfig` proposal.
+    pub fn get_bounty_config(&self) -> Option<BountyConfig> {
+        self.bounty_config.clone()
+    }
+
+    /// Get claims of given bounty with their claimers, grouped by claimer, in paginated view.
+    pub fn get_bounty_claims_by_bounty(
+        &self,
+        id: u64,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(AccountId, BountyClaim)> {
+        let claimants = self.bounty_claimants.get(&id).unwrap_or_default();
+        let mut claims = vec![];
+        for (index, account_id) in claimants.iter().enumerate() {
+            if claimants[..index].contains(account_id) {
+                continue;
+            }
+            claims.extend(
+                self.internal_bounty_claims(account_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|claim| claim.bounty_id == id)
+                    .map(|claim| (account_id.clone(), claim)),
+            );
+        }
+        claims
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+/// Keeps only the reviewer role of the bounty config among roles voting on `BountyDone` proposals.
+pub(crate) fn filter_bounty_reviewer_role(
+    bounty_config: &Option<BountyConfig>,
+    proposal: &Proposal,
+    roles: &mut Vec<String>,
+) {
+    if let (Some(reviewer_role), ProposalKind::BountyDone { .. }) = (
+        bounty_config
+            .as_ref()
+            .and_then(|bounty_config| bounty_config.reviewer_role.as_ref()),
+        &proposal.kind,
+    ) {
+        roles.retain(|role| role == reviewer_role);
+        assert!(!roles.is_empty(), "ERR_NOT_BOUNTY_REVIEWER");
     }
 }
 
@@ -214,7 +898,7 @@ mod tests {
     use near_sdk_sim::to_yocto;
 
     use crate::proposals::{ProposalInput, ProposalKind};
-    use crate::{Action, Config};
+    use crate::{Action, Config, RoleKind, RolePermission};
 
     use super::*;
 
@@ -229,41 +913,556 @@ mod tests {
                     amount: U128(to_yocto("10")),
                     times,
                     max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: false,
                 },
             },
+            idempotency_key: None,
+            depends_on: None,
         });
         assert_eq!(contract.get_last_bounty_id(), id);
         contract.act_proposal(id, Action::VoteApprove, None);
         id
     }
 
-    /// Adds a bounty, and tests it's full lifecycle.
+    fn add_bounty_with_milestones(context: &mut VMContextBuilder, contract: &mut Contract) {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let milestone = |amount, deadline| BountyMilestone {
+            description: "milestone".to_string(),
+            amount: U128(to_yocto(amount)),
+            deadline: U64(deadline),
+        };
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![milestone("4", 500), milestone("6", 1_000)],
+                    requires_assignment: false,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.bounty_claim(0, U64::from(1_000));
+    }
+
+    fn approve_bounty_done(context: &mut VMContextBuilder, contract: &mut Contract, id: u64) {
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(id);
+    }
+
     #[test]
-    fn test_bounty_lifecycle() {
+    fn test_bounty_milestones() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
             Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1).into()]),
+            VersionedPolicy::Default(vec![accounts(1)]),
         );
-        add_bounty(&mut context, &mut contract, 2);
+        add_bounty_with_milestones(&mut context, &mut contract);
 
-        assert_eq!(contract.get_last_bounty_id(), 1);
-        assert_eq!(contract.get_bounty(0).bounty.times, 2);
+        contract.bounty_done(0, None, "First milestone".to_string(), Some(0));
+        approve_bounty_done(&mut context, &mut contract, 1);
+        // Claim stays open for the next milestone.
+        let claims = contract.get_bounty_claims(accounts(1));
+        assert_eq!(claims[0].milestones_done, 1);
+        assert!(!claims[0].completed);
 
-        contract.bounty_claim(0, U64::from(500));
-        assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 1);
-        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+        contract.bounty_done(0, None, "Second milestone".to_string(), Some(1));
+        approve_bounty_done(&mut context, &mut contract, 2);
+        assert!(contract.get_bounty_claims(accounts(1)).is_empty());
+        assert_eq!(contract.get_bounty(0).bounty.times, 0);
+    }
 
-        contract.bounty_giveup(0);
-        assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 0);
-        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+    #[test]
+    #[should_panic(expected = "ERR_WRONG_MILESTONE")]
+    fn test_bounty_milestone_out_of_order() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        add_bounty_with_milestones(&mut context, &mut contract);
+        contract.bounty_done(0, None, "Second milestone".to_string(), Some(1));
+    }
 
-        contract.bounty_claim(0, U64::from(500));
+    #[test]
+    fn test_bounty_claims_by_bounty() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 3);
+        contract.bounty_claim(id, U64::from(500));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_claim(id, U64::from(500));
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.bounty_claim(id, U64::from(600));
+        let claimers = |contract: &Contract, from_index, limit| -> Vec<AccountId> {
+            contract
+                .get_bounty_claims_by_bounty(id, from_index, limit)
+                .into_iter()
+                .map(|(account_id, _)| account_id)
+                .collect()
+        };
+        assert_eq!(
+            claimers(&contract, 0, 10),
+            vec![accounts(1), accounts(1), accounts(2)]
+        );
+        assert_eq!(claimers(&contract, 1, 1), vec![accounts(1)]);
+
+        // Giving up removes just one claim of the account.
+        contract.bounty_giveup(id);
+        assert_eq!(claimers(&contract, 0, 10), vec![accounts(2), accounts(1)]);
+        assert_eq!(
+            contract.get_bounty_claims_by_bounty(id, 1, 1)[0].1.deadline,
+            U64::from(600)
+        );
+    }
+
+    #[test]
+    fn test_bounty_done_shared() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 2);
+        contract.bounty_claim(id, U64::from(500));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_claim(id, U64::from(500));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.bounty_done_shared(
+            id,
+            "Bounty is done together".to_string(),
+            None,
+            vec![BountyShare {
+                account_id: accounts(2),
+                percent: 40,
+            }],
+        );
+        assert!(contract.get_bounty_claims(accounts(2))[0].completed);
+        let done_id = contract.get_last_proposal_id() - 1;
+        contract.act_proposal(done_id, Action::VoteApprove, None);
+        let paid = |account_id: AccountId| -> Balance {
+            near_sdk::test_utils::get_created_receipts()
+                .into_iter()
+                .filter(|receipt| receipt.receiver_id == account_id)
+                .flat_map(|receipt| receipt.actions)
+                .map(|action| match action {
+                    near_sdk::mock::VmAction::Transfer { deposit } => deposit,
+                    _ => 0,
+                })
+                .sum()
+        };
+        assert_eq!(paid(accounts(1)), to_yocto("6"));
+        assert_eq!(paid(accounts(2)), to_yocto("4"));
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(done_id);
+        // Claims of both contributors are closed by one completion.
+        assert_eq!(contract.get_bounty_number_of_claims(id), 0);
+        assert_eq!(contract.get_bounty(id).bounty.times, 1);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_BOUNTY_SHARES")]
+    fn test_bounty_done_shared_invalid_shares() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 2);
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done_shared(
+            id,
+            "Bounty is done".to_string(),
+            None,
+            vec![BountyShare {
+                account_id: accounts(2),
+                percent: 100,
+            }],
+        );
+    }
+
+    #[test]
+    fn test_bounty_cancel() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 2);
+        contract.bounty_claim(id, U64::from(500));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_claim(id, U64::from(500));
+        assert_eq!(contract.locked_amount, to_yocto("2"));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let cancel_id = contract.add_proposal(ProposalInput {
+            description: "stale".to_string(),
+            kind: ProposalKind::BountyCancel {
+                bounty_id: id,
+                kill_fee: Some(U128(to_yocto("1"))),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(cancel_id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(cancel_id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert!(contract.bounties.get(&id).is_none());
+        assert!(contract.get_bounty_claims(accounts(2)).is_empty());
+        assert_eq!(contract.locked_amount, 0);
+        // Bond and kill fee of the second claimer.
+        assert_eq!(
+            near_sdk::test_utils::get_created_receipts()
+                .into_iter()
+                .filter(|receipt| receipt.receiver_id == accounts(2))
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_bounty_cancel_with_claim_done() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done(id, None, "Bounty is done".to_string(), None);
+        let cancel_id = contract.add_proposal(ProposalInput {
+            description: "stale".to_string(),
+            kind: ProposalKind::BountyCancel {
+                bounty_id: id,
+                kill_fee: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(cancel_id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(cancel_id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert_eq!(contract.get_bounty_number_of_claims(id), 1);
+
+        // Rejecting the report frees the claim, and the cancel goes through.
+        contract.act_proposal(1, Action::VoteReject, None);
+        contract.act_proposal(cancel_id, Action::Finalize, None);
+        assert!(contract.bounties.get(&id).is_none());
+    }
+
+    /// Contract with a bounty claimed by `accounts(1)`, and `accounts(2)` as bounty reviewer.
+    fn contract_with_reviewer(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles.push(RolePermission {
+            name: "reviewers".to_string(),
+            kind: RoleKind::Group(vec![accounts(2)].into_iter().collect()),
+            permissions: vec![BOUNTY_REVIEW_PERMISSION.to_string()]
+                .into_iter()
+                .collect(),
+            vote_policy: Default::default(),
+        });
+        policy_mut.bounty_challenge_period = Some(U64(100));
+        policy_mut.bounty_auto_extension = Some(U64(100));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = add_bounty(context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        contract
+    }
+
+    /// Contract with a bounty reported done by `accounts(1)` in proposal 1, and approved by `accounts(2)`.
+    fn contract_with_review(context: &mut VMContextBuilder) -> Contract {
+        let mut contract = contract_with_reviewer(context);
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.bounty_review(1);
+        contract
+    }
+
+    #[test]
+    fn test_bounty_review() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_review(&mut context);
+        let proposal = contract.get_proposal(1).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Queued);
+        assert_eq!(proposal.reviewed_by, Some(accounts(2)));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(100)
+            .build());
+        contract.act_proposal(1, Action::Finalize, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(1);
+        assert_eq!(
+            contract.get_proposal(1).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+    }
+
+    #[test]
+    fn test_bounty_review_challenged() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_review(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(99)
+            .build());
+        contract.bounty_challenge(1);
+        let proposal = contract.get_proposal(1).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.reviewed_by, None);
+        contract.act_proposal(1, Action::VoteReject, None);
+        assert_eq!(
+            contract.get_proposal(1).proposal.status,
+            ProposalStatus::Rejected
+        );
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+    }
+
+    #[test]
+    fn test_bounty_challenge_queued() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_review(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.max_open_proposals = Some(1);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context.attached_deposit(0).build());
+        contract.bounty_challenge(1);
+        assert_eq!(
+            contract.get_proposal(1).proposal.status,
+            ProposalStatus::Waiting
+        );
+        assert_eq!(contract.open_proposals, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_EXPIRED")]
+    fn test_bounty_review_expired() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_reviewer(&mut context);
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .block_timestamp(1_000_000_000 * 60 * 60 * 24 * 8)
+            .build());
+        contract.bounty_review(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_BOUNTY_REVIEWER")]
+    fn test_bounty_review_not_reviewer() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_review(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.bounty_challenge(1);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.bounty_review(1);
+    }
+
+    #[test]
+    fn test_bounty_extension() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_reviewer(&mut context);
+        assert!(contract.bounty_request_extension(0, U64(60)));
+        // Over the auto-granted limit, the extension waits for a reviewer.
+        assert!(!contract.bounty_request_extension(0, U64(60)));
+        assert_eq!(
+            contract.get_bounty_claims(accounts(1))[0].deadline,
+            U64(560)
+        );
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_approve_extension(0, accounts(1));
+        let claim = &contract.get_bounty_claims(accounts(1))[0];
+        assert_eq!(claim.deadline, U64(620));
+        assert_eq!(claim.extension_request, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(600)
+            .build());
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
+        assert!(contract.get_bounty_claims(accounts(1))[0].completed);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_EXTENSION_REQUEST")]
+    fn test_bounty_extension_not_requested() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_reviewer(&mut context);
+        contract.bounty_request_extension(0, U64(60));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_approve_extension(0, accounts(1));
+    }
+
+    fn storage_check(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        storage_balance: Option<StorageBalance>,
+    ) {
+        add_bounty(context, contract, 1);
+        let mut bounty: Bounty = contract.bounties.get(&0).unwrap().into();
+        bounty.token = accounts(4).to_string();
+        contract.bounties.insert(&0, &VersionedBounty::V2(bounty));
+        contract.bounty_claim(0, U64::from(500));
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
+        contract.act_proposal(1, Action::VoteApprove, None);
+        assert!(near_sdk::test_utils::get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                serde_json::to_vec(&storage_balance).unwrap()
+            )],
+        );
+        contract.on_bounty_storage_check(
+            accounts(4),
+            vec![(accounts(1), U128(to_yocto("10")))],
+            "Bounty 0 payout".to_string(),
+        );
+    }
+
+    #[test]
+    fn test_bounty_ft_payout() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        storage_check(
+            &mut context,
+            &mut contract,
+            Some(StorageBalance {
+                total: U128(1),
+                available: U128(0),
+            }),
+        );
+        let transfers = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == accounts(4))
+            .flat_map(|receipt| receipt.actions)
+            .filter(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall { function_name, .. } => {
+                    function_name == "ft_transfer"
+                }
+                _ => false,
+            })
+            .count();
+        assert_eq!(transfers, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BOUNTY_RECEIVER_NOT_REGISTERED")]
+    fn test_bounty_ft_payout_not_registered() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        storage_check(&mut context, &mut contract, None);
+    }
+
+    /// Adds a bounty, and tests it's full lifecycle.
+    #[test]
+    fn test_bounty_lifecycle() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1).into()]),
+        );
+        add_bounty(&mut context, &mut contract, 2);
+
+        assert_eq!(contract.get_last_bounty_id(), 1);
+        assert_eq!(contract.get_bounty(0).bounty.times, 2);
+
+        contract.bounty_claim(0, U64::from(500));
         assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 1);
         assert_eq!(contract.get_bounty_number_of_claims(0), 1);
 
-        contract.bounty_done(0, None, "Bounty is done".to_string());
+        contract.bounty_giveup(0);
+        assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 0);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+
+        contract.bounty_claim(0, U64::from(500));
+        assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 1);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
         assert!(contract.get_bounty_claims(accounts(1))[0].completed);
 
         assert_eq!(contract.get_last_proposal_id(), 2);
@@ -286,7 +1485,7 @@ mod tests {
         assert_eq!(contract.get_bounty(0).bounty.times, 1);
 
         contract.bounty_claim(0, U64::from(500));
-        contract.bounty_done(0, None, "Bounty is done 2".to_string());
+        contract.bounty_done(0, None, "Bounty is done 2".to_string(), None);
         contract.act_proposal(2, Action::VoteApprove, None);
         testing_env!(
             context.build(),
@@ -300,6 +1499,211 @@ mod tests {
         assert_eq!(contract.get_bounty(0).bounty.times, 0);
     }
 
+    #[test]
+    fn test_bounty_budget() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let set_budget = |context: &mut VMContextBuilder, contract: &mut Contract, amount| {
+            testing_env!(context.attached_deposit(to_yocto("1")).build());
+            let id = contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::SetBudget {
+                    name: "grants".to_string(),
+                    budget: Budget {
+                        token: String::from(OLD_BASE_TOKEN),
+                        amount: U128(amount),
+                    },
+                },
+                idempotency_key: None,
+                depends_on: None,
+            });
+            contract.act_proposal(id, Action::VoteApprove, None);
+        };
+        set_budget(&mut context, &mut contract, to_yocto("5"));
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                    budget: Some("grants".to_string()),
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: false,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.bounty_claim(0, U64::from(500));
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
+        let done_id = contract.get_last_proposal_id() - 1;
+        contract.act_proposal(done_id, Action::VoteApprove, None);
+        // Budget can't cover the payout: claim is kept and nothing is debited.
+        assert_eq!(
+            contract.get_proposal(done_id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+        assert_eq!(
+            contract.get_budget("grants".to_string()).unwrap().amount,
+            U128(to_yocto("5"))
+        );
+
+        set_budget(&mut context, &mut contract, to_yocto("15"));
+        contract.act_proposal(done_id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(done_id).proposal.status,
+            ProposalStatus::Approved
+        );
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(done_id);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+        assert_eq!(
+            contract.get_budget("grants".to_string()).unwrap().amount,
+            U128(to_yocto("5"))
+        );
+    }
+
+    /// Payout fails and is finalized again: bonds are returned and the claim is freed only once.
+    #[test]
+    fn test_bounty_bonds_settled_once() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 2);
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done(id, None, "Bounty is done".to_string(), None);
+        assert_eq!(contract.locked_amount, to_yocto("2"));
+        let done_id = contract.get_last_proposal_id() - 1;
+        contract.act_proposal(done_id, Action::VoteApprove, None);
+
+        let callback = |context: &mut VMContextBuilder, contract: &mut Contract, result| {
+            testing_env!(
+                context.build(),
+                near_sdk::VMConfig::test(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![result],
+            );
+            contract.on_proposal_callback(done_id);
+        };
+        callback(&mut context, &mut contract, PromiseResult::Failed);
+        // Claim and bonds stay locked while the payout can be retried.
+        assert_eq!(contract.get_bounty_number_of_claims(id), 1);
+        assert_eq!(contract.locked_amount, to_yocto("2"));
+
+        contract.act_proposal(done_id, Action::Finalize, None);
+        callback(
+            &mut context,
+            &mut contract,
+            PromiseResult::Successful(vec![]),
+        );
+        assert_eq!(contract.get_bounty_number_of_claims(id), 0);
+        assert_eq!(contract.get_bounty(id).bounty.times, 1);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BOUNTY_CLAIM_COMPLETED")]
+    fn test_bounty_giveup_after_done() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done(id, None, "Bounty is done".to_string(), None);
+        contract.bounty_giveup(id);
+    }
+
+    fn refunded(account_id: AccountId) -> Balance {
+        near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == account_id)
+            .flat_map(|receipt| receipt.actions)
+            .map(|action| match action {
+                near_sdk::mock::VmAction::Transfer { deposit } => deposit,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_bounty_giveup_partial_slash() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().bounty_forfeiture =
+            Some(BountyForfeiture::PartialSlash { share: 2_500 });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        let forgiveness_period = contract.get_policy().bounty_forgiveness_period.0;
+        testing_env!(context
+            .attached_deposit(0)
+            .block_timestamp(forgiveness_period + 1)
+            .build());
+        contract.bounty_giveup(id);
+        assert_eq!(refunded(accounts(1)), to_yocto("0.75"));
+        assert_eq!(contract.locked_amount, 0);
+        assert_eq!(contract.get_bounty_number_of_claims(id), 0);
+    }
+
+    #[test]
+    fn test_bounty_expired_refund() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().bounty_forfeiture = Some(BountyForfeiture::Refund);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        testing_env!(context.attached_deposit(0).block_timestamp(501).build());
+        contract.bounty_done(id, None, "Too late".to_string(), None);
+        assert_eq!(refunded(accounts(1)), to_yocto("1"));
+        assert_eq!(contract.locked_amount, 0);
+        assert_eq!(contract.get_last_proposal_id(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BOUNTY_ALREADY_CLAIMED_BY_ACCOUNT")]
+    fn test_bounty_one_claim_per_account() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 2);
+        let mut bounty: Bounty = contract.bounties.get(&id).unwrap().into();
+        bounty.one_claim_per_account = true;
+        contract.bounties.insert(&id, &VersionedBounty::V2(bounty));
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_giveup(id);
+        contract.bounty_claim(id, U64::from(500));
+    }
+
     #[test]
     #[should_panic(expected = "ERR_BOUNTY_ALL_CLAIMED")]
     fn test_bounty_claim_not_allowed() {
@@ -311,7 +1715,96 @@ mod tests {
         );
         let id = add_bounty(&mut context, &mut contract, 1);
         contract.bounty_claim(id, U64::from(500));
-        contract.bounty_done(id, None, "Bounty is done 2".to_string());
+        contract.bounty_done(id, None, "Bounty is done 2".to_string(), None);
         contract.bounty_claim(id, U64::from(500));
     }
+
+    fn set_bounty_config(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        reviewer_role: &str,
+    ) {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangeBountyConfig {
+                bounty_config: BountyConfig {
+                    bond: U128(to_yocto("2")),
+                    forgiveness_period: U64(0),
+                    reviewer_role: Some(reviewer_role.to_string()),
+                    default_times: 3,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_bounty_config() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        set_bounty_config(&mut context, &mut contract, "council");
+        assert_eq!(contract.get_bounty_config().unwrap().default_times, 3);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let proposal_id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 0,
+                    max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: false,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(proposal_id, Action::VoteApprove, None);
+        let id = contract.get_last_bounty_id() - 1;
+        assert_eq!(contract.get_bounty(id).bounty.times, 3);
+        testing_env!(context
+            .attached_deposit(to_yocto("2"))
+            .block_timestamp(1)
+            .build());
+        contract.bounty_claim(id, U64::from(500));
+        // Bond is kept after the forgiveness period of the config.
+        testing_env!(context.block_timestamp(2).build());
+        contract.bounty_giveup(id);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_BOUNTY_REVIEWER")]
+    fn test_bounty_done_by_not_reviewer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 1);
+        // Only the "all" role, which can't vote, decides bounties.
+        set_bounty_config(&mut context, &mut contract, "all");
+        testing_env!(context.attached_deposit(to_yocto("2")).build());
+        contract.bounty_claim(id, U64::from(500));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.bounty_done(id, None, "Bounty is done".to_string(), None);
+        contract.act_proposal(
+            contract.get_last_proposal_id() - 1,
+            Action::VoteApprove,
+            None,
+        );
+    }
 }
@@ -0,0 +1,180 @@
+//! Bounties assigned to applicants: accounts apply for a bounty with `requires_assignment` with a pitch, and can
+//! only claim it once assigned by a bounty reviewer or a member deciding its `BountyDone` proposals.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::bounties::is_bounty_reviewer;
+use crate::*;
+
+/// Application of an account for a bounty.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyApplication {
+    pub account_id: AccountId,
+    /// Why the applicant should do the bounty.
+    pub pitch: String,
+    /// Assigned and not claimed yet.
+    pub assigned: bool,
+}
+
+impl Contract {
+    /// Checks that given account was assigned given bounty, if it requires assignment, and uses up the assignment.
+    pub(crate) fn internal_use_assignment(
+        &mut self,
+        bounty: &Bounty,
+        id: u64,
+        account_id: &AccountId,
+    ) {
+        if !bounty.requires_assignment {
+            return;
+        }
+        let mut applications = self.bounty_applications.get(&id).unwrap_or_default();
+        let application = applications
+            .iter_mut()
+            .find(|application| &application.account_id == account_id && application.assigned)
+            .expect("ERR_BOUNTY_NOT_ASSIGNED");
+        application.assigned = false;
+        self.bounty_applications.insert(&id, &applications);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Applies for given bounty with given pitch. Storage is paid as in `internal_pay_storage`.
+    #[payable]
+    pub fn bounty_apply(&mut self, id: u64, pitch: String) {
+        self.assert_not_read_only();
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        assert!(bounty.requires_assignment, "ERR_BOUNTY_NOT_ASSIGNABLE");
+        let account_id = env::predecessor_account_id();
+        let storage_before = env::storage_usage();
+        let mut applications = self.bounty_applications.get(&id).unwrap_or_default();
+        assert!(
+            applications
+                .iter()
+                .all(|application| application.account_id != account_id),
+            "ERR_ALREADY_APPLIED"
+        );
+        applications.push(BountyApplication {
+            account_id: account_id.clone(),
+            pitch,
+            assigned: false,
+        });
+        self.bounty_applications.insert(&id, &applications);
+        self.internal_pay_storage(&account_id, storage_before);
+    }
+
+    /// Assigns given bounty to given applicant, who can claim it afterwards. Caller must be a member of a role with
+    /// `bounty_review` permission, or allowed to approve `BountyDone` proposals paying this bounty to the applicant.
+    pub fn bounty_assign(&mut self, id: u64, account_id: AccountId) {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let user = self.internal_user_info();
+        let done_kind = ProposalKind::BountyDone {
+            bounty_id: id,
+            receiver_id: account_id.clone(),
+            milestone: None,
+            shares: vec![],
+            dispute_of: None,
+        };
+        assert!(
+            is_bounty_reviewer(&policy, &user)
+                || policy
+                    .can_execute_action(user, &done_kind, &Action::VoteApprove)
+                    .1,
+            "ERR_PERMISSION_DENIED"
+        );
+        let mut applications = self.bounty_applications.get(&id).unwrap_or_default();
+        applications
+            .iter_mut()
+            .find(|application| application.account_id == account_id)
+            .expect("ERR_NO_BOUNTY_APPLICATION")
+            .assigned = true;
+        self.bounty_applications.insert(&id, &applications);
+    }
+
+    /// Returns applications for given bounty.
+    pub fn get_bounty_applications(&self, id: u64) -> Vec<BountyApplication> {
+        self.bounty_applications.get(&id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::{U128, U64};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    /// Contract with bounty 0 requiring assignment, which `accounts(2)` applied for.
+    fn contract_with_application(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: true,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_apply(0, "I did it before".to_string());
+        contract
+    }
+
+    #[test]
+    fn test_bounty_assignment() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_application(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.bounty_assign(0, accounts(2));
+        assert!(contract.get_bounty_applications(0)[0].assigned);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_claim(0, U64::from(500));
+        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+        assert!(!contract.get_bounty_applications(0)[0].assigned);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BOUNTY_NOT_ASSIGNED")]
+    fn test_bounty_claim_not_assigned() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_application(&mut context);
+        contract.bounty_claim(0, U64::from(500));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_bounty_assign_by_applicant() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_application(&mut context);
+        contract.bounty_assign(0, accounts(2));
+    }
+}
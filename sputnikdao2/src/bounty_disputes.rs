@@ -0,0 +1,200 @@
+//! Disputes of rejected bounty reports: with `bounty_dispute_period` in the policy, claims of rejected `BountyDone`
+//! proposals stay locked for the period, in which the claimer can dispute the rejection with evidence. The dispute
+//! is a new `BountyDone` proposal, and the claim is settled once it resolves.
+
+use near_sdk::json_types::U64;
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+impl Contract {
+    /// Opens the dispute period of given rejected proposal, if it's a `BountyDone` proposal that is not a dispute
+    /// itself and the policy has `bounty_dispute_period`. Returns true if opened, the claim is then kept.
+    pub(crate) fn internal_open_bounty_dispute(
+        &mut self,
+        policy: &Policy,
+        proposal: &Proposal,
+        proposal_id: u64,
+    ) -> bool {
+        match (&proposal.kind, policy.bounty_dispute_period) {
+            (
+                ProposalKind::BountyDone {
+                    dispute_of: None, ..
+                },
+                Some(period),
+            ) => {
+                self.bounty_disputes
+                    .insert(&proposal_id, &(policy.now() + period.0));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Disputes rejection of given `BountyDone` proposal within the dispute period, by adding a new `BountyDone`
+    /// proposal with given evidence. Only the claimer can dispute, attaching the proposal bond as for `add_proposal`.
+    /// Returns id of the dispute proposal.
+    #[payable]
+    pub fn bounty_dispute(&mut self, id: u64, evidence: String) -> u64 {
+        self.assert_not_read_only();
+        let until = self.bounty_disputes.get(&id).expect("ERR_NOT_DISPUTABLE");
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(policy.now() <= until, "ERR_DISPUTE_PERIOD_OVER");
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let kind = match proposal.kind {
+            ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+                milestone,
+                shares,
+                ..
+            } => {
+                assert_eq!(
+                    receiver_id,
+                    env::predecessor_account_id(),
+                    "ERR_NOT_BOUNTY_CLAIMER"
+                );
+                ProposalKind::BountyDone {
+                    bounty_id,
+                    receiver_id,
+                    milestone,
+                    shares,
+                    dispute_of: Some(id),
+                }
+            }
+            _ => unreachable!(),
+        };
+        self.bounty_disputes.remove(&id);
+        self.add_proposal(ProposalInput {
+            description: format!(
+                "Dispute of proposal {}: {}\n\n{}",
+                id, evidence, proposal.description
+            ),
+            kind,
+            idempotency_key: None,
+            depends_on: None,
+        })
+    }
+
+    /// Settles the claim of given rejected `BountyDone` proposal once its dispute period is over without a dispute.
+    /// Callable by anyone. Bonds of the claim are returned, as for rejections without dispute period.
+    pub fn settle_bounty_rejection(&mut self, id: u64) {
+        self.assert_not_read_only();
+        let until = self.bounty_disputes.get(&id).expect("ERR_NOT_DISPUTABLE");
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(policy.now() > until, "ERR_DISPUTE_PERIOD_NOT_OVER");
+        self.bounty_disputes.remove(&id);
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        if let ProposalKind::BountyDone {
+            bounty_id,
+            receiver_id,
+            shares,
+            ..
+        } = &proposal.kind
+        {
+            self.internal_remove_claim(*bounty_id, receiver_id, true);
+            for share in shares {
+                self.internal_remove_claim(*bounty_id, &share.account_id, true);
+            }
+        }
+    }
+
+    /// Returns end of the dispute period of given rejected `BountyDone` proposal, None if it can't be disputed.
+    pub fn get_bounty_dispute_period(&self, id: u64) -> Option<U64> {
+        self.bounty_disputes.get(&id).map(U64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    /// Contract with the report of bounty 0 by `accounts(1)` rejected in proposal 1.
+    fn contract_with_rejected_report(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().bounty_dispute_period = Some(U64(100));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: false,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.bounty_claim(0, U64::from(500));
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
+        contract.act_proposal(1, Action::VoteReject, None);
+        contract
+    }
+
+    #[test]
+    fn test_bounty_dispute() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rejected_report(&mut context);
+        // Claim bond stays locked, the proposal bond is returned.
+        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+        assert_eq!(contract.get_bounty_dispute_period(1), Some(U64(100)));
+
+        let dispute_id = contract.bounty_dispute(1, "See the deployed contract".to_string());
+        assert_eq!(contract.get_bounty_dispute_period(1), None);
+        assert_eq!(
+            contract.get_proposal(dispute_id).proposal.description,
+            "Dispute of proposal 1: See the deployed contract\n\nBounty is done"
+        );
+        // Rejected dispute can't be disputed again, the claim is settled.
+        contract.act_proposal(dispute_id, Action::VoteReject, None);
+        assert_eq!(contract.get_bounty_dispute_period(dispute_id), None);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    fn test_settle_bounty_rejection() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rejected_report(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(101)
+            .build());
+        contract.settle_bounty_rejection(1);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DISPUTE_PERIOD_OVER")]
+    fn test_bounty_dispute_too_late() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rejected_report(&mut context);
+        testing_env!(context.block_timestamp(101).build());
+        contract.bounty_dispute(1, "See the deployed contract".to_string());
+    }
+}
@@ -0,0 +1,61 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{log, near_bindgen};
+
+use crate::types::OldAccountId;
+use crate::*;
+
+/// Spending envelope of the DAO, that can be set to fund bounties.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Budget {
+    /// Token of the budget.
+    /// Can be "" for $NEAR or a valid account id.
+    pub token: OldAccountId,
+    /// Amount left to spend from this budget.
+    pub amount: U128,
+}
+
+impl Contract {
+    /// Debits given amount of token from the budget with given name.
+    /// Returns false and doesn't change anything if the budget is missing or can't cover the amount.
+    pub(crate) fn internal_debit_budget(
+        &mut self,
+        name: &String,
+        token: &OldAccountId,
+        amount: Balance,
+    ) -> bool {
+        let mut budget = match self.budgets.get(name) {
+            Some(budget) => budget,
+            None => {
+                log!("ERR_NO_BUDGET:{}", name);
+                return false;
+            }
+        };
+        if &budget.token != token || budget.amount.0 < amount {
+            log!("ERR_BUDGET_EXHAUSTED:{}", name);
+            return false;
+        }
+        budget.amount = U128(budget.amount.0 - amount);
+        self.budgets.insert(name, &budget);
+        true
+    }
+
+    /// Returns given amount back to the budget, e.g. when payout has failed.
+    pub(crate) fn internal_credit_budget(&mut self, name: &String, amount: Balance) {
+        if let Some(mut budget) = self.budgets.get(name) {
+            budget.amount = U128(budget.amount.0 + amount);
+            self.budgets.insert(name, &budget);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns budget with given name if it exists.
+    pub fn get_budget(&self, name: String) -> Option<Budget> {
+        self.budgets.get(&name)
+    }
+}
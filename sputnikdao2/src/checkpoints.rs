@@ -0,0 +1,201 @@
+//! Recurring checkpoints: on each period of a schedule from the policy, the reporter (e.g. a cron integration)
+//! adds a `Checkpoint` proposal with the hash of the periodic report, and the council attests it by approving.
+//! Checkpoints that are not approved are recorded as missed.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::*;
+
+/// Named schedule of checkpoints.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CheckpointSchedule {
+    pub name: String,
+    /// Length of the period between checkpoints, in nanoseconds.
+    pub interval: U64,
+    /// Account allowed to add checkpoint proposals with the report hash.
+    pub reporter_id: AccountId,
+}
+
+/// History of checkpoints of a schedule.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CheckpointRecord {
+    /// Last period a checkpoint proposal was added for.
+    pub last_period: Option<u64>,
+    /// Last period attested by approving its checkpoint proposal.
+    pub last_attested_period: Option<u64>,
+    /// Periods whose checkpoint proposals were not approved, or that elapsed without a checkpoint proposal
+    /// since the first one.
+    pub missed_periods: Vec<u64>,
+}
+
+impl Contract {
+    /// Records outcome of finalized checkpoint proposal.
+    pub(crate) fn internal_settle_checkpoint(&mut self, proposal: &Proposal) {
+        if let ProposalKind::Checkpoint { name, period, .. } = &proposal.kind {
+            let mut record = self.checkpoints.get(name).unwrap_or_default();
+            match proposal.status {
+                ProposalStatus::Approved => record.last_attested_period = Some(*period),
+                _ => record.missed_periods.push(*period),
+            }
+            self.checkpoints.insert(name, &record);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds checkpoint proposal for the current period of given schedule, attesting given report hash.
+    /// Only callable by the reporter of the schedule, once per period. No bond is required.
+    pub fn trigger_checkpoint(&mut self, name: String, report_hash: Base58CryptoHash) -> u64 {
+        self.assert_not_read_only();
+        self.assert_not_paused();
+        let policy = self.policy.get().unwrap().to_policy();
+        let schedule = policy
+            .checkpoint_schedules
+            .iter()
+            .find(|schedule| schedule.name == name)
+            .expect("ERR_NO_CHECKPOINT_SCHEDULE");
+        assert_eq!(
+            env::predecessor_account_id(),
+            schedule.reporter_id,
+            "ERR_NOT_CHECKPOINT_REPORTER"
+        );
+        let period = env::block_timestamp() / schedule.interval.0.max(1);
+        let mut record = self.checkpoints.get(&name).unwrap_or_default();
+        if let Some(last) = record.last_period {
+            assert!(period > last, "ERR_CHECKPOINT_NOT_DUE");
+            record.missed_periods.extend(last + 1..period);
+        }
+        record.last_period = Some(period);
+        self.checkpoints.insert(&name, &record);
+
+        let id = self.last_proposal_id;
+        let mut proposal: Proposal = ProposalInput {
+            description: format!("Checkpoint {} for period {}", name, period),
+            kind: ProposalKind::Checkpoint {
+                name,
+                period,
+                report_hash,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        }
+        .into();
+        proposal.bond = U128(0);
+        self.internal_open_or_enqueue(&policy, &mut proposal, id);
+        self.internal_record_transition(id, &proposal.status);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        self.last_proposal_id += 1;
+        id
+    }
+
+    /// Returns history of checkpoints of given schedule.
+    pub fn get_checkpoint_record(&self, name: String) -> CheckpointRecord {
+        self.checkpoints.get(&name).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::Config;
+
+    use super::*;
+
+    const DAY: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+    fn contract_with_schedule(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().checkpoint_schedules = vec![CheckpointSchedule {
+            name: "weekly".to_string(),
+            interval: U64(7 * DAY),
+            reporter_id: accounts(3),
+        }];
+        Contract::new(Config::test_config(), policy)
+    }
+
+    #[test]
+    fn test_checkpoints() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_schedule(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(7 * DAY)
+            .build());
+        let id = contract.trigger_checkpoint("weekly".to_string(), [1; 32].into());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_checkpoint_record("weekly".to_string()),
+            CheckpointRecord {
+                last_period: Some(1),
+                last_attested_period: Some(1),
+                missed_periods: vec![],
+            }
+        );
+
+        // Next checkpoint is not attested before it expires.
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(14 * DAY)
+            .build());
+        let id = contract.trigger_checkpoint("weekly".to_string(), [2; 32].into());
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(22 * DAY)
+            .build());
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract
+                .get_checkpoint_record("weekly".to_string())
+                .missed_periods,
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_skipped_periods() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_schedule(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(7 * DAY)
+            .build());
+        contract.trigger_checkpoint("weekly".to_string(), [1; 32].into());
+        testing_env!(context.block_timestamp(28 * DAY).build());
+        contract.trigger_checkpoint("weekly".to_string(), [4; 32].into());
+        let record = contract.get_checkpoint_record("weekly".to_string());
+        assert_eq!(record.last_period, Some(4));
+        assert_eq!(record.missed_periods, vec![2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED")]
+    fn test_checkpoint_paused() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_schedule(&mut context);
+        contract.paused = true;
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.trigger_checkpoint("weekly".to_string(), [1; 32].into());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CHECKPOINT_NOT_DUE")]
+    fn test_checkpoint_twice_in_period() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_schedule(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.trigger_checkpoint("weekly".to_string(), [1; 32].into());
+        contract.trigger_checkpoint("weekly".to_string(), [1; 32].into());
+    }
+}
@@ -0,0 +1,279 @@
+//! Commit-reveal voting for roles listed in `commit_reveal` of the policy: members of these roles commit
+//! a hash of their vote while voting is open, and reveal it in the last `reveal_period` of voting,
+//! so they can't follow votes of others in contentious decisions.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, CryptoHash};
+
+use crate::*;
+
+/// Roles voting by commit-reveal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommitReveal {
+    pub roles: Vec<String>,
+    /// Last part of the voting period when commitments are revealed, in `period_unit` of the policy.
+    pub reveal_period: U64,
+}
+
+/// Returns commitment to given vote: sha256 of borsh serialized proposal id, account id, vote and salt.
+fn vote_commitment(
+    proposal_id: u64,
+    account_id: &AccountId,
+    vote: &Vote,
+    salt: &str,
+) -> CryptoHash {
+    env::sha256_array(
+        &(
+            proposal_id,
+            account_id.clone(),
+            vote.clone(),
+            salt.to_string(),
+        )
+            .try_to_vec()
+            .unwrap(),
+    )
+}
+
+/// Removes commit-reveal roles from roles of the voter, as they only vote with `reveal_vote`.
+pub(crate) fn filter_commit_reveal_roles(policy: &Policy, roles: &mut Vec<String>) {
+    if let Some(commit_reveal) = &policy.commit_reveal {
+        roles.retain(|role| !commit_reveal.roles.contains(role));
+        assert!(!roles.is_empty(), "ERR_COMMIT_REVEAL_ROLE");
+    }
+}
+
+/// Returns the time when commitments on given proposal start to be revealed, in `period_unit` of the policy.
+fn reveal_opens(policy: &Policy, commit_reveal: &CommitReveal, proposal: &Proposal) -> u64 {
+    policy
+        .voting_end(proposal)
+        .saturating_sub(commit_reveal.reveal_period.0)
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Commits to a vote on given proposal with a commit-reveal role, until the reveal period starts.
+    /// Commitment is sha256 of borsh serialized proposal id, account id of the caller, vote and salt,
+    /// see `get_vote_commitment`. Committing again replaces the previous commitment.
+    pub fn commit_vote(&mut self, id: u64, commitment: Base58CryptoHash) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        let commit_reveal = policy.commit_reveal.as_ref().expect("ERR_NO_COMMIT_REVEAL");
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::InProgress,
+            "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
+        );
+        assert!(
+            policy.now() < reveal_opens(&policy, commit_reveal, &proposal),
+            "ERR_COMMIT_PERIOD_ENDED"
+        );
+        let user = self.internal_voter_info(id, &proposal);
+        assert!(
+            policy.roles.iter().any(|role| {
+                commit_reveal.roles.contains(&role.name)
+                    && role.kind.match_user(&user)
+                    && !policy.is_expired_member(&role.name, &user.account_id)
+            }),
+            "ERR_PERMISSION_DENIED"
+        );
+        assert!(
+            !proposal.votes.contains_key(&user.account_id),
+            "ERR_ALREADY_VOTED"
+        );
+        proposal.commitments.insert(user.account_id, commitment);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+    }
+
+    /// Reveals vote committed on given proposal, in the reveal period at the end of voting.
+    /// The vote counts in commit-reveal roles of the caller that can cast it.
+    pub fn reveal_vote(&mut self, id: u64, vote: Vote, salt: String) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        let commit_reveal = policy.commit_reveal.as_ref().expect("ERR_NO_COMMIT_REVEAL");
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::InProgress,
+            "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
+        );
+        let now = policy.now();
+        assert!(
+            now >= reveal_opens(&policy, commit_reveal, &proposal),
+            "ERR_REVEAL_NOT_OPEN"
+        );
+        assert!(
+            now <= policy.voting_end(&proposal),
+            "ERR_REVEAL_PERIOD_ENDED"
+        );
+        let sender_id = env::predecessor_account_id();
+        let commitment = proposal
+            .commitments
+            .remove(&sender_id)
+            .expect("ERR_NO_COMMITMENT");
+        assert!(
+            commitment == vote_commitment(id, &sender_id, &vote, &salt).into(),
+            "ERR_INVALID_REVEAL"
+        );
+        let (mut roles, allowed) = policy.can_execute_action(
+            self.internal_voter_info(id, &proposal),
+            &proposal.kind,
+            &Action::from(vote.clone()),
+        );
+        roles.retain(|role| commit_reveal.roles.contains(role));
+        assert!(allowed && !roles.is_empty(), "ERR_PERMISSION_DENIED");
+        self.internal_record_approval(&policy, &sender_id, &vote);
+        let counts_before = proposal.vote_counts.clone();
+        proposal.update_votes(
+            &sender_id,
+            &roles,
+            vote,
+            &policy,
+            self.internal_vote_weight(id, &proposal, &sender_id),
+            &[],
+        );
+        self.internal_log_vote(id, &proposal, &sender_id, &roles, &counts_before, None);
+        proposal.status =
+            policy.proposal_status(&proposal, roles, self.internal_total_weight(&proposal));
+        match proposal.status {
+            ProposalStatus::Approved => self.internal_approve_proposal(&policy, &mut proposal, id),
+            ProposalStatus::Rejected => self.internal_reject_by_votes(&policy, &mut proposal, id),
+            ProposalStatus::Removed => {
+                self.internal_remove_spam(&policy, &mut proposal, &sender_id);
+            }
+            _ => {}
+        }
+        let closed = proposal.status != ProposalStatus::InProgress;
+        if closed {
+            self.internal_record_transition(id, &proposal.status);
+        }
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        if closed {
+            self.internal_close_proposal();
+        }
+    }
+
+    /// Returns commitment to given vote of given account on given proposal, for `commit_vote`.
+    pub fn get_vote_commitment(
+        &self,
+        id: u64,
+        account_id: AccountId,
+        vote: Vote,
+        salt: String,
+    ) -> Base58CryptoHash {
+        vote_commitment(id, &account_id, &vote, &salt).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::BondState;
+    use crate::{BondSlash, Config};
+
+    use super::*;
+
+    const DAY: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+    fn contract_with_commit_reveal(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        policy.to_policy_mut().commit_reveal = Some(CommitReveal {
+            roles: vec!["council".to_string()],
+            reveal_period: U64(DAY),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_commit_reveal(&mut context);
+        for account_id in [accounts(1), accounts(2)] {
+            testing_env!(context.predecessor_account_id(account_id.clone()).build());
+            let commitment =
+                contract.get_vote_commitment(0, account_id, Vote::Approve, "salt".to_string());
+            contract.commit_vote(0, commitment);
+        }
+        assert!(contract.get_proposal(0).proposal.vote_counts.is_empty());
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(6 * DAY + 1)
+            .build());
+        contract.reveal_vote(0, Vote::Approve, "salt".to_string());
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::InProgress
+        );
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.reveal_vote(0, Vote::Approve, "salt".to_string());
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_reveal_reject_slashes_bond() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_commit_reveal(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.bond_slash_on_reject = Some(BondSlash::Treasury);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        for account_id in [accounts(1), accounts(2)] {
+            testing_env!(context.predecessor_account_id(account_id.clone()).build());
+            let commitment =
+                contract.get_vote_commitment(0, account_id, Vote::Reject, "salt".to_string());
+            contract.commit_vote(0, commitment);
+        }
+        for account_id in [accounts(1), accounts(2)] {
+            testing_env!(context
+                .predecessor_account_id(account_id)
+                .block_timestamp(6 * DAY + 1)
+                .build());
+            contract.reveal_vote(0, Vote::Reject, "salt".to_string());
+        }
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!(proposal.bond_state, BondState::Forfeited);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_REVEAL")]
+    fn test_reveal_other_vote() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_commit_reveal(&mut context);
+        let commitment =
+            contract.get_vote_commitment(0, accounts(1), Vote::Approve, "salt".to_string());
+        contract.commit_vote(0, commitment);
+        testing_env!(context.block_timestamp(6 * DAY + 1).build());
+        contract.reveal_vote(0, Vote::Reject, "salt".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_COMMIT_REVEAL_ROLE")]
+    fn test_vote_with_commit_reveal_role() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_commit_reveal(&mut context);
+        contract.act_proposal(0, Action::VoteApprove, None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Sub-committees, e.g. a grants committee: `committees` of the policy delegate proposal kinds to a role with its
+//! own vote policy. Votes of the committee decide only on delegated kinds, and transfers it approves on its own
+//! are paid from its budget, so they execute without a vote of the whole DAO as long as the budget covers them.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Role deciding proposals of some kinds on its own, within a spending cap.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Committee {
+    /// Role of the committee. Its vote policy is set on the role as usual.
+    pub role: String,
+    /// Policy labels of proposal kinds delegated to the committee. Its votes don't decide on other kinds.
+    pub kinds: Vec<String>,
+    /// Budget paying transfers approved by the committee alone, topped up with `SetBudget`.
+    pub budget: String,
+}
+
+impl Contract {
+    /// Debits given transfer, including all its recurring payouts, from the budget of the committee that approved
+    /// it on its own. Returns false and doesn't change anything if the budget can't cover it.
+    pub(crate) fn internal_debit_committee_budget(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+    ) -> bool {
+        let (token_id, amount) = match proposal.kind.transfer_total() {
+            Some((token_id, amount)) => (token_id.clone(), amount),
+            None => return true,
+        };
+        let committee =
+            match policy.approving_committee(proposal, self.internal_total_weight(proposal)) {
+                Some(committee) => committee,
+                None => return true,
+            };
+        if !self.internal_debit_budget(&committee.budget, &token_id, amount) {
+            return false;
+        }
+        proposal.committee_budget = Some(committee.budget.clone());
+        true
+    }
+
+    /// Returns given transfer back to the budget of the committee that paid it, e.g. when payout has failed.
+    pub(crate) fn internal_credit_committee_budget(&mut self, proposal: &mut Proposal) {
+        if let (Some(budget), Some((_, amount))) = (
+            proposal.committee_budget.take(),
+            proposal.kind.transfer_total(),
+        ) {
+            self.internal_credit_budget(&budget, amount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Config, RoleKind, RolePermission};
+
+    use super::*;
+
+    fn contract_with_committee(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles.push(RolePermission {
+            name: "grants".to_string(),
+            kind: RoleKind::Group(vec![accounts(3)].into_iter().collect()),
+            permissions: vec!["*:*".to_string()].into_iter().collect(),
+            vote_policy: Default::default(),
+        });
+        policy_mut.committees.push(Committee {
+            role: "grants".to_string(),
+            kinds: vec!["transfer".to_string()],
+            budget: "grants".to_string(),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.budgets.insert(
+            &"grants".to_string(),
+            &Budget {
+                token: OLD_BASE_TOKEN.to_string(),
+                amount: U128(to_yocto("3")),
+            },
+        );
+        contract
+    }
+
+    fn propose(context: &mut VMContextBuilder, contract: &mut Contract, kind: ProposalKind) -> u64 {
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "grant".to_string(),
+            kind,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context.attached_deposit(0).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        id
+    }
+
+    fn transfer(amount: &str) -> ProposalKind {
+        ProposalKind::Transfer {
+            token_id: String::from(OLD_BASE_TOKEN),
+            receiver_id: accounts(4),
+            amount: U128(to_yocto(amount)),
+            msg: None,
+            category: None,
+            batch: None,
+            recurrence: None,
+        }
+    }
+
+    fn budget_left(contract: &Contract) -> Balance {
+        contract.get_budget("grants".to_string()).unwrap().amount.0
+    }
+
+    #[test]
+    fn test_committee_transfer() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_committee(&mut context);
+        let id = propose(&mut context, &mut contract, transfer("2"));
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+        assert_eq!(proposal.committee_budget, Some("grants".to_string()));
+        assert_eq!(budget_left(&contract), to_yocto("1"));
+
+        // Transfer over what is left of the cap waits for a top-up.
+        let over_cap = propose(&mut context, &mut contract, transfer("2"));
+        assert_eq!(
+            contract.get_proposal(over_cap).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert_eq!(budget_left(&contract), to_yocto("1"));
+
+        // Failed payout returns the transfer to the budget.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(budget_left(&contract), to_yocto("3"));
+        assert_eq!(contract.get_proposal(id).proposal.committee_budget, None);
+    }
+
+    #[test]
+    fn test_committee_only_decides_delegated_kinds() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_committee(&mut context);
+        let id = propose(
+            &mut context,
+            &mut contract,
+            ProposalKind::AddMemberToRole {
+                member_id: accounts(4),
+                role: "council".to_string(),
+            },
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        // Transfer approved by the council isn't paid from the committee budget.
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "grant".to_string(),
+            kind: transfer("1"),
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context.attached_deposit(0).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+        assert_eq!(proposal.committee_budget, None);
+        assert_eq!(budget_left(&contract), to_yocto("3"));
+    }
+}
@@ -1,15 +1,136 @@
+use near_sdk::json_types::U128;
+
 use crate::*;
 
+/// Vote of a delegate cast with delegated weight.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegateVote {
+    pub proposal_id: u64,
+    pub vote: Vote,
+    /// Delegated weight of the delegate at the time of the vote.
+    pub weight: U128,
+}
+
+/// Returns balance at the end of the block before `block` in a history of `(block height, balance)` changes
+/// with given length, or `current` if there is no history.
+fn balance_before(
+    len: u64,
+    get: impl Fn(u64) -> (u64, Balance),
+    block: u64,
+    current: Balance,
+) -> Balance {
+    if len == 0 {
+        return current;
+    }
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = (low + high) / 2;
+        if get(mid).0 < block {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    if low == 0 {
+        0
+    } else {
+        get(low - 1).1
+    }
+}
+
 impl Contract {
     pub fn get_user_weight(&self, account_id: &AccountId) -> Balance {
         self.delegations.get(account_id).unwrap_or_default()
     }
+
+    /// Records change of delegated balance of given account, and of the total, in the current block.
+    /// New histories start with the previous balance from block 0, as it didn't change before they were recorded.
+    fn internal_record_delegation(
+        &mut self,
+        account_id: &AccountId,
+        prev_amount: Balance,
+        prev_total: Balance,
+    ) {
+        let block = env::block_height();
+        let mut history = self.delegation_history.get(account_id).unwrap_or_default();
+        if history.is_empty() {
+            history.push((0, prev_amount));
+        }
+        match history.last_mut() {
+            Some(last) if last.0 == block => last.1 = self.get_user_weight(account_id),
+            _ => history.push((block, self.get_user_weight(account_id))),
+        }
+        self.delegation_history.insert(account_id, &history);
+
+        if self.total_delegation_history.is_empty() {
+            self.total_delegation_history.push(&(0, prev_total));
+        }
+        let last_index = self.total_delegation_history.len() - 1;
+        if self.total_delegation_history.get(last_index).unwrap().0 == block {
+            self.total_delegation_history
+                .replace(last_index, &(block, self.total_delegation_amount));
+        } else {
+            self.total_delegation_history
+                .push(&(block, self.total_delegation_amount));
+        }
+    }
+
+    /// Returns delegated balance of given account at the end of the block before given one.
+    pub(crate) fn internal_delegation_before(&self, account_id: &AccountId, block: u64) -> Balance {
+        let history = self.delegation_history.get(account_id).unwrap_or_default();
+        balance_before(
+            history.len() as u64,
+            |index| history[index as usize],
+            block,
+            self.get_user_weight(account_id),
+        )
+    }
+
+    /// Returns total delegated balance at the end of the block before given one.
+    pub(crate) fn internal_total_delegation_before(&self, block: u64) -> Balance {
+        balance_before(
+            self.total_delegation_history.len(),
+            |index| self.total_delegation_history.get(index).unwrap(),
+            block,
+            self.total_delegation_amount,
+        )
+    }
+
+    fn internal_is_in_progress(&self, proposal_id: u64) -> bool {
+        matches!(
+            self.proposals.get(&proposal_id).map(Proposal::from),
+            Some(proposal) if proposal.status == ProposalStatus::InProgress
+        )
+    }
+
+    /// Records vote of given account if it has delegated weight.
+    pub(crate) fn internal_record_delegate_vote(
+        &mut self,
+        account_id: &AccountId,
+        proposal_id: u64,
+        vote: Vote,
+    ) {
+        let weight = self.get_user_weight(account_id);
+        if weight == 0 {
+            return;
+        }
+        let mut record = self.delegate_votes.get(account_id).unwrap_or_default();
+        record.push(DelegateVote {
+            proposal_id,
+            vote,
+            weight: U128(weight),
+        });
+        self.delegate_votes.insert(account_id, &record);
+    }
 }
 
 #[near_bindgen]
 impl Contract {
     #[payable]
     pub fn register_delegation(&mut self, account_id: &AccountId) {
+        self.assert_not_read_only();
         let staking_id = self.staking_id.clone().expect("ERR_NO_STAKING");
         assert_eq!(
             env::predecessor_account_id(),
@@ -23,6 +144,7 @@ impl Contract {
     /// Adds given amount to given account as delegated weight.
     /// Returns previous amount, new amount and total delegated amount.
     pub fn delegate(&mut self, account_id: &AccountId, amount: U128) -> (U128, U128, U128) {
+        self.assert_not_read_only();
         let staking_id = self.staking_id.clone().expect("ERR_NO_STAKING");
         assert_eq!(
             env::predecessor_account_id(),
@@ -36,6 +158,11 @@ impl Contract {
         let new_amount = prev_amount + amount.0;
         self.delegations.insert(account_id, &new_amount);
         self.total_delegation_amount += amount.0;
+        self.internal_record_delegation(
+            account_id,
+            prev_amount,
+            self.total_delegation_amount - amount.0,
+        );
         (
             U128(prev_amount),
             U128(new_amount),
@@ -46,6 +173,7 @@ impl Contract {
     /// Removes given amount from given account's delegations.
     /// Returns previous, new amount of this account and total delegated amount.
     pub fn undelegate(&mut self, account_id: &AccountId, amount: U128) -> (U128, U128, U128) {
+        self.assert_not_read_only();
         let staking_id = self.staking_id.clone().expect("ERR_NO_STAKING");
         assert_eq!(
             env::predecessor_account_id(),
@@ -57,10 +185,121 @@ impl Contract {
         let new_amount = prev_amount - amount.0;
         self.delegations.insert(account_id, &new_amount);
         self.total_delegation_amount -= amount.0;
+        self.internal_record_delegation(
+            account_id,
+            prev_amount,
+            self.total_delegation_amount + amount.0,
+        );
         (
             U128(prev_amount),
             U128(new_amount),
             self.delegation_total_supply(),
         )
     }
+
+    /// Returns votes given delegate cast with delegated weight, oldest first.
+    /// If the policy hides votes until finalization, votes on proposals in progress are skipped.
+    pub fn get_delegate_record(
+        &self,
+        delegate: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<DelegateVote> {
+        let hide_votes = self.internal_hide_votes();
+        self.delegate_votes
+            .get(&delegate)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|record| !hide_votes || !self.internal_is_in_progress(record.proposal_id))
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U64;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::policy::{WeightKind, WeightOrRatio};
+    use crate::Config;
+
+    use super::*;
+
+    fn delegate(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        block: u64,
+        account_id: AccountId,
+        amount: Balance,
+    ) {
+        testing_env!(context
+            .predecessor_account_id(accounts(5))
+            .block_index(block)
+            .attached_deposit(16 * env::storage_byte_cost())
+            .build());
+        if contract.delegations.get(&account_id).is_none() {
+            contract.register_delegation(&account_id);
+        }
+        contract.delegate(&account_id, U128(amount));
+    }
+
+    #[test]
+    fn test_balance_snapshot() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles.push(RolePermission {
+            name: "holders".to_string(),
+            kind: RoleKind::TokenWeight(U128(1)),
+            permissions: vec!["vote:VoteApprove".to_string()].into_iter().collect(),
+            vote_policy: vec![(
+                "vote".to_string(),
+                VotePolicy {
+                    weight_kind: WeightKind::TokenWeight,
+                    quorum: U128(0),
+                    threshold: WeightOrRatio::Ratio(1, 2),
+                    weight_decay: 0,
+                    participation_quorum: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.staking_id = Some(accounts(5));
+
+        delegate(&mut context, &mut contract, 1, accounts(2), 40);
+        delegate(&mut context, &mut contract, 1, accounts(3), 60);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_index(2)
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+
+        // Delegated after the submission, so doesn't count.
+        delegate(&mut context, &mut contract, 3, accounts(2), 100);
+        assert_eq!(
+            contract.delegation_balance_before(accounts(2), U64(2)),
+            U128(40)
+        );
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.vote_counts["holders"][0], 40);
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+    }
 }
@@ -0,0 +1,157 @@
+//! Per-epoch digests of proposal status transitions, so light clients and bridges can verify decisions of the DAO
+//! with a merkle proof against the digest instead of trusting an indexer.
+//! Tree is built the same way as weight snapshots, see `snapshot_node`; unpaired nodes are carried to the next level.
+
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{near_bindgen, CryptoHash};
+
+use crate::snapshot::snapshot_node;
+use crate::*;
+
+/// Leaf of the digest for transition of given proposal to given status, at given block height:
+/// sha256 of borsh serialized account of this DAO, proposal id, status and block height.
+pub fn transition_leaf(proposal_id: u64, status: &ProposalStatus, block_height: u64) -> CryptoHash {
+    env::sha256_array(
+        &(env::current_account_id(), proposal_id, status, block_height)
+            .try_to_vec()
+            .unwrap(),
+    )
+}
+
+/// Levels of the merkle tree over given leaves, from the leaves up to the root.
+fn digest_levels(leaves: Vec<CryptoHash>) -> Vec<Vec<CryptoHash>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let level = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => snapshot_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(level);
+    }
+    levels
+}
+
+impl Contract {
+    /// Adds transition of given proposal to given status to the digest of the current epoch.
+    pub(crate) fn internal_record_transition(&mut self, proposal_id: u64, status: &ProposalStatus) {
+        let epoch = env::epoch_height();
+        let mut leaves = self.proposal_digests.get(&epoch).unwrap_or_default();
+        leaves.push(transition_leaf(proposal_id, status, env::block_height()));
+        self.proposal_digests.insert(&epoch, &leaves);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the digest of proposal transitions in given epoch, if there were any.
+    pub fn get_proposal_digest(&self, epoch: u64) -> Option<Base58CryptoHash> {
+        let leaves = self.proposal_digests.get(&epoch)?;
+        let levels = digest_levels(leaves);
+        levels.last().unwrap().first().map(|root| (*root).into())
+    }
+
+    /// Returns leaves of the digest of given epoch, in order of transitions.
+    pub fn get_digest_leaves(&self, epoch: u64) -> Vec<Base58CryptoHash> {
+        self.proposal_digests
+            .get(&epoch)
+            .unwrap_or_default()
+            .into_iter()
+            .map(Base58CryptoHash::from)
+            .collect()
+    }
+
+    /// Returns proof of inclusion of the leaf with given index in the digest of given epoch:
+    /// hashes of siblings from the leaf up to the root.
+    pub fn get_digest_proof(&self, epoch: u64, index: u64) -> Vec<Base58CryptoHash> {
+        let leaves = self.proposal_digests.get(&epoch).expect("ERR_NO_DIGEST");
+        assert!((index as usize) < leaves.len(), "ERR_NO_DIGEST_LEAF");
+        let mut index = index as usize;
+        let mut proof = vec![];
+        for level in digest_levels(leaves).iter() {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push((*sibling).into());
+            }
+            index /= 2;
+        }
+        proof
+    }
+
+    /// Verifies that transition of given proposal to given status at given block height is included
+    /// in the digest of given epoch.
+    pub fn verify_digest_proof(
+        &self,
+        epoch: u64,
+        proposal_id: u64,
+        status: ProposalStatus,
+        block_height: u64,
+        proof: Vec<Base58CryptoHash>,
+    ) -> bool {
+        let root: CryptoHash = match self.get_proposal_digest(epoch) {
+            Some(root) => root.into(),
+            None => return false,
+        };
+        let computed = proof.into_iter().fold(
+            transition_leaf(proposal_id, &status, block_height),
+            |node, sibling| snapshot_node(&node, &sibling.into()),
+        );
+        computed == root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_proposal_digest() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .epoch_height(5)
+            .block_index(10)
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        for _ in 0..2 {
+            let id = contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+                idempotency_key: None,
+                depends_on: None,
+            });
+            contract.act_proposal(id, Action::VoteApprove, None);
+        }
+        // Each proposal was added and approved.
+        assert_eq!(contract.get_digest_leaves(5).len(), 4);
+        assert!(contract.get_proposal_digest(4).is_none());
+
+        for (index, (id, status)) in [
+            (0, ProposalStatus::InProgress),
+            (0, ProposalStatus::Approved),
+            (1, ProposalStatus::InProgress),
+            (1, ProposalStatus::Approved),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let proof = contract.get_digest_proof(5, index as u64);
+            assert!(contract.verify_digest_proof(5, *id, status.clone(), 10, proof.clone()));
+            assert!(!contract.verify_digest_proof(5, *id, ProposalStatus::Rejected, 10, proof));
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Emergency multisig of the council: last-resort recovery when the proposal pipeline is wedged,
+//! e.g. by a bad policy. K of N registered ed25519 keys sign a proposal kind which is then executed directly.
+
+use std::convert::TryFrom;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, CryptoHash, CurveType, PublicKey};
+
+use crate::*;
+
+/// Keys allowed to sign emergency executions, and number of signatures required.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmergencySigners {
+    pub public_keys: Vec<PublicKey>,
+    pub threshold: u32,
+}
+
+/// Signature of the emergency message by one of the emergency signers.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EmergencySignature {
+    pub public_key: PublicKey,
+    pub signature: Base64VecU8,
+}
+
+/// Verifies ed25519 `signature` of `message` by `public_key`.
+pub(crate) fn verify_ed25519(public_key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    if public_key.curve_type() != CurveType::ED25519 {
+        return false;
+    }
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..]) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => public_key.verify_strict(message, &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+impl Contract {
+    /// Message emergency signers sign to execute given proposal kind: sha256 of borsh serialized
+    /// account of this DAO, current emergency nonce and the kind.
+    fn internal_emergency_message(&self, kind: &ProposalKind) -> CryptoHash {
+        env::sha256_array(
+            &(env::current_account_id(), self.emergency_nonce, kind)
+                .try_to_vec()
+                .unwrap(),
+        )
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns emergency signers set by `SetEmergencySigners` proposal.
+    pub fn export_emergency_signers(&self) -> Option<EmergencySigners> {
+        self.emergency_signers.clone()
+    }
+
+    /// Returns the message emergency signers need to sign to execute given proposal kind.
+    pub fn get_emergency_message(&self, kind: ProposalKind) -> Base58CryptoHash {
+        self.internal_emergency_message(&kind).into()
+    }
+
+    /// Executes given proposal kind without voting, if signed by enough emergency signers.
+    /// Execution is recorded as an approved proposal, and returns its id.
+    pub fn execute_emergency(
+        &mut self,
+        kind: ProposalKind,
+        signatures: Vec<EmergencySignature>,
+    ) -> u64 {
+        self.assert_not_read_only();
+        let signers = self
+            .emergency_signers
+            .clone()
+            .expect("ERR_NO_EMERGENCY_SIGNERS");
+        let message = self.internal_emergency_message(&kind);
+        let mut signed: Vec<PublicKey> = vec![];
+        for signature in signatures {
+            assert!(
+                signers.public_keys.contains(&signature.public_key),
+                "ERR_NOT_EMERGENCY_SIGNER"
+            );
+            assert!(
+                verify_ed25519(&signature.public_key, &message, &signature.signature.0),
+                "ERR_INVALID_SIGNATURE"
+            );
+            if !signed.contains(&signature.public_key) {
+                signed.push(signature.public_key);
+            }
+        }
+        assert!(
+            signed.len() >= signers.threshold as usize,
+            "ERR_NOT_ENOUGH_SIGNATURES"
+        );
+        self.emergency_nonce += 1;
+
+        let policy = self.policy.get().unwrap().to_policy();
+        self.internal_validate_proposal(&policy, &kind);
+        let id = self.last_proposal_id;
+        let mut proposal: Proposal = ProposalInput {
+            description: "Emergency execution".to_string(),
+            kind,
+            idempotency_key: None,
+            depends_on: None,
+        }
+        .into();
+        proposal.bond = U128(0);
+        proposal.status = ProposalStatus::Approved;
+        self.last_proposal_id += 1;
+        self.internal_execute_proposal(&policy, &mut proposal, id);
+        self.internal_record_transition(id, &proposal.status);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    fn near_public_key(keypair: &Keypair) -> PublicKey {
+        let mut bytes = vec![CurveType::ED25519 as u8];
+        bytes.extend_from_slice(keypair.public.as_bytes());
+        PublicKey::try_from(bytes).unwrap()
+    }
+
+    fn sign(contract: &Contract, keypair: &Keypair, kind: &ProposalKind) -> EmergencySignature {
+        let message: CryptoHash = contract.get_emergency_message(kind.clone()).into();
+        EmergencySignature {
+            public_key: near_public_key(keypair),
+            signature: Base64VecU8(keypair.sign(&message).to_bytes().to_vec()),
+        }
+    }
+
+    fn contract_with_signers(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.emergency_signers = Some(EmergencySigners {
+            public_keys: (1..4).map(|seed| near_public_key(&keypair(seed))).collect(),
+            threshold: 2,
+        });
+        contract
+    }
+
+    #[test]
+    fn test_execute_emergency() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_signers(&mut context);
+        let kind = ProposalKind::ChangePolicy {
+            policy: VersionedPolicy::Default(vec![accounts(2)]).upgrade(),
+        };
+        let signatures = vec![
+            sign(&contract, &keypair(1), &kind),
+            sign(&contract, &keypair(3), &kind),
+        ];
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        let id = contract.execute_emergency(kind, signatures);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert!(contract
+            .get_policy()
+            .is_group_member("council", &accounts(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_EMERGENCY_THRESHOLD")]
+    fn test_set_emergency_signers_invalid_threshold() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.add_proposal(ProposalInput {
+            description: "signers".to_string(),
+            kind: ProposalKind::SetEmergencySigners {
+                emergency_signers: EmergencySigners {
+                    public_keys: vec![near_public_key(&keypair(1))],
+                    threshold: 2,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_SIGNATURES")]
+    fn test_execute_emergency_duplicate_signature() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_signers(&mut context);
+        let kind = ProposalKind::Vote;
+        let signatures = vec![
+            sign(&contract, &keypair(1), &kind),
+            sign(&contract, &keypair(1), &kind),
+        ];
+        contract.execute_emergency(kind, signatures);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_SIGNATURE")]
+    fn test_execute_emergency_replayed() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_signers(&mut context);
+        let kind = ProposalKind::Vote;
+        let signatures = || {
+            vec![
+                sign(&contract, &keypair(1), &kind),
+                sign(&contract, &keypair(2), &kind),
+            ]
+        };
+        let first = signatures();
+        let replayed = signatures();
+        contract.execute_emergency(kind.clone(), first);
+        contract.execute_emergency(kind, replayed);
+    }
+}
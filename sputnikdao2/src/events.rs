@@ -0,0 +1,152 @@
+//! Events logged for votes as `VOTE:<json>`, one per role the vote counts in, so indexers can compute
+//! participation and remaining thresholds per role straight from the event stream.
+
+use std::collections::HashMap;
+
+use near_sdk::json_types::U128;
+use near_sdk::serde::Serialize;
+use near_sdk::{log, serde_json, AccountId, Balance};
+
+use crate::*;
+
+/// Vote counted in one role of the voter.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteEvent {
+    pub proposal_id: u64,
+    pub account_id: AccountId,
+    pub role: String,
+    pub vote: Vote,
+    /// Weight the vote added to the count of the role, including votes of delegators.
+    pub weight: U128,
+    /// Count of the vote in the role after voting.
+    pub count: U128,
+    /// Threshold of the role, None for roles that don't provide a total size.
+    pub threshold: Option<U128>,
+    /// Weight still needed for the vote to reach the threshold and the quorum of the role.
+    pub remaining: Option<U128>,
+    /// Memo of the action.
+    pub memo: Option<String>,
+}
+
+impl Contract {
+    /// Logs vote of given account on given proposal in each of given roles, comparing counts with `counts_before`.
+    /// Nothing is logged for private proposals, nor while the policy hides votes until proposals are finalized.
+    pub(crate) fn internal_log_vote(
+        &self,
+        proposal_id: u64,
+        proposal: &Proposal,
+        account_id: &AccountId,
+        roles: &[String],
+        counts_before: &HashMap<String, [Balance; 4]>,
+        memo: Option<&String>,
+    ) {
+        let policy = self.policy.get().unwrap().to_policy();
+        if proposal.encrypted_details.is_some() || policy.hide_votes_until_finalized {
+            return;
+        }
+        let vote = match proposal.votes.get(account_id) {
+            Some(vote) => vote.clone(),
+            None => return,
+        };
+        let total_supply = self.internal_total_weight(proposal);
+        for role in roles {
+            let role_info = match policy.roles.iter().find(|r| &r.name == role) {
+                Some(role_info) => role_info,
+                None => continue,
+            };
+            let counts = proposal.vote_counts.get(role).unwrap_or(&[0u128; 4]);
+            let count = counts[vote.clone() as usize];
+            let before = counts_before.get(role).unwrap_or(&[0u128; 4])[vote.clone() as usize];
+            let role_threshold = policy.proposal_role_threshold(role_info, proposal, total_supply);
+            let threshold = role_threshold
+                .as_ref()
+                .map(|role_threshold| role_threshold.threshold);
+            // Abstentions only close the gap to the quorum.
+            let remaining = role_threshold.map(|role_threshold| {
+                let participation: Balance = counts.iter().sum();
+                U128(std::cmp::max(
+                    role_threshold.threshold.0.saturating_sub(count),
+                    role_threshold.quorum.0.saturating_sub(participation),
+                ))
+            });
+            let event = VoteEvent {
+                proposal_id,
+                account_id: account_id.clone(),
+                role: role.clone(),
+                vote: vote.clone(),
+                weight: U128(count - before),
+                count: U128(count),
+                threshold,
+                remaining,
+                memo: memo.cloned(),
+            };
+            log!("VOTE:{}", serde_json::to_string(&event).unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_vote_events() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteReject, Some("not now".to_string()));
+        let event = VoteEvent {
+            proposal_id: id,
+            account_id: accounts(1),
+            role: "council".to_string(),
+            vote: Vote::Reject,
+            weight: U128(1),
+            count: U128(1),
+            threshold: Some(U128(2)),
+            remaining: Some(U128(1)),
+            memo: Some("not now".to_string()),
+        };
+        assert!(get_logs().contains(&format!("VOTE:{}", serde_json::to_string(&event).unwrap())));
+    }
+
+    #[test]
+    fn test_vote_events_hidden() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        policy.to_policy_mut().hide_votes_until_finalized = true;
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert!(!get_logs().iter().any(|log| log.starts_with("VOTE:")));
+    }
+}
@@ -0,0 +1,176 @@
+//! Adapter for governing contracts on EVM chains through a bridge configured in the policy.
+//! Approved `EvmCall` proposals are emitted as decisions the EVM governor of the bridge executes,
+//! and the bridge attests the execution back with a receipt.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{log, near_bindgen, serde_json, AccountId};
+
+use crate::*;
+
+/// Bridge relaying decisions of the DAO to EVM chains.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvmBridge {
+    /// Account of the bridge, the only one that can attest execution receipts.
+    pub bridge_id: AccountId,
+    /// Ids of EVM chains the bridge relays to.
+    pub chain_ids: Vec<u64>,
+}
+
+/// Execution of the decision on the EVM chain, attested by the bridge.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvmReceipt {
+    /// Hash of the executing transaction on the EVM chain, 0x prefixed hex.
+    pub tx_hash: String,
+    pub success: bool,
+}
+
+/// Decision on an `EvmCall` proposal, in the format consumed by the EVM governor.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EvmDecision {
+    pub proposal_id: u64,
+    pub chain_id: u64,
+    /// Address of the called contract, 0x prefixed hex.
+    pub target: String,
+    /// Keccak256 of the calldata, 0x prefixed hex.
+    pub calldata_hash: String,
+    pub result: ProposalStatus,
+    pub receipt: Option<EvmReceipt>,
+}
+
+/// Checks that given string is a 0x prefixed hex EVM address.
+pub(crate) fn is_evm_address(address: &str) -> bool {
+    match address.strip_prefix("0x") {
+        Some(hex) => hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => false,
+    }
+}
+
+impl Contract {
+    /// Records decision of approved `EvmCall` proposal and emits it for the bridge.
+    pub(crate) fn internal_emit_evm_decision(
+        &mut self,
+        proposal_id: u64,
+        chain_id: u64,
+        target: &str,
+        calldata: &Base64VecU8,
+    ) {
+        let decision = EvmDecision {
+            proposal_id,
+            chain_id,
+            target: target.to_string(),
+            calldata_hash: format!("0x{}", hex::encode(env::keccak256(&calldata.0))),
+            result: ProposalStatus::Approved,
+            receipt: None,
+        };
+        log!("EVM_DECISION:{}", serde_json::to_string(&decision).unwrap());
+        self.evm_decisions.insert(&proposal_id, &decision);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Attests execution of the decision on given proposal on the EVM chain. Only callable by the bridge of the policy.
+    pub fn submit_evm_receipt(&mut self, proposal_id: u64, receipt: EvmReceipt) {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let bridge = policy.evm_bridge.expect("ERR_NO_EVM_BRIDGE");
+        assert_eq!(
+            env::predecessor_account_id(),
+            bridge.bridge_id,
+            "ERR_NOT_EVM_BRIDGE"
+        );
+        let mut decision = self
+            .evm_decisions
+            .get(&proposal_id)
+            .expect("ERR_NO_EVM_DECISION");
+        assert!(decision.receipt.is_none(), "ERR_EVM_RECEIPT_EXISTS");
+        decision.receipt = Some(receipt);
+        self.evm_decisions.insert(&proposal_id, &decision);
+    }
+
+    /// Returns decision on given `EvmCall` proposal, with receipt once attested.
+    pub fn get_evm_decision(&self, proposal_id: u64) -> Option<EvmDecision> {
+        self.evm_decisions.get(&proposal_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    const TARGET: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn contract_with_decision(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().evm_bridge = Some(EvmBridge {
+            bridge_id: accounts(3),
+            chain_ids: vec![1],
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::EvmCall {
+                chain_id: 1,
+                target: TARGET.to_string(),
+                calldata: Base64VecU8(vec![1, 2, 3]),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract
+    }
+
+    #[test]
+    fn test_evm_decision() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_decision(&mut context);
+        let decision = contract.get_evm_decision(0).unwrap();
+        assert_eq!(decision.target, TARGET);
+        assert_eq!(
+            decision.calldata_hash,
+            format!("0x{}", hex::encode(env::keccak256(&[1, 2, 3])))
+        );
+        assert_eq!(decision.result, ProposalStatus::Approved);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let receipt = EvmReceipt {
+            tx_hash: "0x01".to_string(),
+            success: true,
+        };
+        contract.submit_evm_receipt(0, receipt.clone());
+        assert_eq!(contract.get_evm_decision(0).unwrap().receipt, Some(receipt));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_EVM_BRIDGE")]
+    fn test_evm_receipt_not_bridge() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_decision(&mut context);
+        contract.submit_evm_receipt(
+            0,
+            EvmReceipt {
+                tx_hash: "0x01".to_string(),
+                success: true,
+            },
+        );
+    }
+}
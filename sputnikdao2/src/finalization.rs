@@ -0,0 +1,118 @@
+//! Permissionless finalization of expired proposals, so cron services (e.g. Croncat) can close them
+//! instead of members calling `Finalize` on each one.
+
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+/// Returns true if voting on given proposal ended without a decision, so it can be finalized as expired.
+fn is_due(policy: &Policy, proposal: &Proposal) -> bool {
+    matches!(
+        proposal.status,
+        ProposalStatus::InProgress | ProposalStatus::Signaling | ProposalStatus::AwaitingSponsor
+    ) && policy.voting_end(proposal) < policy.now()
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Finalizes up to `limit` expired proposals, oldest first, returning their ids. Callable by anyone.
+    /// Bonds are returned as with `Finalize`, and the caller is paid `finalizer_reward` of the policy for each.
+    pub fn finalize_due_proposals(&mut self, limit: u64) -> Vec<u64> {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let finalizer_id = env::predecessor_account_id();
+        let mut finalized = vec![];
+        for id in 0..self.last_proposal_id {
+            if finalized.len() as u64 >= limit {
+                break;
+            }
+            let mut proposal: Proposal = match self.proposals.get(&id) {
+                Some(proposal) => proposal.into(),
+                None => continue,
+            };
+            if !is_due(&policy, &proposal) {
+                continue;
+            }
+            let was_open = proposal.status == ProposalStatus::InProgress;
+            proposal.status = ProposalStatus::Expired;
+            self.internal_reject_proposal(&mut proposal, true);
+            self.internal_reward_finalizer(&policy, &mut proposal, &finalizer_id);
+            self.internal_record_transition(id, &proposal.status);
+            self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+            if was_open {
+                self.internal_close_proposal();
+            }
+            finalized.push(id);
+        }
+        finalized
+    }
+
+    /// Get ids of expired proposals that `finalize_due_proposals` would finalize, in paginated view.
+    pub fn get_due_proposals(&self, from_index: u64, limit: u64) -> Vec<u64> {
+        let policy = self.policy.get().unwrap().to_policy();
+        (from_index..std::cmp::min(self.last_proposal_id, from_index + limit))
+            .filter(|id| match self.proposals.get(id) {
+                Some(proposal) => is_due(&policy, &proposal.into()),
+                None => false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_finalize_due_proposals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        for _ in 0..3 {
+            testing_env!(context.attached_deposit(to_yocto("1")).build());
+            contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: String::from(OLD_BASE_TOKEN),
+                    receiver_id: accounts(2),
+                    amount: U128(to_yocto("1")),
+                    msg: None,
+                    category: None,
+                    batch: None,
+                    recurrence: None,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            });
+        }
+        testing_env!(context.attached_deposit(0).build());
+        contract.act_proposal(1, Action::VoteReject, None);
+        let period = contract.get_policy().proposal_period.0;
+        assert!(contract.get_due_proposals(0, 10).is_empty());
+
+        // Anyone can finalize expired proposals.
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(period + 1)
+            .build());
+        assert_eq!(contract.get_due_proposals(0, 10), vec![0, 2]);
+        assert_eq!(contract.finalize_due_proposals(1), vec![0]);
+        assert_eq!(contract.get_due_proposals(0, 10), vec![2]);
+        assert_eq!(contract.finalize_due_proposals(10), vec![2]);
+        assert_eq!(
+            contract.get_proposal(2).proposal.status,
+            ProposalStatus::Expired
+        );
+        assert_eq!(contract.locked_amount, 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Composite health metrics of the DAO, computed on-chain over its latest proposals, so ecosystems ranking
+//! DAOs can read them trustlessly.
+
+use std::collections::HashSet;
+
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::types::convert_old_to_new_token;
+use crate::*;
+
+/// Number of latest proposals the health metrics are computed over.
+const HEALTH_PROPOSALS: u64 = 20;
+
+/// Health of the DAO over its latest proposals.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct HealthMetrics {
+    /// Number of proposals the metrics are computed over.
+    pub proposals: u64,
+    /// Votes cast on these proposals, in basis points of the votes all members of group roles could cast.
+    pub participation_bps: u32,
+    /// Average time from submission to execution of executed proposals, None if none was executed.
+    pub avg_time_to_execute: Option<U64>,
+    /// $NEAR sent by executed `Transfer` proposals.
+    pub spent: U128,
+    /// Time the available $NEAR lasts at the rate it was spent since the submission of the first proposal,
+    /// None if nothing was spent.
+    pub runway: Option<U64>,
+    /// Members of the `council` role who voted on at least one proposal, in basis points of the role.
+    /// None if the policy has no `council` group.
+    pub council_activity_bps: Option<u32>,
+}
+
+/// Returns `part` in basis points of `total`, zero if `total` is zero.
+fn to_bps(part: u128, total: u128) -> u32 {
+    (part * 10_000).checked_div(total).unwrap_or_default() as u32
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns health metrics computed over the latest proposals of the DAO.
+    pub fn get_health_metrics(&self) -> HealthMetrics {
+        let policy = self.policy.get().unwrap().to_policy();
+        let members: HashSet<&AccountId> = policy
+            .roles
+            .iter()
+            .filter_map(|role| role.kind.get_group_members())
+            .flatten()
+            .collect();
+        let council = policy
+            .roles
+            .iter()
+            .find(|role| role.name == "council")
+            .and_then(|role| role.kind.get_group_members());
+        let from_index = self.last_proposal_id.saturating_sub(HEALTH_PROPOSALS);
+        let mut proposals = 0;
+        let mut votes = 0;
+        let mut executed = 0;
+        let mut time_to_execute = 0;
+        let mut spent = 0;
+        let mut first_submission = None;
+        let mut active_council = HashSet::new();
+        for id in from_index..self.last_proposal_id {
+            let proposal: Proposal = match self.proposals.get(&id) {
+                Some(proposal) => proposal.into(),
+                None => continue,
+            };
+            proposals += 1;
+            first_submission.get_or_insert(proposal.submission_time.0);
+            for account_id in proposal.votes.keys().chain(proposal.poll_votes.keys()) {
+                if members.contains(account_id) {
+                    votes += 1;
+                }
+                if matches!(&council, Some(council) if council.contains(account_id)) {
+                    active_council.insert(account_id.clone());
+                }
+            }
+            let executed_at = match proposal.executed_at {
+                Some(executed_at) => executed_at.0,
+                None => continue,
+            };
+            executed += 1;
+            time_to_execute += executed_at.saturating_sub(proposal.submission_time.0);
+            if let ProposalKind::Transfer {
+                token_id, amount, ..
+            } = &proposal.kind
+            {
+                if convert_old_to_new_token(token_id).is_none() {
+                    spent += amount.0;
+                }
+            }
+        }
+        let elapsed = env::block_timestamp().saturating_sub(first_submission.unwrap_or_default());
+        // Spending rate per second, so it doesn't overflow.
+        let spent_per_second = spent * 1_000_000_000 / elapsed.max(1) as u128;
+        let runway = self
+            .get_available_amount()
+            .0
+            .checked_div(spent_per_second)
+            .map(|seconds| U64((seconds as u64).saturating_mul(1_000_000_000)));
+        HealthMetrics {
+            proposals,
+            participation_bps: to_bps(votes, members.len() as u128 * proposals as u128),
+            avg_time_to_execute: time_to_execute.checked_div(executed).map(U64),
+            spent: U128(spent),
+            runway,
+            council_activity_bps: council
+                .map(|council| to_bps(active_council.len() as u128, council.len() as u128)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_health_metrics() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        for _ in 0..2 {
+            contract.add_proposal(ProposalInput {
+                description: "grant".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: String::from(OLD_BASE_TOKEN),
+                    receiver_id: accounts(3),
+                    amount: U128(to_yocto("10")),
+                    msg: None,
+                    category: None,
+                    batch: None,
+                    recurrence: None,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            });
+        }
+        testing_env!(context
+            .block_timestamp(1_000_000_000)
+            .account_balance(to_yocto("1000"))
+            .build());
+        contract.act_proposal(0, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(0, Action::VoteApprove, None);
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(0);
+        let metrics = contract.get_health_metrics();
+        assert_eq!(metrics.proposals, 2);
+        // 2 of the 4 votes both members could cast.
+        assert_eq!(metrics.participation_bps, 5_000);
+        assert_eq!(metrics.avg_time_to_execute, Some(U64(1_000_000_000)));
+        assert_eq!(metrics.spent, U128(to_yocto("10")));
+        assert!(metrics.runway.is_some());
+        assert_eq!(metrics.council_activity_bps, Some(10_000));
+    }
+}
@@ -0,0 +1,45 @@
+//! Idempotency keys of proposals, protecting automated integrations from creating duplicates on retries.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::Promise;
+
+use crate::*;
+
+/// Time during which resubmission with the same idempotency key returns the existing proposal.
+pub const IDEMPOTENCY_WINDOW: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+/// Proposal added with given idempotency key.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct IdempotentProposal {
+    pub proposal_id: u64,
+    pub submission_time: U64,
+}
+
+impl Contract {
+    /// Returns proposal previously added by the caller with given idempotency key within the window.
+    /// Deposit attached to the resubmission is refunded, as no new bond is locked.
+    pub(crate) fn internal_resubmitted_proposal(&self, key: &str) -> Option<u64> {
+        let entry = self
+            .idempotency_keys
+            .get(&(env::predecessor_account_id(), key.to_string()))?;
+        if env::block_timestamp() > entry.submission_time.0 + IDEMPOTENCY_WINDOW {
+            return None;
+        }
+        if env::attached_deposit() > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(env::attached_deposit());
+        }
+        Some(entry.proposal_id)
+    }
+
+    /// Records idempotency key of the proposal added by the caller.
+    pub(crate) fn internal_record_idempotency_key(&mut self, key: String, proposal_id: u64) {
+        self.idempotency_keys.insert(
+            &(env::predecessor_account_id(), key),
+            &IdempotentProposal {
+                proposal_id,
+                submission_time: U64::from(env::block_timestamp()),
+            },
+        );
+    }
+}
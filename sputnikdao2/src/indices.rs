@@ -0,0 +1,116 @@
+//! Indices of proposal ids by a key, stored one entry per (key, proposal) so that adding or removing
+//! a proposal writes a constant amount of storage regardless of how many proposals share the key.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::IntoStorageKey;
+
+/// Set of proposal ids per key. Ids of a key are kept densely by position, removal moves the last id
+/// of the key into the freed position.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ProposalIndex<K: BorshSerialize> {
+    /// Proposal id per key and position.
+    ids: LookupMap<(K, u64), u64>,
+    /// Position per key and proposal id.
+    positions: LookupMap<(K, u64), u64>,
+    /// Number of ids per key.
+    lens: LookupMap<K, u64>,
+}
+
+impl<K: BorshSerialize + BorshDeserialize + Clone> ProposalIndex<K> {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        let prefix = prefix.into_storage_key();
+        let sub_prefix = |suffix: u8| {
+            let mut key = prefix.clone();
+            key.push(suffix);
+            key
+        };
+        Self {
+            ids: LookupMap::new(sub_prefix(b'i')),
+            positions: LookupMap::new(sub_prefix(b'p')),
+            lens: LookupMap::new(sub_prefix(b'n')),
+        }
+    }
+
+    /// Number of proposals under given key.
+    pub fn len(&self, key: &K) -> u64 {
+        self.lens.get(key).unwrap_or_default()
+    }
+
+    /// Adds given proposal under given key, if it's not there yet.
+    pub fn insert(&mut self, key: &K, id: u64) {
+        if self.positions.contains_key(&(key.clone(), id)) {
+            return;
+        }
+        let len = self.len(key);
+        self.ids.insert(&(key.clone(), len), &id);
+        self.positions.insert(&(key.clone(), id), &len);
+        self.lens.insert(key, &(len + 1));
+    }
+
+    /// Removes given proposal from given key, if it's there.
+    pub fn remove(&mut self, key: &K, id: u64) {
+        let position = match self.positions.remove(&(key.clone(), id)) {
+            Some(position) => position,
+            None => return,
+        };
+        let last = self.len(key) - 1;
+        let last_id = self
+            .ids
+            .remove(&(key.clone(), last))
+            .expect("ERR_CORRUPTED_INDEX");
+        if position != last {
+            self.ids.insert(&(key.clone(), position), &last_id);
+            self.positions.insert(&(key.clone(), last_id), &position);
+        }
+        if last == 0 {
+            self.lens.remove(key);
+        } else {
+            self.lens.insert(key, &last);
+        }
+    }
+
+    /// Up to `limit` proposal ids under given key, starting from position `from_index`.
+    pub fn page(&self, key: &K, from_index: u64, limit: u64) -> Vec<u64> {
+        let len = self.len(key);
+        let start = from_index.min(len);
+        (start..len.min(start.saturating_add(limit)))
+            .filter_map(|position| self.ids.get(&(key.clone(), position)))
+            .collect()
+    }
+
+    /// All proposal ids under given key.
+    pub fn all(&self, key: &K) -> Vec<u64> {
+        self.page(key, 0, self.len(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_proposal_index() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut index: ProposalIndex<u8> = ProposalIndex::new(b"t".to_vec());
+        for id in 0..4 {
+            index.insert(&1, id);
+        }
+        index.insert(&1, 2);
+        index.insert(&2, 9);
+        assert_eq!(index.all(&1), vec![0, 1, 2, 3]);
+        index.remove(&1, 1);
+        assert_eq!(index.all(&1), vec![0, 3, 2]);
+        assert_eq!(index.page(&1, 1, 1), vec![3]);
+        index.remove(&1, 7);
+        index.remove(&1, 2);
+        index.remove(&1, 0);
+        index.remove(&1, 3);
+        assert_eq!(index.len(&1), 0);
+        assert!(index.all(&1).is_empty());
+        assert_eq!(index.all(&2), vec![9]);
+    }
+}
@@ -0,0 +1,128 @@
+//! Roles of protocol integrations: members of `RoleKind::Contracts` roles are contracts, not humans, with permissions
+//! scoped to specific actions. A contract is added to such role only once it confirms the grant with `dao_role_granted`,
+//! which accounts without a contract can't do.
+
+use near_sdk::{ext_contract, AccountId, Gas, Promise};
+
+use crate::policy::{RoleKind, RolePermission};
+use crate::types::require;
+
+const GAS_FOR_DAO_ROLE_GRANTED: Gas = Gas(10_000_000_000_000);
+
+#[ext_contract(ext_integration)]
+pub trait Integration {
+    /// Confirms that the integration contract accepts given role in the calling DAO.
+    fn dao_role_granted(&mut self, role: String);
+}
+
+/// Asks `member_id` to confirm grant of given contract role. Member is added on success of the returned promise.
+pub(crate) fn internal_grant_contract_role(member_id: &AccountId, role: &str) -> Promise {
+    ext_integration::dao_role_granted(
+        role.to_string(),
+        member_id.clone(),
+        0,
+        GAS_FOR_DAO_ROLE_GRANTED,
+    )
+}
+
+/// Checks that contract roles only have permissions for specific actions, e.g. `*:Finalize` but not `bounty:*`.
+pub(crate) fn check_scoped_role(role: &RolePermission) -> Result<(), &'static str> {
+    match role.kind {
+        RoleKind::Contracts(_) => require(
+            role.permissions
+                .iter()
+                .all(|permission| !permission.ends_with(":*")),
+            "ERR_CONTRACT_ROLE_NOT_SCOPED",
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+    use near_sdk_sim::to_yocto;
+
+    use crate::policy::UserInfo;
+    use crate::*;
+
+    fn contract_with_bots_role(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles.push(RolePermission {
+            name: "bots".to_string(),
+            kind: RoleKind::Contracts(HashSet::default()),
+            permissions: vec!["*:Finalize".to_string()].into_iter().collect(),
+            vote_policy: HashMap::default(),
+        });
+        Contract::new(Config::test_config(), policy)
+    }
+
+    #[test]
+    fn test_add_contract_to_role() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_bots_role(&mut context);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(4),
+                role: "bots".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+        // Not added until the contract confirms.
+        assert_eq!(contract.get_policy().roles[2].kind.get_role_size(), Some(0));
+
+        // The contract confirmed the grant.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(id);
+        let policy = contract.get_policy();
+        let (roles, allowed) = policy.can_execute_action(
+            UserInfo {
+                account_id: accounts(4),
+                amount: 0,
+            },
+            &ProposalKind::Vote,
+            &Action::Finalize,
+        );
+        assert!(allowed);
+        assert_eq!(roles, vec!["bots".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CONTRACT_ROLE_NOT_SCOPED")]
+    fn test_contract_role_not_scoped() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_bots_role(&mut context);
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicyAddOrUpdateRole {
+                role: RolePermission {
+                    name: "bots".to_string(),
+                    kind: RoleKind::Contracts(HashSet::default()),
+                    permissions: vec!["bounty:*".to_string()].into_iter().collect(),
+                    vote_policy: HashMap::default(),
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+    }
+}
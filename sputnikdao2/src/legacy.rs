@@ -0,0 +1,611 @@
+//! Layout of the state before it was versioned, frozen to read state of DAOs deployed with it in `migrate`.
+//! Proposals and bounties stored with it are converted when they are read.
+
+use std::collections::{HashMap, HashSet};
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128, U64};
+use near_sdk::{AccountId, Balance, CryptoHash};
+
+use crate::policy::{default_policy, RoleKind, WeightKind, WeightOrRatio};
+use crate::proposals::{ActionCall, BondState, PolicyParameters};
+use crate::types::OldAccountId;
+use crate::upgrade::FactoryInfo;
+use crate::*;
+
+/// Key of the contract state in the storage.
+pub(crate) const STATE_KEY: &[u8] = b"STATE";
+
+/// Contract state before versioning.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct OldContract {
+    pub config: LazyOption<ConfigV1>,
+    pub policy: LazyOption<VersionedPolicy>,
+    pub locked_amount: Balance,
+    pub staking_id: Option<AccountId>,
+    pub total_delegation_amount: Balance,
+    pub delegations: LookupMap<AccountId, Balance>,
+    pub last_proposal_id: u64,
+    pub proposals: LookupMap<u64, VersionedProposal>,
+    pub last_bounty_id: u64,
+    pub bounties: LookupMap<u64, VersionedBounty>,
+    pub bounty_claimers: LookupMap<AccountId, Vec<BountyClaimV1>>,
+    pub bounty_claims_count: LookupMap<u64, u32>,
+    pub blobs: LookupMap<CryptoHash, AccountId>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ConfigV1 {
+    pub name: String,
+    pub purpose: String,
+    pub metadata: Base64VecU8,
+}
+
+impl From<ConfigV1> for Config {
+    fn from(config: ConfigV1) -> Self {
+        Config {
+            name: config.name,
+            purpose: config.purpose,
+            metadata: config.metadata,
+            dao_metadata: Default::default(),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct VotePolicyV1 {
+    pub weight_kind: WeightKind,
+    pub quorum: U128,
+    pub threshold: WeightOrRatio,
+}
+
+impl From<VotePolicyV1> for VotePolicy {
+    fn from(vote_policy: VotePolicyV1) -> Self {
+        VotePolicy {
+            weight_kind: vote_policy.weight_kind,
+            quorum: vote_policy.quorum,
+            threshold: vote_policy.threshold,
+            weight_decay: 0,
+            participation_quorum: None,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct RolePermissionV1 {
+    pub name: String,
+    pub kind: RoleKind,
+    pub permissions: HashSet<String>,
+    pub vote_policy: HashMap<String, VotePolicyV1>,
+}
+
+impl From<RolePermissionV1> for RolePermission {
+    fn from(role: RolePermissionV1) -> Self {
+        RolePermission {
+            name: role.name,
+            kind: role.kind,
+            permissions: role.permissions,
+            vote_policy: role
+                .vote_policy
+                .into_iter()
+                .map(|(label, vote_policy)| (label, vote_policy.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Policy stored as `VersionedPolicy::V1`.
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct PolicyV1 {
+    pub roles: Vec<RolePermissionV1>,
+    pub default_vote_policy: VotePolicyV1,
+    pub proposal_bond: U128,
+    pub proposal_period: U64,
+    pub bounty_bond: U128,
+    pub bounty_forgiveness_period: U64,
+}
+
+impl From<PolicyV1> for Policy {
+    /// Parameters added since are left at their defaults.
+    fn from(policy: PolicyV1) -> Self {
+        Policy {
+            roles: policy.roles.into_iter().map(Into::into).collect(),
+            default_vote_policy: policy.default_vote_policy.into(),
+            proposal_bond: policy.proposal_bond,
+            proposal_period: policy.proposal_period,
+            bounty_bond: policy.bounty_bond,
+            bounty_forgiveness_period: policy.bounty_forgiveness_period,
+            ..default_policy(vec![])
+        }
+    }
+}
+
+/// Bounty stored as `VersionedBounty::Default`.
+#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct BountyV1 {
+    pub description: String,
+    pub token: OldAccountId,
+    pub amount: U128,
+    pub times: u32,
+    pub max_deadline: U64,
+}
+
+impl From<BountyV1> for Bounty {
+    fn from(bounty: BountyV1) -> Self {
+        Bounty {
+            description: bounty.description,
+            token: bounty.token,
+            amount: bounty.amount,
+            times: bounty.times,
+            max_deadline: bounty.max_deadline,
+            budget: None,
+            one_claim_per_account: false,
+            required_tags: vec![],
+            milestones: vec![],
+            requires_assignment: false,
+        }
+    }
+}
+
+/// Claims stored under `StorageKeys::BountyClaimers`, see `Contract::internal_bounty_claims`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct BountyClaimV1 {
+    pub bounty_id: u64,
+    pub start_time: U64,
+    pub deadline: U64,
+    pub completed: bool,
+}
+
+/// Returns the map of claims stored before versioning.
+pub(crate) fn legacy_bounty_claimers() -> LookupMap<AccountId, Vec<BountyClaimV1>> {
+    LookupMap::new(StorageKeys::BountyClaimers)
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[allow(clippy::large_enum_variant)]
+pub enum ProposalKindV1 {
+    ChangeConfig {
+        config: ConfigV1,
+    },
+    ChangePolicy {
+        policy: VersionedPolicy,
+    },
+    AddMemberToRole {
+        member_id: AccountId,
+        role: String,
+    },
+    RemoveMemberFromRole {
+        member_id: AccountId,
+        role: String,
+    },
+    FunctionCall {
+        receiver_id: AccountId,
+        actions: Vec<ActionCall>,
+    },
+    UpgradeSelf {
+        hash: Base58CryptoHash,
+    },
+    UpgradeRemote {
+        receiver_id: AccountId,
+        method_name: String,
+        hash: Base58CryptoHash,
+    },
+    Transfer {
+        token_id: OldAccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        msg: Option<String>,
+    },
+    SetStakingContract {
+        staking_id: AccountId,
+    },
+    AddBounty {
+        bounty: BountyV1,
+    },
+    BountyDone {
+        bounty_id: u64,
+        receiver_id: AccountId,
+    },
+    Vote,
+    FactoryInfoUpdate {
+        factory_info: FactoryInfo,
+    },
+    ChangePolicyAddOrUpdateRole {
+        role: RolePermissionV1,
+    },
+    ChangePolicyRemoveRole {
+        role: String,
+    },
+    ChangePolicyUpdateDefaultVotePolicy {
+        vote_policy: VotePolicyV1,
+    },
+    ChangePolicyUpdateParameters {
+        parameters: PolicyParameters,
+    },
+}
+
+impl From<ProposalKindV1> for ProposalKind {
+    fn from(kind: ProposalKindV1) -> Self {
+        match kind {
+            ProposalKindV1::ChangeConfig { config } => ProposalKind::ChangeConfig {
+                config: Config {
+                    dao_metadata: stored_config().dao_metadata,
+                    ..config.into()
+                },
+            },
+            ProposalKindV1::ChangePolicy { policy } => ProposalKind::ChangePolicy {
+                policy: policy.upgrade(),
+            },
+            ProposalKindV1::AddMemberToRole { member_id, role } => {
+                ProposalKind::AddMemberToRole { member_id, role }
+            }
+            ProposalKindV1::RemoveMemberFromRole { member_id, role } => {
+                ProposalKind::RemoveMemberFromRole { member_id, role }
+            }
+            ProposalKindV1::FunctionCall {
+                receiver_id,
+                actions,
+            } => ProposalKind::FunctionCall {
+                receiver_id,
+                actions,
+            },
+            ProposalKindV1::UpgradeSelf { hash } => ProposalKind::UpgradeSelf { hash },
+            ProposalKindV1::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            } => ProposalKind::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            },
+            ProposalKindV1::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+            } => ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            ProposalKindV1::SetStakingContract { staking_id } => {
+                ProposalKind::SetStakingContract { staking_id }
+            }
+            ProposalKindV1::AddBounty { bounty } => ProposalKind::AddBounty {
+                bounty: bounty.into(),
+            },
+            ProposalKindV1::BountyDone {
+                bounty_id,
+                receiver_id,
+            } => ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+                milestone: None,
+                shares: vec![],
+                dispute_of: None,
+            },
+            ProposalKindV1::Vote => ProposalKind::Vote,
+            ProposalKindV1::FactoryInfoUpdate { factory_info } => {
+                ProposalKind::FactoryInfoUpdate { factory_info }
+            }
+            ProposalKindV1::ChangePolicyAddOrUpdateRole { role } => {
+                ProposalKind::ChangePolicyAddOrUpdateRole { role: role.into() }
+            }
+            ProposalKindV1::ChangePolicyRemoveRole { role } => {
+                ProposalKind::ChangePolicyRemoveRole { role }
+            }
+            ProposalKindV1::ChangePolicyUpdateDefaultVotePolicy { vote_policy } => {
+                ProposalKind::ChangePolicyUpdateDefaultVotePolicy {
+                    vote_policy: vote_policy.into(),
+                }
+            }
+            ProposalKindV1::ChangePolicyUpdateParameters { parameters } => {
+                ProposalKind::ChangePolicyUpdateParameters { parameters }
+            }
+        }
+    }
+}
+
+/// Proposal stored as `VersionedProposal::Default`.
+#[derive(BorshSerialize, BorshDeserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct ProposalV1 {
+    pub proposer: AccountId,
+    pub description: String,
+    pub kind: ProposalKindV1,
+    pub status: ProposalStatus,
+    pub vote_counts: HashMap<String, [Balance; 3]>,
+    pub votes: HashMap<AccountId, Vote>,
+    pub submission_time: U64,
+}
+
+impl ProposalV1 {
+    /// Converts into the current layout. Before versioning the DAO returned the `proposal_bond` of the
+    /// current policy, so it's recorded as the bond of proposals that still hold it.
+    pub(crate) fn upgrade(self, policy: &Policy) -> Proposal {
+        let bond_state = match self.status {
+            ProposalStatus::InProgress | ProposalStatus::Failed => BondState::Locked,
+            ProposalStatus::Removed => BondState::Forfeited,
+            _ => BondState::Returned,
+        };
+        let kind: ProposalKind = self.kind.into();
+        Proposal {
+            proposer: self.proposer,
+            description: self.description,
+            kind_hash: kind.hash().into(),
+            kind,
+            status: self.status,
+            vote_counts: self
+                .vote_counts
+                .into_iter()
+                .map(|(role, [approve, reject, remove])| (role, [approve, reject, remove, 0]))
+                .collect(),
+            votes: self.votes,
+            submission_time: self.submission_time,
+            submission_block: U64(0),
+            voting_extension: U64(0),
+            auto_extensions: 0,
+            bond: policy.proposal_bond,
+            bond_state,
+            encrypted_details: None,
+            executed_at: None,
+            execution_after: None,
+            revoke_counts: HashMap::default(),
+            revoked_by: vec![],
+            bond_fronted_by: None,
+            execution_started_at: None,
+            reconciled: false,
+            payout_balance_before: None,
+            finalized_by: None,
+            reviewer: None,
+            weight_snapshot: None,
+            tombstone: None,
+            signals: HashMap::default(),
+            balance_snapshot: false,
+            commitments: HashMap::default(),
+            poll_votes: HashMap::default(),
+            poll_tallies: vec![],
+            vote_weights: HashMap::default(),
+            execution_result: None,
+            provider_snapshot: None,
+            depends_on: None,
+            sponsor: None,
+            reviewed_by: None,
+            committee_budget: None,
+            tier_vote_policy: None,
+            delegated_votes: HashMap::default(),
+        }
+    }
+}
+
+/// Reads the config from the storage. Legacy `ChangeConfig` proposals keep its structured metadata,
+/// that they can't set.
+fn stored_config() -> Config {
+    LazyOption::<Config>::new(StorageKeys::Config, None)
+        .get()
+        .expect("ERR_NO_CONFIG")
+}
+
+/// Reads the policy from the storage, for conversions that need parameters the old layout didn't record.
+pub(crate) fn stored_policy() -> Policy {
+    LazyOption::<VersionedPolicy>::new(StorageKeys::Policy, None)
+        .get()
+        .expect("ERR_NO_POLICY")
+        .upgrade()
+        .to_policy()
+}
+
+impl Contract {
+    /// Converts the state stored before versioning. Config and policy are rewritten,
+    /// collections are kept under their prefixes and the ones added since start empty.
+    /// Proposals in progress are counted in `open_proposals`.
+    pub(crate) fn internal_migrate_v1(old: OldContract) -> Self {
+        let config = old.config.get().expect("ERR_NO_CONFIG");
+        let policy = old.policy.get().expect("ERR_NO_POLICY");
+        let open_proposals = (0..old.last_proposal_id)
+            .filter_map(|id| old.proposals.get(&id))
+            .filter(|proposal| {
+                let status = match proposal {
+                    VersionedProposal::Default(proposal) => &proposal.status,
+                    VersionedProposal::V2(proposal) => &proposal.status,
+                };
+                *status == ProposalStatus::InProgress
+            })
+            .count() as u64;
+        let mut this = Self::internal_new(config.into(), policy);
+        this.locked_amount = old.locked_amount;
+        this.staking_id = old.staking_id;
+        this.total_delegation_amount = old.total_delegation_amount;
+        this.last_proposal_id = old.last_proposal_id;
+        this.last_bounty_id = old.last_bounty_id;
+        this.open_proposals = open_proposals;
+        this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{env, testing_env};
+    use near_sdk_sim::to_yocto;
+
+    use crate::policy::RoleKind;
+    use crate::types::OLD_BASE_TOKEN;
+
+    use super::*;
+
+    /// Writes the state of a DAO deployed before versioning: council of `accounts(1)`, a transfer proposal
+    /// in progress and a bounty claimed by `accounts(2)`.
+    fn write_v1_state() {
+        let council = RolePermissionV1 {
+            name: "council".to_string(),
+            kind: RoleKind::Group(vec![accounts(1)].into_iter().collect()),
+            permissions: vec!["*:*".to_string()].into_iter().collect(),
+            vote_policy: HashMap::default(),
+        };
+        let policy = PolicyV1 {
+            roles: vec![council],
+            default_vote_policy: VotePolicyV1 {
+                weight_kind: WeightKind::RoleWeight,
+                quorum: U128(0),
+                threshold: WeightOrRatio::Ratio(1, 2),
+            },
+            proposal_bond: U128(to_yocto("1")),
+            proposal_period: U64(1_000),
+            bounty_bond: U128(to_yocto("2")),
+            bounty_forgiveness_period: U64(1_000),
+        };
+        let config = ConfigV1 {
+            name: "legacy".to_string(),
+            purpose: "to test".to_string(),
+            metadata: Base64VecU8(vec![]),
+        };
+        let mut old = OldContract {
+            config: LazyOption::new(StorageKeys::Config, Some(&config)),
+            policy: LazyOption::new(StorageKeys::Policy, Some(&VersionedPolicy::V1(policy))),
+            locked_amount: to_yocto("3"),
+            staking_id: None,
+            total_delegation_amount: 0,
+            delegations: LookupMap::new(StorageKeys::Delegations),
+            last_proposal_id: 1,
+            proposals: LookupMap::new(StorageKeys::Proposals),
+            last_bounty_id: 1,
+            bounties: LookupMap::new(StorageKeys::Bounties),
+            bounty_claimers: LookupMap::new(StorageKeys::BountyClaimers),
+            bounty_claims_count: LookupMap::new(StorageKeys::BountyClaimCounts),
+            blobs: LookupMap::new(StorageKeys::Blobs),
+        };
+        old.proposals.insert(
+            &0,
+            &VersionedProposal::Default(ProposalV1 {
+                proposer: accounts(2),
+                description: "legacy transfer".to_string(),
+                kind: ProposalKindV1::Transfer {
+                    token_id: OLD_BASE_TOKEN.to_string(),
+                    receiver_id: accounts(2),
+                    amount: U128(to_yocto("10")),
+                    msg: None,
+                },
+                status: ProposalStatus::InProgress,
+                vote_counts: HashMap::default(),
+                votes: HashMap::default(),
+                submission_time: U64(0),
+            }),
+        );
+        old.bounties.insert(
+            &0,
+            &VersionedBounty::Default(BountyV1 {
+                description: "legacy bounty".to_string(),
+                token: OLD_BASE_TOKEN.to_string(),
+                amount: U128(to_yocto("5")),
+                times: 1,
+                max_deadline: U64(1_000),
+            }),
+        );
+        old.bounty_claimers.insert(
+            &accounts(2),
+            &vec![BountyClaimV1 {
+                bounty_id: 0,
+                start_time: U64(0),
+                deadline: U64(1_000),
+                completed: false,
+            }],
+        );
+        old.bounty_claims_count.insert(&0, &1);
+        env::state_write(&old);
+    }
+
+    #[test]
+    fn test_migrate_v1_state() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        write_v1_state();
+        let mut contract = Contract::migrate();
+
+        assert_eq!(contract.get_config().name, "legacy");
+        let policy = contract.get_policy();
+        assert_eq!(policy.bounty_bond, U128(to_yocto("2")));
+        assert_eq!(policy.roles[0].name, "council");
+        assert_eq!(contract.locked_amount, to_yocto("3"));
+        assert_eq!(contract.get_last_proposal_id(), 1);
+        assert_eq!(contract.open_proposals, 1);
+
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.description, "legacy transfer");
+        assert_eq!(proposal.bond, U128(to_yocto("1")));
+        assert_eq!(proposal.bond_state, BondState::Locked);
+        contract.act_proposal(0, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(contract.open_proposals, 0);
+
+        assert_eq!(contract.get_bounty(0).bounty.amount, U128(to_yocto("5")));
+        assert_eq!(contract.get_bounty_claims(accounts(2)).len(), 1);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.bounty_giveup(0);
+        assert!(contract.get_bounty_claims(accounts(2)).is_empty());
+        // Bond of the proposal is returned in the callback of the transfer.
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+
+        // State with the layout of this code is kept as is.
+        env::state_write(&contract);
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_config().name, "legacy");
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_migrate_v1_change_config() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        write_v1_state();
+        let mut contract = Contract::migrate();
+        let mut config = contract.get_config();
+        config.dao_metadata.links = vec!["https://example.com".to_string()];
+        contract.config.set(&config);
+        contract.proposals.insert(
+            &1,
+            &VersionedProposal::Default(ProposalV1 {
+                proposer: accounts(2),
+                description: "rename".to_string(),
+                kind: ProposalKindV1::ChangeConfig {
+                    config: ConfigV1 {
+                        name: "renamed".to_string(),
+                        purpose: "to test".to_string(),
+                        metadata: Base64VecU8(vec![]),
+                    },
+                },
+                status: ProposalStatus::InProgress,
+                vote_counts: HashMap::default(),
+                votes: HashMap::default(),
+                submission_time: U64(0),
+            }),
+        );
+        contract.last_proposal_id = 2;
+
+        contract.act_proposal(1, Action::VoteApprove, None);
+        assert_eq!(contract.get_config().name, "renamed");
+        assert_eq!(
+            contract.get_dao_metadata().links,
+            vec!["https://example.com".to_string()]
+        );
+    }
+}
@@ -1,29 +1,114 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
 use near_sdk::json_types::{Base58CryptoHash, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     env, ext_contract, near_bindgen, AccountId, Balance, BorshStorageKey, CryptoHash,
-    PanicOnDefault, Promise, PromiseResult,
+    PanicOnDefault, Promise, PromiseResult, PublicKey,
 };
 
-pub use crate::bounties::{Bounty, BountyClaim, VersionedBounty};
+pub use crate::alumni::{AlumniRecord, MemberActivity};
+pub use crate::applicants::Applicants;
+pub use crate::approvals::ProposalApproval;
+pub use crate::audit::{AuditMismatch, AuditReport};
+pub use crate::batches::TransferBatch;
+pub use crate::bounties::{Bounty, BountyClaim, BountyConfig, BountyShare, VersionedBounty};
+pub use crate::bounty_applications::BountyApplication;
+pub use crate::budgets::Budget;
+pub use crate::checkpoints::{CheckpointRecord, CheckpointSchedule};
+pub use crate::commit_reveal::CommitReveal;
+pub use crate::committees::Committee;
+pub use crate::delegation::DelegateVote;
+pub use crate::emergency::{EmergencySignature, EmergencySigners};
+pub use crate::events::VoteEvent;
+pub use crate::evm::{EvmBridge, EvmDecision, EvmReceipt};
+pub use crate::health::HealthMetrics;
+use crate::idempotency::IdempotentProposal;
+use crate::indices::ProposalIndex;
+use crate::legacy::{OldContract, STATE_KEY};
+pub use crate::member_terms::ExpiringMember;
+pub use crate::onboarding::{Onboarding, OnboardingEvent};
 pub use crate::policy::{
-    default_policy, Policy, RoleKind, RolePermission, VersionedPolicy, VotePolicy,
+    default_policy, BondSlash, BountyForfeiture, ExecutionWindow, PeriodUnit, Policy, RevokeGrace,
+    RoleKind, RolePermission, RoleThreshold, SeatNft, TransferTier, VersionedPolicy, VoteExtension,
+    VotePolicy,
 };
 use crate::proposals::VersionedProposal;
-pub use crate::proposals::{Proposal, ProposalInput, ProposalKind, ProposalStatus};
-pub use crate::types::{Action, Config, OldAccountId, OLD_BASE_TOKEN};
-use crate::upgrade::{internal_get_factory_info, internal_set_factory_info, FactoryInfo};
-pub use crate::views::{BountyOutput, ProposalOutput};
+pub use crate::proposals::{
+    BondState, EncryptedDetails, ExecutionResult, Proposal, ProposalInput, ProposalKind,
+    ProposalStatus, Tombstone, Vote,
+};
+use crate::queue::WaitingQueue;
+pub use crate::rebalancing::{AllocationTarget, RebalancingPolicy};
+pub use crate::recurring::{Recurrence, RecurringTransfer};
+pub use crate::reviewers::ReviewerAssignment;
+pub use crate::snapshot::WeightSnapshot;
+pub use crate::spending::{CategorySpending, SpendingCategories};
+pub use crate::treasury::{
+    DiversificationLimit, HoldingValue, PriceOracle, ReportedPrice, TokenPrice, TreasuryToken,
+    TreasuryValuation,
+};
+pub use crate::types::{
+    Action, Config, DaoMetadata, DaoMetadataUpdate, OldAccountId, OLD_BASE_TOKEN,
+};
+use crate::upgrade::{
+    internal_get_factory_info, internal_is_read_only, internal_set_factory_info, FactoryInfo,
+};
+pub use crate::views::{
+    BountyOutput, ProposalKindInfo, ProposalOutput, ProposalTimeline, SimulatedVote,
+    ValidationResult, VoteScenario,
+};
+pub use crate::weight_provider::ProviderSnapshot;
 
+mod alumni;
+mod applicants;
+mod approvals;
+mod audit;
+mod batches;
 mod bounties;
+mod bounty_applications;
+mod bounty_disputes;
+mod budgets;
+mod checkpoints;
+mod commit_reveal;
+mod committees;
 mod delegation;
+mod digests;
+mod emergency;
+mod events;
+mod evm;
+mod finalization;
+mod health;
+mod idempotency;
+mod indices;
+mod integrations;
+mod legacy;
+mod marketplace;
+mod member_terms;
+mod onboarding;
+mod pause;
 mod policy;
+mod polls;
 mod proposals;
+mod queue;
+mod rebalancing;
+mod reconcile;
+mod recurring;
+mod reviewers;
+mod search;
+mod seats;
+mod signals;
+mod skills;
+mod snapshot;
+mod spending;
+mod sponsorship;
+mod tokens;
+mod treasury;
 mod types;
 mod upgrade;
 pub mod views;
+mod vote_delegation;
+mod weight_provider;
 
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKeys {
@@ -35,6 +120,48 @@ pub enum StorageKeys {
     BountyClaimers,
     BountyClaimCounts,
     Blobs,
+    Budgets,
+    BountyPastClaimers,
+    ProposalsByHash,
+    ProposalsByReceiver,
+    TokenDecimals,
+    Spending,
+    FrontedBonds,
+    BountyListings,
+    DelegateVotes,
+    LastReviewers,
+    TokenPrices,
+    TokenBalances,
+    TokenWhitelist,
+    IdempotencyKeys,
+    SnapshotWeights,
+    SigningKeys,
+    ProposalDigests,
+    EvmDecisions,
+    Checkpoints,
+    SkillTags,
+    VoteDelegations,
+    VoteDelegators,
+    StorageSponsorships,
+    DelegationHistory,
+    TotalDelegationHistory,
+    TransferBatches,
+    MemberSince,
+    MemberActivity,
+    Alumni,
+    AlumniByAccount,
+    ProposalsByKeyword,
+    BountiesByKeyword,
+    RecurringTransfers,
+    ApplicantLastProposal,
+    BountyClaimants,
+    BountyApplications,
+    BountyDisputes,
+    TreasuryTokens,
+    BountyClaimersV2,
+    WaitingProposals,
+    ProposalsByHashV2,
+    ProposalsByReceiverV2,
 }
 
 /// After payouts, allows a callback
@@ -42,6 +169,33 @@ pub enum StorageKeys {
 pub trait ExtSelf {
     /// Callback after proposal execution.
     fn on_proposal_callback(&mut self, proposal_id: u64) -> PromiseOrValue<()>;
+    /// Callback after fetching fungible token metadata.
+    fn on_ft_metadata(&mut self, token_id: AccountId) -> u8;
+    /// Callback after exporting bounty to a marketplace.
+    fn on_bounty_export(&mut self, bounty_id: u64, marketplace_id: AccountId) -> bool;
+    /// Callback after fetching balance of the receiver of a stuck proposal.
+    fn on_reconcile_proposal(&mut self, proposal_id: u64, amount: U128) -> PromiseOrValue<()>;
+    /// Callback after fetching balance of the receiver of a payout when its execution was dispatched.
+    fn on_payout_balance(&mut self, proposal_id: u64);
+    /// Callback after a call of `MultiFunctionCall` proposal.
+    fn on_call_step(&mut self, proposal_id: u64, step: u32) -> Promise;
+    /// Callback after fetching balance of this DAO in fungible token.
+    fn on_ft_balance(&mut self, token_id: AccountId) -> U128;
+    /// Callback after executing transfers of a batch sent in one promise.
+    fn on_transfer_batch(&mut self, proposal_ids: Vec<u64>);
+    /// Callback after fetching weight of a voter from the vote weight provider.
+    fn on_vote_weight(&mut self, proposal_id: u64, account_id: AccountId) -> U128;
+    /// Callback after fetching total weight of a proposal from the vote weight provider.
+    fn on_provider_total_weight(&mut self, proposal_id: u64) -> U128;
+    /// Callback after a payout of recurring transfer.
+    fn on_recurring_transfer(&mut self, proposal_id: u64, schedule: RecurringTransfer);
+    /// Callback after checking that receivers of a bounty payout are registered in its token.
+    fn on_bounty_storage_check(
+        &mut self,
+        token_id: AccountId,
+        payouts: Vec<(AccountId, U128)>,
+        memo: String,
+    ) -> Promise;
 }
 
 #[near_bindgen]
@@ -75,30 +229,137 @@ pub struct Contract {
     pub bounty_claimers: LookupMap<AccountId, Vec<BountyClaim>>,
     /// Count of claims per bounty.
     pub bounty_claims_count: LookupMap<u64, u32>,
+    /// Accounts that have ever claimed given bounty. Only kept for bounties with `one_claim_per_account`.
+    pub bounty_past_claimers: LookupSet<(u64, AccountId)>,
 
     /// Large blob storage.
     pub blobs: LookupMap<CryptoHash, AccountId>,
+
+    /// Named spending budgets that can fund bounties.
+    pub budgets: LookupMap<String, Budget>,
+
+    /// Ids of proposals per hash of their kind.
+    pub proposals_by_hash: ProposalIndex<CryptoHash>,
+    /// Ids of `Transfer` and `FunctionCall` proposals per receiver.
+    pub proposals_by_receiver: ProposalIndex<AccountId>,
+
+    /// Decimals of fungible tokens, registered via `register_token_metadata`.
+    pub token_decimals: LookupMap<AccountId, u8>,
+
+    /// Spending per category per period.
+    pub spending: LookupMap<u64, Vec<CategorySpending>>,
+
+    /// Amount of proposal bonds currently fronted by the DAO per role.
+    pub fronted_bonds: LookupMap<String, Balance>,
+
+    /// Marketplace each exported bounty is listed on.
+    pub bounty_listings: LookupMap<u64, AccountId>,
+
+    /// Votes cast with delegated weight per delegate.
+    pub delegate_votes: LookupMap<AccountId, Vec<DelegateVote>>,
+
+    /// Reviewer last assigned from each role, skipped on the next assignment.
+    pub last_reviewers: LookupMap<String, AccountId>,
+
+    /// Number of proposals in progress.
+    pub open_proposals: u64,
+    /// Proposals waiting to be opened for voting, oldest first.
+    pub waiting_proposals: WaitingQueue,
+
+    /// Latest prices per token and reference token, reported by the price oracle.
+    pub token_prices: LookupMap<(OldAccountId, OldAccountId), TokenPrice>,
+    /// Balances of this DAO in fungible tokens, fetched via `refresh_token_balance`.
+    pub token_balances: UnorderedMap<AccountId, Balance>,
+
+    /// Tokens the DAO is registered with and accepts, managed by whitelist proposals.
+    pub token_whitelist: UnorderedSet<AccountId>,
+
+    /// Proposals by proposer and idempotency key they were added with.
+    pub idempotency_keys: LookupMap<(AccountId, String), IdempotentProposal>,
+
+    /// Weights proven by voters against the snapshots of proposals.
+    pub snapshot_weights: LookupMap<(u64, AccountId), Balance>,
+
+    /// Keys which can execute proposal kinds without voting in an emergency.
+    pub emergency_signers: Option<EmergencySigners>,
+
+    /// Number of emergency executions, signed with the message to prevent replays.
+    pub emergency_nonce: u64,
+
+    /// Keys members sign proposal approvals with, see `add_proposal_with_approvals`.
+    pub signing_keys: LookupMap<AccountId, PublicKey>,
+
+    /// Leaves of digests of proposal status transitions per epoch.
+    pub proposal_digests: LookupMap<u64, Vec<CryptoHash>>,
+
+    /// Decisions on approved `EvmCall` proposals, by proposal id.
+    pub evm_decisions: LookupMap<u64, EvmDecision>,
+
+    /// History of checkpoints per schedule name.
+    pub checkpoints: LookupMap<String, CheckpointRecord>,
+
+    /// Skill tags of accounts, matched against tags required by bounties.
+    pub skill_tags: UnorderedMap<AccountId, Vec<String>>,
+
+    /// Time of the last `rebalance` of the treasury.
+    pub last_rebalance: Option<u64>,
+
+    /// Member each member delegated voting to, see `delegate_voting`.
+    pub vote_delegations: LookupMap<AccountId, AccountId>,
+    /// Members who directly delegated voting to each member.
+    pub vote_delegators: LookupMap<AccountId, Vec<AccountId>>,
+
+    /// Storage sponsored for each account that is not used yet, see `sponsor_storage`.
+    pub storage_sponsorships: LookupMap<AccountId, Balance>,
+
+    /// Changes of delegated balance per account, as block height and balance after the change.
+    pub delegation_history: LookupMap<AccountId, Vec<(u64, Balance)>>,
+    /// Changes of the total delegated balance, as block height and total after the change.
+    pub total_delegation_history: Vector<(u64, Balance)>,
+
+    /// Approved transfers waiting for their batch window to close, per batch key.
+    pub transfer_batches: LookupMap<String, TransferBatch>,
+
+    /// Parameters of the bounty subsystem, see `ChangeBountyConfig`.
+    pub bounty_config: Option<BountyConfig>,
+
+    /// When members were added to group roles by proposals, per role and member.
+    pub member_since: LookupMap<(String, AccountId), u64>,
+    /// Activity of members of group roles.
+    pub member_activity: LookupMap<AccountId, MemberActivity>,
+    /// Records of members removed from group roles, in order of removal.
+    pub alumni: Vector<AlumniRecord>,
+    /// Indices of alumni records per account.
+    pub alumni_by_account: LookupMap<AccountId, Vec<u64>>,
+
+    /// Ids of proposals per hash of keyword of their description, see `search_proposals`.
+    pub proposals_by_keyword: LookupMap<CryptoHash, Vec<u64>>,
+    /// Ids of bounties per hash of keyword of their description or required tags, see `search_bounties`.
+    pub bounties_by_keyword: LookupMap<CryptoHash, Vec<u64>>,
+    /// Payouts left of executed recurring transfers, see `trigger_recurring`.
+    pub recurring_transfers: LookupMap<u64, RecurringTransfer>,
+    /// When each applicant last added a proposal, in `period_unit` of the policy.
+    pub applicant_last_proposal: LookupMap<AccountId, u64>,
+    /// Amount of $NEAR of burned proposal bonds, locked in the DAO forever.
+    pub burned_amount: Balance,
+
+    /// Accounts holding claims of each bounty.
+    pub bounty_claimants: LookupMap<u64, Vec<AccountId>>,
+    /// Applications for bounties that require assignment.
+    pub bounty_applications: LookupMap<u64, Vec<BountyApplication>>,
+    /// Rejected `BountyDone` proposals that can be disputed, with the end of their dispute period.
+    pub bounty_disputes: LookupMap<u64, u64>,
+    /// Whether new proposals and execution are stopped, see `pause`.
+    pub paused: bool,
+    /// Fungible tokens held by the DAO, registered by deposits and token whitelisting, see `get_treasury`.
+    pub treasury_tokens: UnorderedSet<AccountId>,
 }
 
 #[near_bindgen]
 impl Contract {
     #[init]
     pub fn new(config: Config, policy: VersionedPolicy) -> Self {
-        let this = Self {
-            config: LazyOption::new(StorageKeys::Config, Some(&config)),
-            policy: LazyOption::new(StorageKeys::Policy, Some(&policy.upgrade())),
-            staking_id: None,
-            total_delegation_amount: 0,
-            delegations: LookupMap::new(StorageKeys::Delegations),
-            last_proposal_id: 0,
-            proposals: LookupMap::new(StorageKeys::Proposals),
-            last_bounty_id: 0,
-            bounties: LookupMap::new(StorageKeys::Bounties),
-            bounty_claimers: LookupMap::new(StorageKeys::BountyClaimers),
-            bounty_claims_count: LookupMap::new(StorageKeys::BountyClaimCounts),
-            blobs: LookupMap::new(StorageKeys::Blobs),
-            locked_amount: 0,
-        };
+        let this = Self::internal_new(config, policy);
         internal_set_factory_info(&FactoryInfo {
             factory_id: env::predecessor_account_id(),
             auto_update: true,
@@ -107,19 +368,90 @@ impl Contract {
     }
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
+    /// Converts the state stored before versioning, see `legacy`. State with the layout of this code is kept as is.
+    /// If parts of the state loaded lazily can't be read, the DAO is put in read-only mode instead of failing.
     /// If you have changed state, you need to implement migration from old state (keep the old struct with different name to deserialize it first).
-    /// After migrate goes live on MainNet, return this implementation for next updates.
     #[private]
     #[init(ignore_state)]
     pub fn migrate() -> Self {
-        let this: Contract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+        let state = env::storage_read(STATE_KEY).expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+        let this = match OldContract::try_from_slice(&state) {
+            Ok(old) => Self::internal_migrate_v1(old),
+            Err(_) => Self::try_from_slice(&state).expect("ERR_INCOMPATIBLE_STATE"),
+        };
+        this.internal_check_state_compatible();
         this
     }
 
+    /// State of a new DAO with given config and policy. Collections are created under their prefixes.
+    fn internal_new(config: Config, policy: VersionedPolicy) -> Self {
+        Self {
+            config: LazyOption::new(StorageKeys::Config, Some(&config)),
+            policy: LazyOption::new(StorageKeys::Policy, Some(&policy.upgrade())),
+            staking_id: None,
+            total_delegation_amount: 0,
+            delegations: LookupMap::new(StorageKeys::Delegations),
+            last_proposal_id: 0,
+            proposals: LookupMap::new(StorageKeys::Proposals),
+            last_bounty_id: 0,
+            bounties: LookupMap::new(StorageKeys::Bounties),
+            bounty_claimers: LookupMap::new(StorageKeys::BountyClaimersV2),
+            bounty_claims_count: LookupMap::new(StorageKeys::BountyClaimCounts),
+            bounty_past_claimers: LookupSet::new(StorageKeys::BountyPastClaimers),
+            blobs: LookupMap::new(StorageKeys::Blobs),
+            budgets: LookupMap::new(StorageKeys::Budgets),
+            proposals_by_hash: ProposalIndex::new(StorageKeys::ProposalsByHashV2),
+            proposals_by_receiver: ProposalIndex::new(StorageKeys::ProposalsByReceiverV2),
+            token_decimals: LookupMap::new(StorageKeys::TokenDecimals),
+            spending: LookupMap::new(StorageKeys::Spending),
+            fronted_bonds: LookupMap::new(StorageKeys::FrontedBonds),
+            bounty_listings: LookupMap::new(StorageKeys::BountyListings),
+            delegate_votes: LookupMap::new(StorageKeys::DelegateVotes),
+            last_reviewers: LookupMap::new(StorageKeys::LastReviewers),
+            open_proposals: 0,
+            waiting_proposals: WaitingQueue::new(),
+            token_prices: LookupMap::new(StorageKeys::TokenPrices),
+            token_balances: UnorderedMap::new(StorageKeys::TokenBalances),
+            token_whitelist: UnorderedSet::new(StorageKeys::TokenWhitelist),
+            idempotency_keys: LookupMap::new(StorageKeys::IdempotencyKeys),
+            snapshot_weights: LookupMap::new(StorageKeys::SnapshotWeights),
+            emergency_signers: None,
+            emergency_nonce: 0,
+            signing_keys: LookupMap::new(StorageKeys::SigningKeys),
+            proposal_digests: LookupMap::new(StorageKeys::ProposalDigests),
+            evm_decisions: LookupMap::new(StorageKeys::EvmDecisions),
+            checkpoints: LookupMap::new(StorageKeys::Checkpoints),
+            skill_tags: UnorderedMap::new(StorageKeys::SkillTags),
+            last_rebalance: None,
+            vote_delegations: LookupMap::new(StorageKeys::VoteDelegations),
+            vote_delegators: LookupMap::new(StorageKeys::VoteDelegators),
+            storage_sponsorships: LookupMap::new(StorageKeys::StorageSponsorships),
+            delegation_history: LookupMap::new(StorageKeys::DelegationHistory),
+            total_delegation_history: Vector::new(StorageKeys::TotalDelegationHistory),
+            transfer_batches: LookupMap::new(StorageKeys::TransferBatches),
+            bounty_config: None,
+            member_since: LookupMap::new(StorageKeys::MemberSince),
+            member_activity: LookupMap::new(StorageKeys::MemberActivity),
+            alumni: Vector::new(StorageKeys::Alumni),
+            alumni_by_account: LookupMap::new(StorageKeys::AlumniByAccount),
+            proposals_by_keyword: LookupMap::new(StorageKeys::ProposalsByKeyword),
+            bounties_by_keyword: LookupMap::new(StorageKeys::BountiesByKeyword),
+            recurring_transfers: LookupMap::new(StorageKeys::RecurringTransfers),
+            applicant_last_proposal: LookupMap::new(StorageKeys::ApplicantLastProposal),
+            burned_amount: 0,
+            bounty_claimants: LookupMap::new(StorageKeys::BountyClaimants),
+            bounty_applications: LookupMap::new(StorageKeys::BountyApplications),
+            bounty_disputes: LookupMap::new(StorageKeys::BountyDisputes),
+            paused: false,
+            treasury_tokens: UnorderedSet::new(StorageKeys::TreasuryTokens),
+            locked_amount: 0,
+        }
+    }
+
     /// Remove blob from contract storage and pay back to original storer.
     /// Only original storer can call this.
     pub fn remove_blob(&mut self, hash: Base58CryptoHash) -> Promise {
+        self.assert_not_read_only();
         let hash: CryptoHash = hash.into();
         let account_id = self.blobs.remove(&hash).expect("ERR_NO_BLOB");
         assert_eq!(
@@ -137,6 +469,11 @@ impl Contract {
     pub fn get_factory_info(&self) -> FactoryInfo {
         internal_get_factory_info()
     }
+
+    /// Returns true if the DAO is in read-only mode after migrating to code that can't read its state.
+    pub fn is_read_only(&self) -> bool {
+        internal_is_read_only()
+    }
 }
 
 /// Stores attached data into blob store and returns hash of it.
@@ -145,6 +482,7 @@ impl Contract {
 pub extern "C" fn store_blob() {
     env::setup_panic_hook();
     let mut contract: Contract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+    contract.assert_not_read_only();
     let input = env::input().expect("ERR_NO_INPUT");
     let sha256_hash = env::sha256(&input);
     assert!(!env::storage_has_key(&sha256_hash), "ERR_ALREADY_EXISTS");
@@ -173,10 +511,14 @@ pub extern "C" fn store_blob() {
 
 #[cfg(test)]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::testing_env;
+    use near_sdk::json_types::Base64VecU8;
+    use near_sdk::json_types::U64;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, IntoStorageKey};
     use near_sdk_sim::to_yocto;
 
+    use crate::idempotency::IDEMPOTENCY_WINDOW;
+    use crate::policy::{WeightKind, WeightOrRatio};
     use crate::proposals::ProposalStatus;
 
     use super::*;
@@ -190,7 +532,12 @@ mod tests {
                 receiver_id: accounts(2).into(),
                 amount: U128(to_yocto("100")),
                 msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
             },
+            idempotency_key: None,
+            depends_on: None,
         })
     }
 
@@ -235,6 +582,8 @@ mod tests {
                 member_id: accounts(2).into(),
                 role: "council".to_string(),
             },
+            idempotency_key: None,
+            depends_on: None,
         });
     }
 
@@ -263,59 +612,2205 @@ mod tests {
         let mut contract = Contract::new(Config::test_config(), policy);
         let id = create_proposal(&mut context, &mut contract);
         assert_eq!(contract.get_proposal(id).proposal.description, "test");
+        let content_hash = contract.get_proposal(id).proposal.content_hash();
         contract.act_proposal(id, Action::RemoveProposal, None);
-        assert_eq!(contract.get_proposals(0, 10).len(), 0);
+        // Proposal stays as a tombstone.
+        assert_eq!(contract.get_proposals(0, 10).len(), 1);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Removed);
+        assert!(proposal.description.is_empty());
+        let tombstone = proposal.tombstone.unwrap();
+        assert_eq!(CryptoHash::from(tombstone.content_hash), content_hash);
+        assert_eq!(tombstone.removed_by, accounts(1));
     }
 
     #[test]
-    fn test_vote_expired_proposal() {
+    fn test_act_proposals() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
             Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1).into()]),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let first = create_proposal(&mut context, &mut contract);
+        let second = create_proposal(&mut context, &mut contract);
+        contract.act_proposals(vec![
+            (first, Action::VoteApprove),
+            (second, Action::VoteReject),
+        ]);
+        assert_eq!(
+            contract.get_proposal(first).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(
+            contract.get_proposal(second).proposal.status,
+            ProposalStatus::Rejected
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_act_proposals_denied() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let first = create_proposal(&mut context, &mut contract);
+        let second = create_proposal(&mut context, &mut contract);
+        contract.act_proposals(vec![
+            (first, Action::VoteApprove),
+            (second, Action::RemoveProposal),
+        ]);
+    }
+
+    #[test]
+    fn test_withdraw_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
         );
+        // Proposer without any permission to act on proposals.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
         let id = create_proposal(&mut context, &mut contract);
-        testing_env!(context
-            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
-            .build());
+        contract.act_proposal(id, Action::Withdraw, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Removed);
+        assert_eq!(proposal.bond_state, BondState::Returned);
+        assert_eq!(contract.locked_amount, 0);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(2)));
+    }
+
+    #[test]
+    fn test_update_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        let kind = ProposalKind::Transfer {
+            token_id: String::from(OLD_BASE_TOKEN),
+            receiver_id: accounts(3),
+            amount: U128(to_yocto("10")),
+            msg: None,
+            category: None,
+            batch: None,
+            recurrence: None,
+        };
+        contract.update_proposal(
+            id,
+            ProposalInput {
+                description: "fixed".to_string(),
+                kind: kind.clone(),
+                idempotency_key: None,
+                depends_on: None,
+            },
+        );
+        let proposal = contract.get_proposal(id);
+        assert_eq!(proposal.id, id);
+        assert_eq!(proposal.proposal.description, "fixed");
+        assert_eq!(proposal.proposal.kind_hash, kind.hash().into());
+        assert!(contract
+            .get_proposals_targeting(accounts(2), 0, 10)
+            .is_empty());
+        assert_eq!(
+            contract.get_proposals_targeting(accounts(3), 0, 10).len(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_HAS_VOTES")]
+    fn test_update_voted_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
         contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.update_proposal(
+            id,
+            ProposalInput {
+                description: "fixed".to_string(),
+                kind: ProposalKind::Vote,
+                idempotency_key: None,
+                depends_on: None,
+            },
+        );
     }
 
     #[test]
-    #[should_panic(expected = "ERR_ALREADY_VOTED")]
-    fn test_vote_twice() {
+    #[should_panic(expected = "ERR_PROPOSAL_HAS_VOTES")]
+    fn test_withdraw_voted_proposal() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
             Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1).into(), accounts(2).into()]),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
         );
         let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteReject, None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(id, Action::Withdraw, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_EXECUTION_PENDING")]
+    fn test_remove_proposal_pending_execution() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .insert("*:RemoveProposal".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        // Transfer is executed, but the callback hasn't arrived yet.
         contract.act_proposal(id, Action::VoteApprove, None);
+        contract.act_proposal(id, Action::RemoveProposal, None);
+    }
+
+    #[test]
+    fn test_forfeited_bond_share() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles[1]
+            .permissions
+            .insert("*:RemoveProposal".to_string());
+        policy_mut.forfeited_bond_share = 2_000;
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.act_proposal(id, Action::RemoveProposal, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.bond_state,
+            BondState::Forfeited
+        );
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(2)));
+    }
+
+    #[test]
+    fn test_mark_spam_burns_bond() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles[1]
+            .permissions
+            .insert("*:MarkSpam".to_string());
+        policy_mut.bond_slash_on_spam = Some(BondSlash::Burn);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::MarkSpam, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Removed);
+        assert_eq!(proposal.bond_state, BondState::Burned);
+        assert_eq!(contract.locked_amount, 0);
+        assert_eq!(contract.get_burned_amount(), U128(to_yocto("1")));
+    }
+
+    #[test]
+    fn test_bond_slash_on_reject() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().bond_slash_on_reject = Some(BondSlash::Treasury);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteReject, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!(proposal.bond_state, BondState::Forfeited);
+        assert_eq!(contract.locked_amount, 0);
+        assert!(get_created_receipts().is_empty());
+    }
+
+    #[test]
+    fn test_bond_settled_once() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+        // Bond is raised while the proposal is in progress.
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.proposal_bond = U128(to_yocto("2"));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
         contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+
+        // Failed proposal expires: the bond it was submitted with is returned once.
+        testing_env!(context
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .build());
+        contract.act_proposal(id, Action::Finalize, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Expired);
+        assert_eq!(proposal.bond_state, BondState::Returned);
+        assert_eq!(contract.locked_amount, 0);
     }
 
     #[test]
-    fn test_add_to_missing_role() {
+    fn test_granular_policy_patches() {
         let mut context = VMContextBuilder::new();
         testing_env!(context.predecessor_account_id(accounts(1)).build());
         let mut contract = Contract::new(
             Config::test_config(),
-            VersionedPolicy::Default(vec![accounts(1).into()]),
+            VersionedPolicy::Default(vec![accounts(1)]),
         );
-        testing_env!(context.attached_deposit(to_yocto("1")).build());
-        let id = contract.add_proposal(ProposalInput {
-            description: "test".to_string(),
-            kind: ProposalKind::AddMemberToRole {
-                member_id: accounts(2).into(),
-                role: "missing".to_string(),
+        let mut add = |context: &mut VMContextBuilder, kind| {
+            testing_env!(context.attached_deposit(to_yocto("1")).build());
+            contract.add_proposal(ProposalInput {
+                description: "patch".to_string(),
+                kind,
+                idempotency_key: None,
+                depends_on: None,
+            });
+        };
+        // Patches added at the same time don't overwrite each other.
+        add(
+            &mut context,
+            ProposalKind::ChangePolicyUpdateRolePermissions {
+                role: "all".to_string(),
+                add: vec!["vote:VoteApprove".to_string()],
+                remove: vec![],
+            },
+        );
+        add(
+            &mut context,
+            ProposalKind::ChangePolicyUpdateRolePermissions {
+                role: "all".to_string(),
+                add: vec![],
+                remove: vec!["*:AddProposal".to_string()],
+            },
+        );
+        add(
+            &mut context,
+            ProposalKind::ChangePolicyUpdateVotePolicy {
+                role: "council".to_string(),
+                proposal_kind: "vote".to_string(),
+                vote_policy: Some(VotePolicy {
+                    threshold: WeightOrRatio::Weight(U128(1)),
+                    ..Default::default()
+                }),
             },
+        );
+        add(
+            &mut context,
+            ProposalKind::ChangePolicyAddRole {
+                role: RolePermission {
+                    name: "guests".to_string(),
+                    kind: RoleKind::Group(Default::default()),
+                    permissions: Default::default(),
+                    vote_policy: Default::default(),
+                },
+            },
+        );
+        for id in 0..4 {
+            contract.act_proposal(id, Action::VoteApprove, None);
+        }
+        let policy = contract.get_policy();
+        assert_eq!(
+            policy.roles[0].permissions,
+            vec!["vote:VoteApprove".to_string()].into_iter().collect()
+        );
+        assert_eq!(
+            policy.roles[1].vote_policy["vote"].threshold,
+            WeightOrRatio::Weight(U128(1))
+        );
+        assert_eq!(policy.roles[2].name, "guests");
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ROLE_EXISTS")]
+    fn test_add_existing_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let role = contract.get_policy().roles[1].clone();
+        contract.add_proposal(ProposalInput {
+            description: "patch".to_string(),
+            kind: ProposalKind::ChangePolicyAddRole { role },
+            idempotency_key: None,
+            depends_on: None,
         });
+    }
+
+    #[test]
+    fn test_weighted_group() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![]).upgrade();
+        policy.to_policy_mut().roles[1].kind = RoleKind::WeightedGroup(
+            vec![
+                (accounts(1), U128(2)),
+                (accounts(2), U128(1)),
+                (accounts(3), U128(1)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.get_proposal_thresholds(id)[0].threshold, U128(3));
+        testing_env!(context.attached_deposit(0).build());
         contract.act_proposal(id, Action::VoteApprove, None);
-        let x = contract.get_policy();
-        // still 2 roles: all and council.
-        assert_eq!(x.roles.len(), 2);
+        assert_eq!(
+            contract.get_proposal(id).proposal.vote_counts["council"],
+            [2, 0, 0, 0]
+        );
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_max_members() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy
+            .to_policy_mut()
+            .max_members
+            .insert("council".to_string(), 2);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let propose = |context: &mut VMContextBuilder, contract: &mut Contract, kind| {
+            testing_env!(context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(to_yocto("1"))
+                .build());
+            let id = contract.add_proposal(ProposalInput {
+                description: "seat".to_string(),
+                kind,
+                idempotency_key: None,
+                depends_on: None,
+            });
+            for voter in 1..3 {
+                testing_env!(context
+                    .predecessor_account_id(accounts(voter))
+                    .attached_deposit(0)
+                    .build());
+                contract.act_proposal(id, Action::VoteApprove, None);
+            }
+            id
+        };
+        let id = propose(
+            &mut context,
+            &mut contract,
+            ProposalKind::AddMemberToRole {
+                member_id: accounts(3),
+                role: "council".to_string(),
+            },
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert!(!contract
+            .get_policy()
+            .is_group_member("council", &accounts(3)));
+
+        // Seat freed by a removal can be filled by finalizing the proposal again.
+        propose(
+            &mut context,
+            &mut contract,
+            ProposalKind::RemoveMemberFromRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+        );
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert!(contract
+            .get_policy()
+            .is_group_member("council", &accounts(3)));
+    }
+
+    #[test]
+    fn test_transfer_tiers() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default((1..4).map(accounts).collect()).upgrade();
+        policy.to_policy_mut().transfer_tiers.push(TransferTier {
+            token: OLD_BASE_TOKEN.to_string(),
+            min_amount: U128(to_yocto("10")),
+            vote_policy: VotePolicy {
+                threshold: WeightOrRatio::Ratio(2, 3),
+                ..Default::default()
+            },
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let propose =
+            |context: &mut VMContextBuilder, contract: &mut Contract, amount, recurrence| {
+                testing_env!(context
+                    .predecessor_account_id(accounts(1))
+                    .attached_deposit(to_yocto("1"))
+                    .build());
+                contract.add_proposal(ProposalInput {
+                    description: "transfer".to_string(),
+                    kind: ProposalKind::Transfer {
+                        token_id: String::from(OLD_BASE_TOKEN),
+                        receiver_id: accounts(4),
+                        amount: U128(to_yocto(amount)),
+                        msg: None,
+                        category: None,
+                        batch: None,
+                        recurrence,
+                    },
+                    idempotency_key: None,
+                    depends_on: None,
+                })
+            };
+        let small = propose(&mut context, &mut contract, "9", None);
+        let large = propose(&mut context, &mut contract, "10", None);
+        // Tiered by the total of all payouts.
+        let recurring = propose(
+            &mut context,
+            &mut contract,
+            "5",
+            Some(Recurrence {
+                interval: U64(1),
+                times: 2,
+            }),
+        );
+        assert_eq!(
+            contract.get_proposal_thresholds(recurring)[0].threshold,
+            U128(3)
+        );
+        assert_eq!(
+            contract.get_proposal_thresholds(small)[0].threshold,
+            U128(2)
+        );
+        assert_eq!(
+            contract.get_proposal_thresholds(large)[0].threshold,
+            U128(3)
+        );
+        for voter in 1..3 {
+            testing_env!(context
+                .predecessor_account_id(accounts(voter))
+                .attached_deposit(0)
+                .build());
+            contract.act_proposal(small, Action::VoteApprove, None);
+            contract.act_proposal(large, Action::VoteApprove, None);
+        }
+        assert_eq!(
+            contract.get_proposal(small).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(
+            contract.get_proposal(large).proposal.status,
+            ProposalStatus::InProgress
+        );
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(large, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(large).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_transfer_tier_updated() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default((1..4).map(accounts).collect()).upgrade();
+        policy.to_policy_mut().transfer_tiers.push(TransferTier {
+            token: OLD_BASE_TOKEN.to_string(),
+            min_amount: U128(to_yocto("10")),
+            vote_policy: VotePolicy {
+                threshold: WeightOrRatio::Ratio(2, 3),
+                ..Default::default()
+            },
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let transfer = |amount| ProposalInput {
+            description: "transfer".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(4),
+                amount: U128(to_yocto(amount)),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        };
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(transfer("9"));
+        assert_eq!(contract.get_proposal_thresholds(id)[0].threshold, U128(2));
+        // Raising the amount moves the proposal to the higher tier.
+        contract.update_proposal(id, transfer("10"));
+        assert_eq!(contract.get_proposal_thresholds(id)[0].threshold, U128(3));
+    }
+
+    #[test]
+    fn test_participation_quorum() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default((0..5).map(accounts).collect()).upgrade();
+        policy
+            .to_policy_mut()
+            .default_vote_policy
+            .participation_quorum = Some(WeightOrRatio::Ratio(2, 5));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        let vote = |context: &mut VMContextBuilder, contract: &mut Contract, voter, action| {
+            testing_env!(context
+                .predecessor_account_id(accounts(voter))
+                .attached_deposit(0)
+                .build());
+            contract.act_proposal(id, action, None);
+            contract.get_proposal(id).proposal.status
+        };
+        // Majority of the votes cast doesn't decide until the quorum of 3 votes is reached.
+        assert_eq!(
+            vote(&mut context, &mut contract, 0, Action::VoteApprove),
+            ProposalStatus::InProgress
+        );
+        assert_eq!(
+            vote(&mut context, &mut contract, 1, Action::VoteReject),
+            ProposalStatus::InProgress
+        );
+        assert_eq!(
+            vote(&mut context, &mut contract, 2, Action::VoteApprove),
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_proposal_bonds_by_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().proposal_bonds.insert(
+            "council".to_string(),
+            vec![
+                ("*".to_string(), U128(0)),
+                ("policy".to_string(), U128(to_yocto("5"))),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let add = |contract: &mut Contract, kind| {
+            contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind,
+                idempotency_key: None,
+                depends_on: None,
+            })
+        };
+        // Council members pay no bond, except for policy changes.
+        add(&mut contract, ProposalKind::Vote);
+        testing_env!(context.attached_deposit(to_yocto("5")).build());
+        let policy = VersionedPolicy::Current(contract.get_policy());
+        add(&mut contract, ProposalKind::ChangePolicy { policy });
+        assert_eq!(contract.locked_amount, to_yocto("5"));
+
+        // Outsiders pay the default bond.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        add(&mut contract, ProposalKind::Vote);
+        assert_eq!(contract.locked_amount, to_yocto("6"));
+        let info = contract
+            .get_proposal_kinds()
+            .into_iter()
+            .find(|info| info.label == "policy")
+            .unwrap();
+        assert_eq!(info.bond, U128(to_yocto("1")));
+        assert_eq!(info.role_bonds["council"], U128(to_yocto("5")));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_BOND")]
+    fn test_proposal_bond_of_outsider() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().proposal_bonds.insert(
+            "council".to_string(),
+            vec![("*".to_string(), U128(0))].into_iter().collect(),
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+    }
+
+    #[test]
+    fn test_private_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = contract.add_private_proposal(
+            ProposalInput {
+                description: "compensation".to_string(),
+                kind: ProposalKind::Vote,
+                idempotency_key: None,
+                depends_on: None,
+            },
+            EncryptedDetails {
+                ciphertext: Base64VecU8(vec![1, 2, 3]),
+                key_hint: "council-2022".to_string(),
+            },
+        );
+        contract.act_proposal(id, Action::VoteApprove, Some("salary is fine".to_string()));
+        assert!(get_logs().is_empty());
+        let details = contract
+            .get_proposal(id)
+            .proposal
+            .encrypted_details
+            .unwrap();
+        assert_eq!(details.ciphertext.0, vec![1, 2, 3]);
+        assert_eq!(details.key_hint, "council-2022");
+    }
+
+    #[test]
+    fn test_private_proposal_resubmission() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let mut add = |ciphertext: Vec<u8>| {
+            contract.add_private_proposal(
+                ProposalInput {
+                    description: "compensation".to_string(),
+                    kind: ProposalKind::Vote,
+                    idempotency_key: Some("key".to_string()),
+                    depends_on: None,
+                },
+                EncryptedDetails {
+                    ciphertext: Base64VecU8(ciphertext),
+                    key_hint: "council-2022".to_string(),
+                },
+            )
+        };
+        let id = add(vec![1, 2, 3]);
+        assert_eq!(add(vec![4, 5, 6]), id);
+        let details = contract
+            .get_proposal(id)
+            .proposal
+            .encrypted_details
+            .unwrap();
+        assert_eq!(details.ciphertext.0, vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PRIVATE_PROPOSAL_NOT_VOTE")]
+    fn test_private_proposal_transfer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        contract.add_private_proposal(
+            ProposalInput {
+                description: "compensation".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: String::from(OLD_BASE_TOKEN),
+                    receiver_id: accounts(2).into(),
+                    amount: U128(to_yocto("100")),
+                    msg: None,
+                    category: None,
+                    batch: None,
+                    recurrence: None,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            },
+            EncryptedDetails {
+                ciphertext: Base64VecU8(vec![1, 2, 3]),
+                key_hint: "council-2022".to_string(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_find_proposal_by_hash() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        let kind_hash = contract.get_proposal(id).proposal.kind_hash;
+        let other_id = create_proposal(&mut context, &mut contract);
+        let found = contract.find_proposal_by_hash(kind_hash);
+        assert_eq!(
+            found.iter().map(|p| p.id).collect::<Vec<_>>(),
+            vec![id, other_id]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DUPLICATE_PROPOSAL")]
+    fn test_reject_duplicate_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().reject_duplicate_proposals = true;
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteReject, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteReject, None);
+        // Rejected proposal doesn't block the same one.
+        create_proposal(&mut context, &mut contract);
+        create_proposal(&mut context, &mut contract);
+    }
+
+    #[test]
+    fn test_get_proposals_targeting() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        create_proposal(&mut context, &mut contract);
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(
+            contract.get_proposals_targeting(accounts(2), 0, 10).len(),
+            2
+        );
+        let page = contract.get_proposals_targeting(accounts(2), 1, 10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, id);
+        assert!(contract
+            .get_proposals_targeting(accounts(3), 0, 10)
+            .is_empty());
+        // Edited proposal keeps its place.
+        contract.update_proposal(
+            0,
+            ProposalInput {
+                description: "fixed".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: String::from(OLD_BASE_TOKEN),
+                    receiver_id: accounts(2).into(),
+                    amount: U128(to_yocto("50")),
+                    msg: None,
+                    category: None,
+                    batch: None,
+                    recurrence: None,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            },
+        );
+        let page = contract.get_proposals_targeting(accounts(2), 0, 10);
+        assert_eq!(
+            page.iter().map(|proposal| proposal.id).collect::<Vec<_>>(),
+            vec![0, id]
+        );
+    }
+
+    #[test]
+    fn test_update_dao_metadata() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut update = |update: DaoMetadataUpdate| {
+            let id = contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::ChangeConfigUpdateDaoMetadata {
+                    dao_metadata: update,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            });
+            contract.act_proposal(id, Action::VoteApprove, None);
+        };
+        update(DaoMetadataUpdate {
+            links: Some(vec!["https://example.com".to_string()]),
+            logo_hash: Some("logo".to_string()),
+            legal_entity: None,
+            timezone: Some("UTC".to_string()),
+        });
+        update(DaoMetadataUpdate {
+            links: None,
+            logo_hash: None,
+            legal_entity: None,
+            timezone: Some("Europe/Berlin".to_string()),
+        });
+        let metadata = contract.get_dao_metadata();
+        assert_eq!(metadata.links, vec!["https://example.com".to_string()]);
+        assert_eq!(metadata.logo_hash, Some("logo".to_string()));
+        assert_eq!(metadata.legal_entity, None);
+        assert_eq!(metadata.timezone, Some("Europe/Berlin".to_string()));
+        assert_eq!(contract.get_config().name, "Test");
+    }
+
+    #[test]
+    fn test_proposal_timeline() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context
+            .block_timestamp(100)
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        let period = contract.get_policy().proposal_period.0;
+        let timeline = contract.get_proposal_timeline(id);
+        assert_eq!(timeline.submission_time, U64(100));
+        assert_eq!(timeline.voting_closes, U64(100 + period));
+        assert_eq!(timeline.executed_at, None);
+
+        testing_env!(context.block_timestamp(200).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal_timeline(id).executed_at,
+            Some(U64(200))
+        );
+    }
+
+    fn revoke_grace_contract(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .insert("*:Revoke".to_string());
+        policy.to_policy_mut().revoke_grace = Some(RevokeGrace {
+            period: U64(1_000),
+            threshold: WeightOrRatio::Ratio(1, 2),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract
+    }
+
+    #[test]
+    fn test_revoke_in_grace_period() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = revoke_grace_contract(&mut context);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Queued
+        );
+        contract.act_proposal(0, Action::Revoke, None);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(0, Action::Revoke, None);
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Revoked);
+        assert_eq!(proposal.bond_state, BondState::Forfeited);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    fn test_revoke_ignores_empty_role() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = revoke_grace_contract(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.roles.push(RolePermission {
+            name: "auditors".to_string(),
+            kind: RoleKind::Group(Default::default()),
+            permissions: vec!["*:Revoke".to_string()].into_iter().collect(),
+            vote_policy: Default::default(),
+        });
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.act_proposal(0, Action::Revoke, None);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Queued
+        );
+    }
+
+    #[test]
+    fn test_execute_after_grace_period() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = revoke_grace_contract(&mut context);
+        contract.act_proposal(0, Action::Revoke, None);
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.act_proposal(0, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_IN_GRACE_PERIOD")]
+    fn test_finalize_in_grace_period() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = revoke_grace_contract(&mut context);
+        contract.act_proposal(0, Action::Finalize, None);
+    }
+
+    #[test]
+    fn test_execution_delay() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.execution_delay = Some(U64(1_000));
+        policy_mut.roles[1]
+            .permissions
+            .insert("*:Execute".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Queued);
+        assert_eq!(proposal.execution_after, Some(U64(1_000)));
+
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.act_proposal(id, Action::Execute, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    fn guardian_contract(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.execution_delay = Some(U64(1_000));
+        policy_mut.guardian_role = Some("guardian".to_string());
+        policy_mut.roles[1].permissions.insert("*:Veto".to_string());
+        policy_mut.roles.push(RolePermission {
+            name: "guardian".to_string(),
+            kind: RoleKind::Group(vec![accounts(3)].into_iter().collect()),
+            permissions: vec!["*:Veto".to_string()].into_iter().collect(),
+            vote_policy: Default::default(),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract
+    }
+
+    #[test]
+    fn test_guardian_veto() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = guardian_contract(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(0, Action::Veto, None);
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Vetoed);
+        assert_eq!(proposal.bond_state, BondState::Returned);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_GUARDIAN")]
+    fn test_veto_by_council() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = guardian_contract(&mut context);
+        contract.act_proposal(0, Action::Veto, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_IN_GRACE_PERIOD")]
+    fn test_execute_before_delay() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.execution_delay = Some(U64(1_000));
+        policy_mut.roles[1]
+            .permissions
+            .insert("*:Execute".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.act_proposal(id, Action::Execute, None);
+    }
+
+    #[test]
+    fn test_fronted_bonds() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy
+            .to_policy_mut()
+            .bond_allowances
+            .insert("council".to_string(), U128(to_yocto("1")));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let add_vote = |contract: &mut Contract| {
+            contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+                idempotency_key: None,
+                depends_on: None,
+            })
+        };
+        let id = add_vote(&mut contract);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.bond_fronted_by, Some("council".to_string()));
+        assert_eq!(
+            contract.get_fronted_bonds("council".to_string()).0,
+            to_yocto("1")
+        );
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(contract.get_fronted_bonds("council".to_string()).0, 0);
+        assert_eq!(contract.locked_amount, 0);
+        add_vote(&mut contract);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_BOND")]
+    fn test_fronted_bonds_exhausted() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy
+            .to_policy_mut()
+            .bond_allowances
+            .insert("council".to_string(), U128(to_yocto("1")));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        for _ in 0..2 {
+            contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+                idempotency_key: None,
+                depends_on: None,
+            });
+        }
+    }
+
+    #[test]
+    fn test_delegate_record() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        contract.delegations.insert(&accounts(1), &10);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        create_proposal(&mut context, &mut contract);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteReject, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteReject, None);
+
+        let record = contract.get_delegate_record(accounts(1), 0, 10);
+        assert_eq!(record.len(), 2);
+        assert_eq!(record[1].proposal_id, id);
+        assert_eq!(record[1].vote, Vote::Reject);
+        assert_eq!(record[1].weight, U128(10));
+        assert_eq!(contract.get_delegate_record(accounts(1), 1, 1).len(), 1);
+        assert!(contract.get_delegate_record(accounts(2), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_hide_votes_until_finalized() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().hide_votes_until_finalized = true;
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.delegations.insert(&accounts(1), &10);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteReject, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert!(proposal.votes.is_empty());
+        assert!(proposal.vote_weights.is_empty());
+        assert_eq!(proposal.vote_counts["council"], [0, 1, 0, 0]);
+        assert!(contract.get_proposals(0, 10)[0].proposal.votes.is_empty());
+        assert!(contract.get_delegate_record(accounts(1), 0, 10).is_empty());
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteReject, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!(proposal.votes.len(), 2);
+        assert_eq!(contract.get_delegate_record(accounts(1), 0, 10).len(), 1);
+    }
+
+    #[test]
+    fn test_function_call_actions() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let actions = near_sdk::serde_json::from_value(near_sdk::serde_json::json!([
+            {"method_name": "approve", "args": "", "deposit": "1", "gas": "10000000000000"},
+            {"method_name": "deposit", "args": "", "deposit": "0", "gas": "20000000000000"}
+        ]))
+        .unwrap();
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::FunctionCall {
+                receiver_id: accounts(3),
+                actions,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        // Actions are executed in order, in a single receipt on the receiver.
+        let receipt = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(3))
+            .unwrap();
+        let methods: Vec<String> = receipt
+            .actions
+            .into_iter()
+            .filter_map(|action| match action {
+                near_sdk::mock::VmAction::FunctionCall { function_name, .. } => Some(function_name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(methods, vec!["approve", "deposit"]);
+    }
+
+    #[test]
+    fn test_function_call_execution_result() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let actions = near_sdk::serde_json::from_value(near_sdk::serde_json::json!([
+            {"method_name": "get", "args": "", "deposit": "0", "gas": "10000000000000"}
+        ]))
+        .unwrap();
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::FunctionCall {
+                receiver_id: accounts(3),
+                actions,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(contract.get_proposal_execution_result(id), None);
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![7; 300])],
+        );
+        contract.on_proposal_callback(id);
+        // Return value is truncated.
+        assert_eq!(
+            contract.get_proposal_execution_result(id),
+            Some(ExecutionResult {
+                success: true,
+                value: vec![7; 256].into(),
+                truncated: true,
+            })
+        );
+    }
+
+    fn multi_call_contract(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let calls = near_sdk::serde_json::from_value(near_sdk::serde_json::json!([
+            {
+                "receiver_id": accounts(3),
+                "actions": [{"method_name": "register", "args": "", "deposit": "0", "gas": "10000000000000"}]
+            },
+            {
+                "receiver_id": accounts(4),
+                "actions": [{"method_name": "swap", "args": "", "deposit": "0", "gas": "10000000000000"}]
+            }
+        ]))
+        .unwrap();
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::MultiFunctionCall { calls },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        assert_eq!(
+            contract.get_proposals_targeting(accounts(4), 0, 10).len(),
+            1
+        );
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(3)));
+        assert!(!get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+        contract
+    }
+
+    fn call_step_env(context: &mut VMContextBuilder, result: PromiseResult) {
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    #[test]
+    fn test_multi_function_call() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = multi_call_contract(&mut context);
+        call_step_env(&mut context, PromiseResult::Successful(vec![]));
+        contract.on_call_step(0, 1);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_CALL_FAILED")]
+    fn test_multi_function_call_stops_on_failure() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = multi_call_contract(&mut context);
+        call_step_env(&mut context, PromiseResult::Failed);
+        contract.on_call_step(0, 1);
+    }
+
+    #[test]
+    fn test_finalizer_reward() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().finalizer_reward = Some(U128(to_yocto("0.1")));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .build());
+        contract.act_proposal(id, Action::Finalize, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Expired);
+        assert_eq!(proposal.finalized_by, Some(accounts(2)));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(2)));
+    }
+
+    #[test]
+    fn test_storage_unregister() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::StorageUnregister {
+                token_id: accounts(3),
+                force: false,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(3)));
+        assert!(contract
+            .get_proposal(id)
+            .proposal
+            .execution_started_at
+            .is_some());
+    }
+
+    #[test]
+    fn test_token_whitelist() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddTokenToWhitelist {
+                token_id: accounts(3),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(3)));
+        // Token is whitelisted only once the storage registration succeeds.
+        assert!(contract.get_whitelisted_tokens().is_empty());
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(contract.get_whitelisted_tokens(), vec![accounts(3)]);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::RemoveTokenFromWhitelist {
+                token_id: accounts(3),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(contract.get_whitelisted_tokens().is_empty());
+    }
+
+    fn add_dependent(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        depends_on: u64,
+    ) -> u64 {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "second step".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: Some(depends_on),
+        })
+    }
+
+    #[test]
+    fn test_proposal_dependency() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let first = create_proposal(&mut context, &mut contract);
+        let second = add_dependent(&mut context, &mut contract, first);
+        contract.act_proposal(first, Action::VoteApprove, None);
+        contract.act_proposal(second, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(second).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_proposal_dependency_not_approved() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let first = create_proposal(&mut context, &mut contract);
+        let second = add_dependent(&mut context, &mut contract, first);
+        // Votes count, execution waits for the dependency.
+        contract.act_proposal(second, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(second).proposal.status,
+            ProposalStatus::Queued
+        );
+        contract.act_proposal(second, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(second).proposal.status,
+            ProposalStatus::Queued
+        );
+
+        contract.act_proposal(first, Action::VoteApprove, None);
+        contract.act_proposal(second, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(second).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_idempotency_key() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let input = || ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+            idempotency_key: Some("key".to_string()),
+            depends_on: None,
+        };
+        let id = contract.add_proposal(input());
+        assert_eq!(contract.add_proposal(input()), id);
+        assert_eq!(contract.get_last_proposal_id(), 1);
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+        // Bond attached to the resubmission is refunded.
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(1)));
+
+        // Same key of another account, or after the window, adds a new proposal.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        assert_eq!(contract.add_proposal(input()), 1);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(IDEMPOTENCY_WINDOW + 1)
+            .build());
+        assert_eq!(contract.add_proposal(input()), 2);
+    }
+
+    #[test]
+    fn test_send_chain_message() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().chain_message_bridges = vec![accounts(3)];
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::SendChainMessage {
+                bridge_id: accounts(3),
+                destination_chain: "ethereum".to_string(),
+                payload: Base64VecU8(vec![1, 2, 3]),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        assert_eq!(
+            contract.get_proposals_targeting(accounts(3), 0, 10)[0].id,
+            id
+        );
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UNKNOWN_BRIDGE")]
+    fn test_send_chain_message_unknown_bridge() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::SendChainMessage {
+                bridge_id: accounts(3),
+                destination_chain: "ethereum".to_string(),
+                payload: Base64VecU8(vec![1, 2, 3]),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+    }
+
+    #[test]
+    fn test_execution_window() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().execution_windows = vec![ExecutionWindow {
+            kinds: vec!["transfer".to_string()],
+            start: U64(100),
+            duration: U64(10),
+            interval: U64(1_000),
+        }];
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(50).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Queued);
+        assert_eq!(proposal.execution_after, Some(U64(100)));
+
+        // Window closed again before the proposal was finalized.
+        testing_env!(context.block_timestamp(1_150).build());
+        contract.act_proposal(id, Action::Finalize, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Queued);
+        assert_eq!(proposal.execution_after, Some(U64(2_100)));
+
+        testing_env!(context.block_timestamp(2_105).build());
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_passed_execution_window() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().execution_windows = vec![ExecutionWindow {
+            kinds: vec!["*".to_string()],
+            start: U64(100),
+            duration: U64(10),
+            interval: U64(0),
+        }];
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        // The only window has closed for good, so it doesn't hold the proposal back.
+        testing_env!(context.block_timestamp(200).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_reviewer_assignment() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3), accounts(4)])
+                .upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.reviewer_assignment = Some(ReviewerAssignment {
+            role: "council".to_string(),
+            kinds: vec!["transfer".to_string()],
+        });
+        policy_mut.roles[1].vote_policy.insert(
+            "transfer".to_string(),
+            VotePolicy {
+                weight_kind: WeightKind::RoleWeight,
+                quorum: U128(0),
+                threshold: WeightOrRatio::Weight(U128(1)),
+                weight_decay: 0,
+                participation_quorum: None,
+            },
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let mut previous = None;
+        for _ in 0..3 {
+            let id = create_proposal(&mut context, &mut contract);
+            let reviewer = contract.get_proposal(id).proposal.reviewer.unwrap();
+            assert_ne!(reviewer, accounts(1));
+            assert_ne!(Some(reviewer.clone()), previous);
+            previous = Some(reviewer);
+        }
+
+        let id = contract.get_last_proposal_id() - 1;
+        let reviewer = previous.unwrap();
+        testing_env!(context.predecessor_account_id(reviewer).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ASSIGNED_REVIEWER")]
+    fn test_vote_by_not_assigned_reviewer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().reviewer_assignment = Some(ReviewerAssignment {
+            role: "council".to_string(),
+            kinds: vec!["transfer".to_string()],
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(
+            contract.get_proposal(id).proposal.reviewer,
+            Some(accounts(2))
+        );
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_max_open_proposals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.max_open_proposals = Some(1);
+        policy_mut.roles[1]
+            .permissions
+            .insert("*:RemoveProposal".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let first = create_proposal(&mut context, &mut contract);
+        let second = create_proposal(&mut context, &mut contract);
+        let third = create_proposal(&mut context, &mut contract);
+        assert_eq!(
+            contract.get_proposal(second).proposal.status,
+            ProposalStatus::Waiting
+        );
+        assert_eq!(contract.get_waiting_proposals(0, 10), vec![second, third]);
+
+        contract.act_proposal(third, Action::RemoveProposal, None);
+        assert_eq!(contract.get_waiting_proposals(0, 10), vec![second]);
+
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.act_proposal(first, Action::VoteReject, None);
+        let proposal = contract.get_proposal(second).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.submission_time, U64(1_000));
+        assert!(contract.get_waiting_proposals(0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_period_in_blocks() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_index(100)
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.period_unit = PeriodUnit::Blocks;
+        policy_mut.proposal_period = U64(10);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.get_proposal_timeline(id).voting_closes, U64(110));
+
+        // Far in time, but not in blocks.
+        testing_env!(context
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .block_index(110)
+            .build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        testing_env!(context.block_index(111).build());
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_proposal_period_of_kind() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy
+            .to_policy_mut()
+            .proposal_periods
+            .insert("transfer".to_string(), U64(1_000));
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.get_proposal_timeline(id).voting_closes, U64(1_000));
+
+        testing_env!(context.block_timestamp(1_001).build());
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_vote_expired_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1).into()]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ALREADY_VOTED")]
+    fn test_vote_twice() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1).into(), accounts(2).into()]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_change_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteReject, None);
+        contract.act_proposal(id, Action::ChangeVote(Vote::Approve), None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.vote_counts["council"], [1, 0, 0, 0]);
+        assert_eq!(proposal.votes[&accounts(1)], Vote::Approve);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_VOTE")]
+    fn test_change_vote_without_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::ChangeVote(Vote::Approve), None);
+    }
+
+    #[test]
+    fn test_vote_abstain() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3), accounts(4)])
+                .upgrade();
+        let council = &mut policy.to_policy_mut().roles[1];
+        council.permissions.insert("*:VoteAbstain".to_string());
+        council.vote_policy.insert(
+            "transfer".to_string(),
+            VotePolicy {
+                weight_kind: WeightKind::RoleWeight,
+                quorum: U128(3),
+                threshold: WeightOrRatio::Weight(U128(2)),
+                weight_decay: 0,
+                participation_quorum: None,
+            },
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteAbstain, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.vote_counts["council"], [1, 0, 0, 1]);
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        // Abstention counts toward the quorum, the approvals reach the threshold.
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_add_to_missing_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1).into()]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2).into(),
+                role: "missing".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let x = contract.get_policy();
+        // still 2 roles: all and council.
+        assert_eq!(x.roles.len(), 2);
+    }
+
+    #[test]
+    fn test_vote_extension_on_flip() {
+        let day = 1_000_000_000 * 24 * 60 * 60;
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        policy.to_policy_mut().vote_extension = Some(VoteExtension {
+            window: U64(2 * day),
+            increment: U64(day),
+            max_extensions: 1,
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        // Vote within the window before expiry changes the leading vote.
+        testing_env!(context.block_timestamp(6 * day).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.voting_extension, U64(day));
+        assert_eq!(proposal.auto_extensions, 1);
+        // Voting is still open after the original expiry, but can't be extended again.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .block_timestamp(7 * day + day / 2)
+            .build());
+        contract.act_proposal(id, Action::VoteReject, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.auto_extensions, 1);
+        testing_env!(context.block_timestamp(8 * day + 1).build());
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_extend_voting() {
+        let day = 1_000_000_000 * 24 * 60 * 60;
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .insert("*:ExtendVoting".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(6 * day).build());
+        contract.act_proposal(
+            id,
+            Action::ExtendVoting {
+                extra_time: U64(day),
+            },
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(id).proposal.voting_extension,
+            U64(day)
+        );
+        // Voting is still open after the original expiry.
+        testing_env!(context.block_timestamp(7 * day + day / 2).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_EXPIRED")]
+    fn test_extend_expired_voting() {
+        let day = 1_000_000_000 * 24 * 60 * 60;
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .insert("*:ExtendVoting".to_string());
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.block_timestamp(7 * day + 1).build());
+        contract.act_proposal(
+            id,
+            Action::ExtendVoting {
+                extra_time: U64(day),
+            },
+            None,
+        );
+    }
+
+    #[test]
+    fn test_simulate_policy() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let policy = VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]);
+        let vote = |account_id: AccountId| SimulatedVote {
+            account_id,
+            weight: U128(0),
+            vote: Vote::Approve,
+        };
+        let scenario = |votes: Vec<SimulatedVote>| VoteScenario {
+            kind: ProposalKind::Vote,
+            votes,
+            total_supply: None,
+        };
+        // Non council votes are ignored.
+        assert_eq!(
+            contract.simulate_policy(
+                policy.clone(),
+                scenario(vec![vote(accounts(1)), vote(accounts(4))])
+            ),
+            ProposalStatus::InProgress
+        );
+        assert_eq!(
+            contract.simulate_policy(policy, scenario(vec![vote(accounts(1)), vote(accounts(2))])),
+            ProposalStatus::Approved
+        );
+        // Current policy is not changed by simulation.
+        assert_eq!(contract.get_policy().roles[1].kind.get_role_size(), Some(1));
+    }
+
+    #[test]
+    fn test_simulate_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let label = || "transfer".to_string();
+        assert!(contract.check_permission(accounts(1), label(), Action::VoteApprove));
+        assert!(!contract.check_permission(accounts(2), label(), Action::VoteApprove));
+        assert!(contract.check_permission(accounts(2), label(), Action::AddProposal));
+
+        let proposal = |depends_on| ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on,
+        };
+        assert_eq!(
+            contract.simulate_proposal(accounts(2), proposal(None)),
+            ValidationResult {
+                valid: true,
+                errors: vec![],
+                bond: U128(to_yocto("1")),
+                awaits_sponsor: false,
+            }
+        );
+        assert_eq!(
+            contract
+                .simulate_proposal(accounts(2), proposal(Some(0)))
+                .errors,
+            vec!["ERR_INVALID_DEPENDENCY".to_string()]
+        );
+        let result = contract.simulate_proposal(
+            accounts(2),
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Poll { options: vec![] },
+                idempotency_key: None,
+                depends_on: None,
+            },
+        );
+        assert!(!result.valid);
+        assert_eq!(result.errors, vec!["ERR_POLL_OPTIONS".to_string()]);
+    }
+
+    /// Migrates the state of a DAO with one proposal stored with a layout this code can't read.
+    /// Returns the storage key and the value of the proposal before it was overwritten.
+    fn migrate_incompatible(context: &mut VMContextBuilder) -> (Contract, Vec<u8>, Vec<u8>) {
+        testing_env!(context
+            .current_account_id("dao.sputnik-dao.near".parse().unwrap())
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        create_proposal(context, &mut contract);
+        env::state_write(&contract);
+        assert!(!Contract::migrate().is_read_only());
+        let key = [
+            StorageKeys::Proposals.into_storage_key(),
+            0u64.try_to_vec().unwrap(),
+        ]
+        .concat();
+        let value = env::storage_read(&key).unwrap();
+        env::storage_write(&key, &[0xff]);
+        (Contract::migrate(), key, value)
+    }
+
+    #[test]
+    fn test_migrate_incompatible_state() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, key, value) = migrate_incompatible(&mut context);
+        assert!(contract.is_read_only());
+        assert_eq!(contract.get_last_proposal_id(), 1);
+        assert_eq!(contract.get_policy().roles.len(), 2);
+        // Upgrade to code that reads the state can still be voted on.
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "fix".to_string(),
+            kind: ProposalKind::UpgradeSelf {
+                hash: Base58CryptoHash::default(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context.attached_deposit(0).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        // Migrating state the code reads leaves read-only mode.
+        env::storage_write(&key, &value);
+        env::state_write(&contract);
+        assert!(!Contract::migrate().is_read_only());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_READ_ONLY")]
+    fn test_read_only_add_proposal() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, _, _) = migrate_incompatible(&mut context);
+        create_proposal(&mut context, &mut contract);
     }
 
     #[test]
@@ -333,6 +2828,8 @@ mod tests {
             kind: ProposalKind::ChangePolicy {
                 policy: VersionedPolicy::Default(vec![]),
             },
+            idempotency_key: None,
+            depends_on: None,
         });
     }
 }
@@ -0,0 +1,195 @@
+//! Listing of bounties on external marketplaces, trusted by the policy.
+//! Marketplace matches hunters and reports completion, payout is still approved by the DAO.
+
+use near_sdk::{near_bindgen, serde_json, AccountId, Gas, Promise};
+
+use crate::*;
+
+const GAS_FOR_LIST_BOUNTY: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_ON_BOUNTY_EXPORT: Gas = Gas(5_000_000_000_000);
+
+#[ext_contract(ext_bounty_marketplace)]
+pub trait BountyMarketplace {
+    /// Receives bounty exported by the DAO. Returns true if the bounty was listed.
+    fn list_bounty(&mut self, bounty_id: u64, bounty: Bounty) -> bool;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Exports given bounty to one of the marketplaces from the policy.
+    /// Once the marketplace accepts it, the marketplace can report completion of the bounty.
+    pub fn bounty_export(&mut self, id: u64, marketplace_id: AccountId) -> Promise {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            policy.bounty_marketplaces.contains(&marketplace_id),
+            "ERR_UNKNOWN_MARKETPLACE"
+        );
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        ext_bounty_marketplace::list_bounty(
+            id,
+            bounty,
+            marketplace_id.clone(),
+            0,
+            GAS_FOR_LIST_BOUNTY,
+        )
+        .then(ext_self::on_bounty_export(
+            id,
+            marketplace_id,
+            env::current_account_id(),
+            0,
+            GAS_FOR_ON_BOUNTY_EXPORT,
+        ))
+    }
+
+    /// Receiving acceptance of the bounty exported in `bounty_export`.
+    #[private]
+    pub fn on_bounty_export(&mut self, bounty_id: u64, marketplace_id: AccountId) -> bool {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        let listed = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice(&value).unwrap_or(false),
+            _ => false,
+        };
+        if listed && self.bounties.get(&bounty_id).is_some() {
+            self.bounty_listings.insert(&bounty_id, &marketplace_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Report by the marketplace the bounty is listed on, that `receiver_id` has done it.
+    /// Records completed claim without bond and creates a proposal to vote for paying out the bounty.
+    /// Proposal bond must be attached, same as for `add_proposal`.
+    #[payable]
+    pub fn bounty_marketplace_done(
+        &mut self,
+        id: u64,
+        receiver_id: AccountId,
+        description: String,
+    ) -> u64 {
+        self.assert_not_read_only();
+        assert_eq!(
+            self.bounty_listings.get(&id),
+            Some(env::predecessor_account_id()),
+            "ERR_NOT_BOUNTY_MARKETPLACE"
+        );
+        let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+        self.internal_add_claim(id, &receiver_id, bounty.max_deadline, 0, true);
+        self.add_proposal(ProposalInput {
+            description,
+            kind: ProposalKind::BountyDone {
+                bounty_id: id,
+                receiver_id,
+                milestone: None,
+                shares: vec![],
+                dispute_of: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        })
+    }
+
+    /// Returns marketplace given bounty is listed on.
+    pub fn get_bounty_listing(&self, id: u64) -> Option<AccountId> {
+        self.bounty_listings.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::{U128, U64};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn contract_with_bounty(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().bounty_marketplaces = vec![accounts(3)];
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: false,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract
+    }
+
+    fn export_result(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        listed: bool,
+    ) -> bool {
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                serde_json::to_vec(&listed).unwrap()
+            )],
+        );
+        contract.on_bounty_export(0, accounts(3))
+    }
+
+    #[test]
+    fn test_bounty_marketplace_done() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_bounty(&mut context);
+        assert!(!export_result(&mut context, &mut contract, false));
+        assert_eq!(contract.get_bounty_listing(0), None);
+        assert!(export_result(&mut context, &mut contract, true));
+        assert_eq!(contract.get_bounty_listing(0), Some(accounts(3)));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.bounty_marketplace_done(0, accounts(2), "done".to_string());
+        assert_eq!(contract.get_bounty_claims(accounts(2)).len(), 1);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+        assert_eq!(contract.get_proposal(id).proposal.proposer, accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_BOUNTY_MARKETPLACE")]
+    fn test_bounty_marketplace_done_not_listed() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_bounty(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.bounty_marketplace_done(0, accounts(2), "done".to_string());
+    }
+}
@@ -0,0 +1,211 @@
+//! Fixed terms of members of group roles, e.g. elected councils: `member_expiry` of the policy sets when the term
+//! of each member ends. Members keep their seat in the role, but lose its permissions and stop counting
+//! towards its thresholds once the term ends, until they are removed or added again.
+
+use near_sdk::json_types::U64;
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Seat of a member of a group role with a term.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExpiringMember {
+    pub role: String,
+    pub account_id: AccountId,
+    /// End of the term, in `period_unit` of the policy.
+    pub expires_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns members of group roles whose term ends within `within` from now, in `period_unit` of the policy,
+    /// including already ended terms. Sorted by end of the term.
+    pub fn get_expiring_members(&self, within: U64) -> Vec<ExpiringMember> {
+        let policy = self.policy.get().unwrap().to_policy();
+        let until = policy.now().saturating_add(within.0);
+        let mut members: Vec<ExpiringMember> = policy
+            .member_expiry
+            .iter()
+            .flat_map(|(role, expiry)| {
+                expiry
+                    .iter()
+                    .map(move |(account_id, expires_at)| ExpiringMember {
+                        role: role.clone(),
+                        account_id: account_id.clone(),
+                        expires_at: *expires_at,
+                    })
+            })
+            .filter(|member| {
+                member.expires_at.0 <= until
+                    && policy.is_group_member(&member.role, &member.account_id)
+            })
+            .collect();
+        members.sort_by_key(|member| member.expires_at.0);
+        members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::bounties::is_bounty_reviewer;
+    use crate::commit_reveal::CommitReveal;
+    use crate::policy::UserInfo;
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_member_expiry() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.member_expiry.insert(
+            "council".to_string(),
+            vec![(accounts(1), U64(1_000)), (accounts(2), U64(100))]
+                .into_iter()
+                .collect(),
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        assert_eq!(
+            contract.get_expiring_members(U64(100)),
+            vec![ExpiringMember {
+                role: "council".to_string(),
+                account_id: accounts(2),
+                expires_at: U64(100),
+            }]
+        );
+
+        testing_env!(context
+            .block_timestamp(100)
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let policy = contract.get_policy();
+        let kind = ProposalKind::Transfer {
+            token_id: String::from(OLD_BASE_TOKEN),
+            receiver_id: accounts(3),
+            amount: U128(to_yocto("1")),
+            msg: None,
+            category: None,
+            batch: None,
+            recurrence: None,
+        };
+        let user = |account_id| UserInfo {
+            account_id,
+            amount: 0,
+        };
+        assert!(
+            !policy
+                .can_execute_action(user(accounts(2)), &kind, &Action::VoteApprove)
+                .1
+        );
+        // Expired member doesn't count towards the threshold of the role.
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_expired_member_cannot_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().member_expiry.insert(
+            "council".to_string(),
+            vec![(accounts(2), U64(100))].into_iter().collect(),
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(3),
+                role: "council".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .block_timestamp(100)
+            .build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_expired_member_loses_membership() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles[1]
+            .permissions
+            .insert("bounty_review".to_string());
+        policy_mut.member_expiry.insert(
+            "council".to_string(),
+            vec![(accounts(2), U64(100))].into_iter().collect(),
+        );
+        let contract = Contract::new(Config::test_config(), policy);
+        testing_env!(context.block_timestamp(100).build());
+        let policy = contract.get_policy();
+        let user = |account_id| UserInfo {
+            account_id,
+            amount: 0,
+        };
+        assert!(policy.is_member(&accounts(1)));
+        assert!(is_bounty_reviewer(&policy, &user(accounts(1))));
+        assert!(!policy.is_member(&accounts(2)));
+        assert!(!is_bounty_reviewer(&policy, &user(accounts(2))));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_expired_member_cannot_commit_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.commit_reveal = Some(CommitReveal {
+            roles: vec!["council".to_string()],
+            reveal_period: U64(100),
+        });
+        policy_mut.member_expiry.insert(
+            "council".to_string(),
+            vec![(accounts(2), U64(100))].into_iter().collect(),
+        );
+        let mut contract = Contract::new(Config::test_config(), policy);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .block_timestamp(100)
+            .build());
+        let commitment =
+            contract.get_vote_commitment(id, accounts(2), Vote::Approve, "salt".to_string());
+        contract.commit_vote(id, commitment);
+    }
+}
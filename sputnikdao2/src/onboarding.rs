@@ -0,0 +1,168 @@
+//! Onboarding of new members of roles listed in `onboarding` of the policy: once a proposal adding them is
+//! executed, they are registered on the token of the DAO, get their initial skill tags and a starter grant,
+//! and a single `ONBOARDING:<json>` event is logged.
+
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{log, serde_json, AccountId, Promise};
+
+use crate::tokens::internal_storage_deposit;
+use crate::types::{GAS_FOR_FT_TRANSFER, ONE_YOCTO_NEAR};
+use crate::*;
+
+/// Template of steps run for new members of given roles.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Onboarding {
+    /// Roles whose new members are onboarded.
+    pub roles: Vec<String>,
+    /// Fungible token new members are registered on, and granted `starter_amount` of. $NEAR if not set.
+    pub token_id: Option<AccountId>,
+    /// Amount granted to new members, zero for no grant.
+    pub starter_amount: U128,
+    /// Skill tags set for new members who don't have any yet.
+    pub skill_tags: Vec<String>,
+}
+
+/// Logged once a new member is onboarded.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct OnboardingEvent {
+    pub member_id: AccountId,
+    pub role: String,
+    /// Token the member is registered on.
+    pub token_id: Option<AccountId>,
+    /// Amount granted, zero if the DAO couldn't afford the grant in $NEAR.
+    pub starter_amount: U128,
+    pub skill_tags: Vec<String>,
+}
+
+impl Contract {
+    /// Onboards new member of given role, if the policy has onboarding for it.
+    /// Failure of the registration or the grant doesn't affect the membership.
+    pub(crate) fn internal_onboard(
+        &mut self,
+        policy: &Policy,
+        member_id: &AccountId,
+        role: &String,
+    ) {
+        let onboarding = match &policy.onboarding {
+            Some(onboarding) if onboarding.roles.contains(role) => onboarding,
+            _ => return,
+        };
+        let mut skill_tags = self.skill_tags.get(member_id).unwrap_or_default();
+        if skill_tags.is_empty() && !onboarding.skill_tags.is_empty() {
+            skill_tags = onboarding.skill_tags.clone();
+            self.skill_tags.insert(member_id, &skill_tags);
+        }
+        let mut starter_amount = onboarding.starter_amount.0;
+        match &onboarding.token_id {
+            Some(token_id) => {
+                let registration = internal_storage_deposit(token_id, member_id);
+                if starter_amount > 0 {
+                    registration.then(ext_fungible_token::ft_transfer(
+                        member_id.clone(),
+                        U128(starter_amount),
+                        Some(format!("Onboarding as {}", role)),
+                        token_id.clone(),
+                        ONE_YOCTO_NEAR,
+                        GAS_FOR_FT_TRANSFER,
+                    ));
+                }
+            }
+            None if starter_amount > self.get_available_amount().0 => starter_amount = 0,
+            None if starter_amount > 0 => {
+                Promise::new(member_id.clone()).transfer(starter_amount);
+            }
+            None => {}
+        }
+        let event = OnboardingEvent {
+            member_id: member_id.clone(),
+            role: role.clone(),
+            token_id: onboarding.token_id.clone(),
+            starter_amount: U128(starter_amount),
+            skill_tags,
+        };
+        log!("ONBOARDING:{}", serde_json::to_string(&event).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_onboarding() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().onboarding = Some(Onboarding {
+            roles: vec!["council".to_string()],
+            token_id: Some(accounts(4)),
+            starter_amount: U128(100),
+            skill_tags: vec!["newcomer".to_string()],
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "welcome".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_skill_tags(accounts(2)),
+            vec!["newcomer".to_string()]
+        );
+        let event = OnboardingEvent {
+            member_id: accounts(2),
+            role: "council".to_string(),
+            token_id: Some(accounts(4)),
+            starter_amount: U128(100),
+            skill_tags: vec!["newcomer".to_string()],
+        };
+        assert!(get_logs().contains(&format!(
+            "ONBOARDING:{}",
+            serde_json::to_string(&event).unwrap()
+        )));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+
+        // Members who are already in the role are not onboarded again.
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "again".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert!(get_logs().iter().all(|log| !log.starts_with("ONBOARDING:")));
+    }
+}
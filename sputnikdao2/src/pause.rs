@@ -0,0 +1,160 @@
+//! Circuit breaker for exploits or contentious forks: members of roles with the `Pause` and `Unpause` permissions,
+//! e.g. `*:Pause`, can stop new proposals, edits of proposals and execution of approved proposals,
+//! including bounty payouts.
+//! Votes still accrue while paused, and proposals approved meanwhile are queued until the DAO is unpaused.
+
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+impl Contract {
+    /// Checks that the DAO is not paused.
+    pub(crate) fn assert_not_paused(&self) {
+        assert!(!self.paused, "ERR_PAUSED");
+    }
+
+    /// Checks that the caller has given permission that isn't tied to a proposal kind.
+    pub(crate) fn assert_can(&self, action: &Action) {
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            policy.can_execute_dao_action(self.internal_user_info(), action),
+            "ERR_PERMISSION_DENIED"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pauses the DAO. Caller needs the `Pause` permission.
+    pub fn pause(&mut self) {
+        self.assert_not_read_only();
+        self.assert_can(&Action::Pause);
+        assert!(!self.paused, "ERR_ALREADY_PAUSED");
+        self.paused = true;
+    }
+
+    /// Unpauses the DAO. Queued proposals approved while paused can then be executed with `Execute`.
+    /// Caller needs the `Unpause` permission.
+    pub fn unpause(&mut self) {
+        self.assert_not_read_only();
+        self.assert_can(&Action::Unpause);
+        assert!(self.paused, "ERR_NOT_PAUSED");
+        self.paused = false;
+    }
+
+    /// Returns true if the DAO is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn paused_contract(context: &mut VMContextBuilder) -> (Contract, u64) {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().roles[1]
+            .permissions
+            .extend(vec!["*:Pause".to_string(), "*:Unpause".to_string()]);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        testing_env!(context.attached_deposit(0).build());
+        contract.pause();
+        (contract, id)
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = paused_contract(&mut context);
+        assert!(contract.is_paused());
+        // Votes accrue, but the approved proposal waits for the DAO to be unpaused.
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Queued
+        );
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Queued
+        );
+
+        contract.unpause();
+        contract.act_proposal(id, Action::Finalize, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED")]
+    fn test_paused_add_proposal() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, _) = paused_contract(&mut context);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED")]
+    fn test_paused_update_proposal() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = paused_contract(&mut context);
+        contract.update_proposal(
+            id,
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: String::from(OLD_BASE_TOKEN),
+                    receiver_id: accounts(2),
+                    amount: U128(to_yocto("2")),
+                    msg: None,
+                    category: None,
+                    batch: None,
+                    recurrence: None,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_unpause_without_permission() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, _) = paused_contract(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.unpause();
+    }
+}
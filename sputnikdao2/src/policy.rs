@@ -6,8 +6,19 @@ use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, AccountId, Balance};
 
+use crate::applicants::Applicants;
+use crate::checkpoints::CheckpointSchedule;
+use crate::commit_reveal::CommitReveal;
+use crate::committees::Committee;
+use crate::evm::EvmBridge;
+use crate::legacy::PolicyV1;
+use crate::onboarding::Onboarding;
 use crate::proposals::{PolicyParameters, Proposal, ProposalKind, ProposalStatus, Vote};
-use crate::types::Action;
+use crate::rebalancing::RebalancingPolicy;
+use crate::reviewers::ReviewerAssignment;
+use crate::spending::SpendingCategories;
+use crate::treasury::{DiversificationLimit, PriceOracle};
+use crate::types::{Action, OldAccountId};
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
@@ -19,6 +30,14 @@ pub enum RoleKind {
     Member(U128),
     /// Set of accounts.
     Group(HashSet<AccountId>),
+    /// Set of accounts voting with given weights in role weighted vote policies, e.g. founders with 2 votes.
+    WeightedGroup(HashMap<AccountId, U128>),
+    /// Holders of at least given amount of delegated tokens before the submission of the proposal.
+    /// Proposals added while the policy has such role take token weight of all votes from balances before
+    /// their submission, so later transfers can't change outcomes.
+    TokenWeight(U128),
+    /// Set of contracts of protocol integrations, added only after confirming the grant, see `dao_role_granted`.
+    Contracts(HashSet<AccountId>),
 }
 
 impl RoleKind {
@@ -26,32 +45,73 @@ impl RoleKind {
     pub fn match_user(&self, user: &UserInfo) -> bool {
         match self {
             RoleKind::Everyone => true,
-            RoleKind::Member(amount) => user.amount >= amount.0,
-            RoleKind::Group(accounts) => accounts.contains(&user.account_id),
+            RoleKind::Member(amount) | RoleKind::TokenWeight(amount) => user.amount >= amount.0,
+            RoleKind::Group(accounts) | RoleKind::Contracts(accounts) => {
+                accounts.contains(&user.account_id)
+            }
+            RoleKind::WeightedGroup(accounts) => accounts.contains_key(&user.account_id),
         }
     }
 
     /// Returns the number of people in the this role or None if not supported role kind.
     pub fn get_role_size(&self) -> Option<usize> {
         match self {
-            RoleKind::Group(accounts) => Some(accounts.len()),
+            RoleKind::Group(accounts) | RoleKind::Contracts(accounts) => Some(accounts.len()),
+            RoleKind::WeightedGroup(accounts) => Some(accounts.len()),
             _ => None,
         }
     }
 
+    /// Returns members of group roles, weighted or not. None for other kinds of roles.
+    pub fn get_group_members(&self) -> Option<HashSet<&AccountId>> {
+        match self {
+            RoleKind::Group(accounts) => Some(accounts.iter().collect()),
+            RoleKind::WeightedGroup(accounts) => Some(accounts.keys().collect()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if given account is a member of this group role, weighted or not.
+    pub fn is_group_member(&self, member_id: &AccountId) -> bool {
+        match self {
+            RoleKind::Group(accounts) => accounts.contains(member_id),
+            RoleKind::WeightedGroup(accounts) => accounts.contains_key(member_id),
+            _ => false,
+        }
+    }
+
+    /// Returns the weight of a vote of given member in role weighted vote policies.
+    pub fn member_weight(&self, member_id: &AccountId) -> Balance {
+        match self {
+            RoleKind::WeightedGroup(accounts) => {
+                accounts.get(member_id).map_or(0, |weight| weight.0)
+            }
+            _ => 1,
+        }
+    }
+
+    /// Adds member to the group. New members of weighted groups get weight of 1.
     pub fn add_member_to_group(&mut self, member_id: &AccountId) -> Result<(), ()> {
         match self {
-            RoleKind::Group(accounts) => {
+            RoleKind::Group(accounts) | RoleKind::Contracts(accounts) => {
                 accounts.insert(member_id.clone());
                 Ok(())
             }
+            RoleKind::WeightedGroup(accounts) => {
+                accounts.entry(member_id.clone()).or_insert(U128(1));
+                Ok(())
+            }
             _ => Err(()),
         }
     }
 
     pub fn remove_member_from_group(&mut self, member_id: &AccountId) -> Result<(), ()> {
         match self {
-            RoleKind::Group(accounts) => {
+            RoleKind::Group(accounts) | RoleKind::Contracts(accounts) => {
+                accounts.remove(member_id);
+                Ok(())
+            }
+            RoleKind::WeightedGroup(accounts) => {
                 accounts.remove(member_id);
                 Ok(())
             }
@@ -80,7 +140,17 @@ pub struct UserInfo {
     pub amount: Balance,
 }
 
+/// Checks if set of permissions allows given action on given kind of proposals, including wildcards.
+fn has_permission(permissions: &HashSet<String>, proposal_kind_label: &str, action: &str) -> bool {
+    permissions.contains(&format!("{}:{}", proposal_kind_label, action))
+        || permissions.contains(&format!("{}:*", proposal_kind_label))
+        || permissions.contains(&format!("*:{}", action))
+        || permissions.contains("*:*")
+}
+
 /// Direct weight or ratio to total weight, used for the voting policy.
+/// For token weighted vote policies, direct weight is an absolute amount of tokens
+/// and doesn't depend on the total supply at the moment of the vote.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde")]
@@ -112,6 +182,23 @@ pub enum WeightKind {
     TokenWeight,
     /// Weight of the group role. Roles that don't have scoped group are not supported.
     RoleWeight,
+    /// Square root of token amounts, limiting dominance of large holders.
+    /// Ratio thresholds are taken of the square root of the total delegated, the weight of a single holder of all tokens.
+    Quadratic,
+}
+
+/// Returns the integer square root of given value, rounded down.
+fn integer_sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
 }
 
 /// Defines configuration of the vote.
@@ -129,6 +216,27 @@ pub struct VotePolicy {
     pub quorum: U128,
     /// How many votes to pass this vote.
     pub threshold: WeightOrRatio,
+    /// For proposals with a weight snapshot, part of the vote weight lost by voting at the end of the voting period,
+    /// in basis points. Weight decays linearly from the submission, so early participation counts more.
+    #[serde(default)]
+    pub weight_decay: u32,
+    /// Participation required to decide, as weight or ratio of the total weight of the role, counting all
+    /// votes including abstentions. If set, ratio `threshold` is the share of votes cast, without abstentions,
+    /// needed for an option instead of the share of the total weight.
+    #[serde(default)]
+    pub participation_quorum: Option<WeightOrRatio>,
+}
+
+/// Vote policy of `Transfer` proposals moving at least `min_amount` of given token, e.g. 2/3 for large transfers.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferTier {
+    /// Can be "" for $NEAR or a valid account id.
+    pub token: OldAccountId,
+    pub min_amount: U128,
+    /// Replaces quorums and thresholds of vote policies of all roles. Votes keep the weight kind and decay of the role.
+    pub vote_policy: VotePolicy,
 }
 
 impl Default for VotePolicy {
@@ -137,10 +245,150 @@ impl Default for VotePolicy {
             weight_kind: WeightKind::RoleWeight,
             quorum: U128(0),
             threshold: WeightOrRatio::Ratio(1, 2),
+            weight_decay: 0,
+            participation_quorum: None,
+        }
+    }
+}
+
+/// Automatic extension of the voting period, to prevent last-second vote sniping.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteExtension {
+    /// If the leading vote changes within this period before the end of voting, voting is extended.
+    pub window: U64,
+    /// Time added to the voting period on each extension.
+    pub increment: U64,
+    /// Maximum number of automatic extensions per proposal.
+    pub max_extensions: u32,
+}
+
+/// Unit in which voting periods and timelocks of the policy are measured.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub enum PeriodUnit {
+    /// Nanoseconds of block timestamps.
+    #[default]
+    Nanoseconds,
+    /// Block heights, not affected by clock skew of block producers.
+    Blocks,
+}
+
+/// What happens to slashed proposal bonds.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub enum BondSlash {
+    /// Bond is kept in the treasury of the DAO.
+    Treasury,
+    /// Bond is locked in the DAO forever, see `get_burned_amount`.
+    Burn,
+}
+
+/// What happens to the bond of a bounty claim given up after the forgiveness period or past its deadline.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub enum BountyForfeiture {
+    /// Bond is returned to the claimer.
+    Refund,
+    /// Share of the bond in basis points is kept in the treasury of the DAO, the rest is returned.
+    PartialSlash { share: u32 },
+    /// Bond is kept in the treasury of the DAO.
+    FullSlash,
+}
+
+impl BountyForfeiture {
+    /// Returns the part of given bond returned to the claimer.
+    pub fn refund(&self, bond: Balance) -> Balance {
+        match self {
+            BountyForfeiture::Refund => bond,
+            BountyForfeiture::PartialSlash { share } => {
+                bond - bond / 10_000 * (*share).min(10_000) as u128
+            }
+            BountyForfeiture::FullSlash => 0,
+        }
+    }
+}
+
+/// Window after approval in which execution of the proposal can be revoked, e.g. when fraud is revealed late.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RevokeGrace {
+    /// Length of the window. After it approved proposal is executed with `Finalize`.
+    pub period: U64,
+    /// Weight of `Revoke` votes from a role required to revoke, e.g. supermajority of 2/3.
+    pub threshold: WeightOrRatio,
+}
+
+/// Recurring window in which approved proposals of given kinds can be executed, e.g. announced maintenance window for upgrades.
+/// Times are in `period_unit` of the policy.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExecutionWindow {
+    /// Policy labels of the kinds restricted to this window, or `*` for all kinds.
+    pub kinds: Vec<String>,
+    /// Opening of the first window.
+    pub start: U64,
+    /// How long each window stays open.
+    pub duration: U64,
+    /// Time between openings of consecutive windows. Zero if the window opens only once.
+    pub interval: U64,
+}
+
+impl ExecutionWindow {
+    /// Returns given time if the window is open then, otherwise its next opening. None if it never opens again.
+    fn next_opening(&self, now: u64) -> Option<u64> {
+        if now < self.start.0 {
+            return Some(self.start.0);
+        }
+        let elapsed = now - self.start.0;
+        if self.interval.0 == 0 {
+            return if elapsed < self.duration.0 {
+                Some(now)
+            } else {
+                None
+            };
+        }
+        let offset = elapsed % self.interval.0;
+        if offset < self.duration.0 {
+            Some(now)
+        } else {
+            Some(now - offset + self.interval.0)
         }
     }
 }
 
+/// External contract that mints non-transferable seat tokens to members of given roles.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeatNft {
+    /// Contract implementing `mint_seat` and `burn_seat`. It must allow this DAO to call them.
+    pub minter_id: AccountId,
+    /// Roles whose members get a seat token.
+    pub roles: Vec<String>,
+}
+
+/// Weight required from given role to decide on a proposal.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleThreshold {
+    /// Name of the role.
+    pub role: String,
+    /// Total weight of the role: number of members or total token supply.
+    pub total_weight: U128,
+    /// Weight of votes for the same option required to approve, reject or remove the proposal.
+    pub threshold: U128,
+    /// Weight of all votes, including abstentions, required before any option decides the proposal.
+    pub quorum: U128,
+}
+
 /// Defines voting / decision making policy of this DAO.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
@@ -158,15 +406,161 @@ pub struct Policy {
     pub bounty_bond: U128,
     /// Period in which giving up on bounty is not punished.
     pub bounty_forgiveness_period: U64,
+    /// Automatic extension of voting when the outcome flips right before the end of voting.
+    #[serde(default)]
+    pub vote_extension: Option<VoteExtension>,
+    /// If true, proposals with the same kind hash as a pending (in progress or failed) one are rejected.
+    #[serde(default)]
+    pub reject_duplicate_proposals: bool,
+    /// Max amount of `Transfer` and bounty proposals in whole tokens, to catch amounts given in wrong decimals.
+    /// Only enforced for $NEAR and tokens with registered metadata.
+    #[serde(default)]
+    pub max_whole_token_amount: Option<U128>,
+    /// Seat tokens minted on `AddMemberToRole` and burnt on `RemoveMemberFromRole` proposals.
+    #[serde(default)]
+    pub seat_nft: Option<SeatNft>,
+    /// Spending categories `Transfer` proposals must be tagged with, and period of spending reports.
+    #[serde(default)]
+    pub spending_categories: Option<SpendingCategories>,
+    /// If set, approved proposals are queued for the grace period, in which they can be revoked.
+    #[serde(default)]
+    pub revoke_grace: Option<RevokeGrace>,
+    /// Roles whose members' proposal bonds are fronted by the DAO, with max amount of bonds fronted at once.
+    /// Members of these roles can submit proposals without attaching the bond while the allowance lasts.
+    #[serde(default)]
+    pub bond_allowances: HashMap<String, U128>,
+    /// Proposal bonds of members of roles, by role and proposal kind label or `*` for all kinds.
+    /// Proposers pay the lowest bond set for their roles, or `proposal_bond` if none is set.
+    #[serde(default)]
+    pub proposal_bonds: HashMap<String, HashMap<String, U128>>,
+    /// Bounty marketplaces trusted to list bounties and report their completion.
+    #[serde(default)]
+    pub bounty_marketplaces: Vec<AccountId>,
+    /// If true, views expose only vote counts of proposals in progress. Individual votes are shown once the proposal is finalized.
+    #[serde(default)]
+    pub hide_votes_until_finalized: bool,
+    /// Paid from the treasury to the first account whose `Finalize` executes or expires a proposal, to cover its gas.
+    #[serde(default)]
+    pub finalizer_reward: Option<U128>,
+    /// If set, proposals of given kinds get a randomly selected reviewer, the only member of the role who can vote on them.
+    #[serde(default)]
+    pub reviewer_assignment: Option<ReviewerAssignment>,
+    /// Max number of proposals in progress at once. New proposals beyond it wait in a queue and are opened in order
+    /// as open proposals are finalized.
+    #[serde(default)]
+    pub max_open_proposals: Option<u32>,
+    /// Oracle reporting prices for `get_treasury_valuation`.
+    #[serde(default)]
+    pub price_oracle: Option<PriceOracle>,
+    /// Unit of `proposal_period`, `vote_extension` and `revoke_grace` periods.
+    #[serde(default)]
+    pub period_unit: PeriodUnit,
+    /// Role whose members can add proposals weighted by an off-chain snapshot, see `add_proposal_with_snapshot`.
+    #[serde(default)]
+    pub snapshot_committer_role: Option<String>,
+    /// Bridge relaying approved `EvmCall` proposals to EVM chains.
+    #[serde(default)]
+    pub evm_bridge: Option<EvmBridge>,
+    /// Bridge or omni-chain router contracts `SendChainMessage` proposals can send messages through.
+    #[serde(default)]
+    pub chain_message_bridges: Vec<AccountId>,
+    /// Schedules of recurring checkpoints the council attests, see `trigger_checkpoint`.
+    #[serde(default)]
+    pub checkpoint_schedules: Vec<CheckpointSchedule>,
+    /// Windows restricting when proposals of some kinds are executed. Approved outside of their windows,
+    /// such proposals are queued until the next window opens.
+    #[serde(default)]
+    pub execution_windows: Vec<ExecutionWindow>,
+    /// Limit on concentration of the treasury in a single token after `Swap` proposals, using prices of `price_oracle`.
+    #[serde(default)]
+    pub diversification_limit: Option<DiversificationLimit>,
+    /// Proposal kinds starting with a temperature check, with number of members who must signal support
+    /// before formal voting, see `signal_proposal`.
+    #[serde(default)]
+    pub temperature_checks: HashMap<String, u32>,
+    /// Share of forfeited bonds of removed proposals paid to the account who removed them, in basis points.
+    /// Rewards cleanup of spam proposals.
+    #[serde(default)]
+    pub forfeited_bond_share: u32,
+    /// Target allocations of the treasury restored by `rebalance`, using prices of `price_oracle`.
+    #[serde(default)]
+    pub rebalancing: Option<RebalancingPolicy>,
+    /// Roles voting by commit-reveal with `commit_vote` and `reveal_vote` instead of `act_proposal`.
+    #[serde(default)]
+    pub commit_reveal: Option<CommitReveal>,
+    /// Steps run for new members of some roles, see `Onboarding`.
+    #[serde(default)]
+    pub onboarding: Option<Onboarding>,
+    /// Window after the first approved `Transfer` of a batch, in `period_unit`, collecting transfers with the same
+    /// `batch` key that are executed together once it closes. Transfers are not batched if not set.
+    #[serde(default)]
+    pub transfer_batch_window: Option<U64>,
+    /// Voting periods of proposal kinds by policy label, overriding `proposal_period`, in `period_unit`.
+    #[serde(default)]
+    pub proposal_periods: HashMap<String, U64>,
+    /// Delay after approval before proposals can be executed with `Execute`, in `period_unit`, giving members
+    /// time to exit before changes land.
+    #[serde(default)]
+    pub execution_delay: Option<U64>,
+    /// Role whose members can `Veto` approved proposals before they are executed, as a safety valve for
+    /// compromised councils. The role also needs the `Veto` permission.
+    #[serde(default)]
+    pub guardian_role: Option<String>,
+    /// Contract implementing `VoteWeightProvider`, whose weights are used for voting on proposals added while set.
+    #[serde(default)]
+    pub vote_weight_provider: Option<AccountId>,
+    /// Self-registration of outside applicants into a propose-only role, see `register_applicant`.
+    #[serde(default)]
+    pub applicants: Option<Applicants>,
+    /// Slashes bonds of rejected proposals instead of returning them.
+    #[serde(default)]
+    pub bond_slash_on_reject: Option<BondSlash>,
+    /// How bonds of proposals removed as spam, by `VoteRemove` or `MarkSpam`, are slashed. Kept in the
+    /// treasury if not set.
+    #[serde(default)]
+    pub bond_slash_on_spam: Option<BondSlash>,
+    /// Window in which `BountyDone` proposals approved by a reviewer with `bounty_review` can be challenged,
+    /// see `bounty_review`. If not set, they can be executed right away.
+    #[serde(default)]
+    pub bounty_challenge_period: Option<U64>,
+    /// Total extension of the deadline of a bounty claim granted on request without approval, in nanoseconds
+    /// like claim deadlines. Longer extensions need approval of a bounty reviewer, see `bounty_request_extension`.
+    #[serde(default)]
+    pub bounty_auto_extension: Option<U64>,
+    /// Period after rejection of a `BountyDone` proposal in which the claimer can dispute it, see `bounty_dispute`.
+    #[serde(default)]
+    pub bounty_dispute_period: Option<U64>,
+    /// How bonds of bounty claims given up after the forgiveness period or past their deadline are forfeited.
+    /// Kept in the treasury if not set.
+    #[serde(default)]
+    pub bounty_forfeiture: Option<BountyForfeiture>,
+    /// End of terms of members of group roles, by role and member, in `period_unit` of the policy.
+    /// Members lose permissions of the role once their term ends, see `get_expiring_members`.
+    #[serde(default)]
+    pub member_expiry: HashMap<String, HashMap<AccountId, U64>>,
+    /// Sub-committees deciding proposals of some kinds on their own, see `Committee`.
+    #[serde(default)]
+    pub committees: Vec<Committee>,
+    /// Maximum number of members of group roles, by role, e.g. 7 for a seven-seat council.
+    /// `AddMemberToRole` proposals fail on execution while the role is full.
+    #[serde(default)]
+    pub max_members: HashMap<String, u32>,
+    /// Vote policies of `Transfer` proposals by the amount moved, matched on creation of the proposal.
+    #[serde(default)]
+    pub transfer_tiers: Vec<TransferTier>,
 }
 
 /// Versioned policy.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde", untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedPolicy {
     /// Default policy with given accounts as council.
     Default(Vec<AccountId>),
+    /// Stored before versioning, see `legacy`.
+    #[serde(skip)]
+    V1(PolicyV1),
     Current(Policy),
 }
 
@@ -206,6 +600,48 @@ pub fn default_policy(council: Vec<AccountId>) -> Policy {
         proposal_period: U64::from(1_000_000_000 * 60 * 60 * 24 * 7),
         bounty_bond: U128(10u128.pow(24)),
         bounty_forgiveness_period: U64::from(1_000_000_000 * 60 * 60 * 24),
+        vote_extension: None,
+        reject_duplicate_proposals: false,
+        max_whole_token_amount: None,
+        seat_nft: None,
+        spending_categories: None,
+        revoke_grace: None,
+        bond_allowances: HashMap::default(),
+        proposal_bonds: HashMap::default(),
+        bounty_marketplaces: vec![],
+        hide_votes_until_finalized: false,
+        finalizer_reward: None,
+        reviewer_assignment: None,
+        max_open_proposals: None,
+        price_oracle: None,
+        period_unit: PeriodUnit::Nanoseconds,
+        snapshot_committer_role: None,
+        evm_bridge: None,
+        chain_message_bridges: vec![],
+        checkpoint_schedules: vec![],
+        execution_windows: vec![],
+        diversification_limit: None,
+        temperature_checks: HashMap::default(),
+        forfeited_bond_share: 0,
+        rebalancing: None,
+        commit_reveal: None,
+        onboarding: None,
+        transfer_batch_window: None,
+        proposal_periods: HashMap::default(),
+        execution_delay: None,
+        guardian_role: None,
+        vote_weight_provider: None,
+        applicants: None,
+        bond_slash_on_reject: None,
+        bond_slash_on_spam: None,
+        bounty_challenge_period: None,
+        bounty_auto_extension: None,
+        bounty_dispute_period: None,
+        bounty_forfeiture: None,
+        member_expiry: HashMap::default(),
+        committees: vec![],
+        max_members: HashMap::default(),
+        transfer_tiers: vec![],
     }
 }
 
@@ -216,6 +652,7 @@ impl VersionedPolicy {
             VersionedPolicy::Default(accounts) => {
                 VersionedPolicy::Current(default_policy(accounts))
             }
+            VersionedPolicy::V1(policy) => VersionedPolicy::Current(policy.into()),
             VersionedPolicy::Current(policy) => VersionedPolicy::Current(policy),
         }
     }
@@ -262,6 +699,49 @@ impl Policy {
         env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role));
     }
 
+    /// Adds new role to the policy. Existing role with the same name is kept.
+    pub fn add_role(&mut self, role: &RolePermission) {
+        if self.roles.iter().any(|r| r.name == role.name) {
+            env::log_str(&format!("ERR_ROLE_EXISTS:{}", role.name));
+            return;
+        }
+        self.roles.push(role.clone());
+    }
+
+    /// Adds and removes permissions of given role.
+    pub fn update_role_permissions(&mut self, role: &String, add: &[String], remove: &[String]) {
+        match self.roles.iter_mut().find(|r| &r.name == role) {
+            Some(role) => {
+                for permission in remove {
+                    role.permissions.remove(permission);
+                }
+                role.permissions.extend(add.iter().cloned());
+            }
+            None => env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role)),
+        }
+    }
+
+    /// Sets vote policy of given role for given proposal kind label. None removes it, so the default applies.
+    pub fn update_role_vote_policy(
+        &mut self,
+        role: &String,
+        proposal_kind_label: &str,
+        vote_policy: &Option<VotePolicy>,
+    ) {
+        match self.roles.iter_mut().find(|r| &r.name == role) {
+            Some(role) => match vote_policy {
+                Some(vote_policy) => {
+                    role.vote_policy
+                        .insert(proposal_kind_label.to_string(), vote_policy.clone());
+                }
+                None => {
+                    role.vote_policy.remove(proposal_kind_label);
+                }
+            },
+            None => env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role)),
+        }
+    }
+
     pub fn update_default_vote_policy(&mut self, vote_policy: &VotePolicy) {
         self.default_vote_policy = vote_policy.clone();
         env::log_str("Successfully updated the default vote policy.");
@@ -283,7 +763,9 @@ impl Policy {
         env::log_str("Successfully updated the policy parameters.");
     }
 
+    /// Adds member to given group role. Members added again start a new term without expiry.
     pub fn add_member_to_role(&mut self, role: &String, member_id: &AccountId) {
+        self.clear_member_expiry(role, member_id);
         for i in 0..self.roles.len() {
             if &self.roles[i].name == role {
                 self.roles[i]
@@ -299,6 +781,7 @@ impl Policy {
     }
 
     pub fn remove_member_from_role(&mut self, role: &String, member_id: &AccountId) {
+        self.clear_member_expiry(role, member_id);
         for i in 0..self.roles.len() {
             if &self.roles[i].name == role {
                 self.roles[i]
@@ -313,11 +796,74 @@ impl Policy {
         env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role));
     }
 
+    fn clear_member_expiry(&mut self, role: &str, member_id: &AccountId) {
+        if let Some(expiry) = self.member_expiry.get_mut(role) {
+            expiry.remove(member_id);
+            if expiry.is_empty() {
+                self.member_expiry.remove(role);
+            }
+        }
+    }
+
+    /// Returns true if the term of given member of given role has ended.
+    pub fn is_expired_member(&self, role: &str, member_id: &AccountId) -> bool {
+        match self
+            .member_expiry
+            .get(role)
+            .and_then(|expiry| expiry.get(member_id))
+        {
+            Some(expires_at) => self.now() >= expires_at.0,
+            None => false,
+        }
+    }
+
+    /// Returns true if the group role with given name has as many members as its `max_members`.
+    /// Members whose term has ended still hold their seat.
+    pub fn is_role_full(&self, role: &String) -> bool {
+        match (
+            self.max_members.get(role),
+            self.internal_get_role(role)
+                .and_then(|role| role.kind.get_group_members()),
+        ) {
+            (Some(max_members), Some(members)) => members.len() >= *max_members as usize,
+            _ => false,
+        }
+    }
+
+    /// Returns true if given account is a member of the group role with given name.
+    pub fn is_group_member(&self, role: &str, member_id: &AccountId) -> bool {
+        self.roles
+            .iter()
+            .any(|r| r.name == role && r.kind.is_group_member(member_id))
+    }
+
+    /// Returns true if role with given name is a role of integration contracts.
+    pub fn is_contract_role(&self, role: &str) -> bool {
+        self.roles
+            .iter()
+            .any(|r| r.name == role && matches!(r.kind, RoleKind::Contracts(_)))
+    }
+
+    /// Returns true if any role of the policy is `RoleKind::TokenWeight`.
+    pub fn has_token_weight_role(&self) -> bool {
+        self.roles
+            .iter()
+            .any(|r| matches!(r.kind, RoleKind::TokenWeight(_)))
+    }
+
+    /// Returns true if given account is a member of any group role.
+    pub fn is_member(&self, member_id: &AccountId) -> bool {
+        self.roles.iter().any(|r| {
+            r.kind.is_group_member(member_id) && !self.is_expired_member(&r.name, member_id)
+        })
+    }
+
     /// Returns set of roles that this user is member of permissions for given user across all the roles it's member of.
     fn get_user_roles(&self, user: UserInfo) -> HashMap<String, &HashSet<String>> {
         let mut roles = HashMap::default();
         for role in self.roles.iter() {
-            if role.kind.match_user(&user) {
+            if role.kind.match_user(&user) && !self.is_expired_member(&role.name, &user.account_id)
+            {
                 roles.insert(role.name.clone(), &role.permissions);
             }
         }
@@ -337,14 +883,11 @@ impl Policy {
         let allowed_roles = roles
             .into_iter()
             .filter_map(|(role, permissions)| {
-                let allowed_role = permissions.contains(&format!(
-                    "{}:{}",
+                let allowed_role = has_permission(
+                    permissions,
                     proposal_kind.to_policy_label(),
-                    action.to_policy_label()
-                )) || permissions
-                    .contains(&format!("{}:*", proposal_kind.to_policy_label()))
-                    || permissions.contains(&format!("*:{}", action.to_policy_label()))
-                    || permissions.contains("*:*");
+                    &action.to_policy_label(),
+                );
                 allowed = allowed || allowed_role;
                 if allowed_role {
                     Some(role)
@@ -356,6 +899,47 @@ impl Policy {
         (allowed_roles, allowed)
     }
 
+    /// Returns true if given user is allowed to execute given action that isn't tied to a proposal kind,
+    /// with a permission for all kinds, e.g. `*:Pause`.
+    pub fn can_execute_dao_action(&self, user: UserInfo, action: &Action) -> bool {
+        self.has_permission(user, "*", action)
+    }
+
+    /// Returns true if given user is allowed to execute given action on proposals with given kind label.
+    pub fn has_permission(
+        &self,
+        user: UserInfo,
+        proposal_kind_label: &str,
+        action: &Action,
+    ) -> bool {
+        self.get_user_roles(user).values().any(|permissions| {
+            has_permission(permissions, proposal_kind_label, &action.to_policy_label())
+        })
+    }
+
+    /// Returns names of all roles that are allowed to execute given action on given kind of proposals.
+    pub fn roles_with_permission(&self, proposal_kind_label: &str, action: &Action) -> Vec<String> {
+        self.roles
+            .iter()
+            .filter(|role| {
+                has_permission(
+                    &role.permissions,
+                    proposal_kind_label,
+                    &action.to_policy_label(),
+                )
+            })
+            .map(|role| role.name.clone())
+            .collect()
+    }
+
+    /// Returns the weight of a vote of given member of given role, for role weighted vote policies.
+    pub fn role_member_weight(&self, role: &String, member_id: &AccountId) -> Balance {
+        self.internal_get_role(role)
+            .expect("ERR_ROLE_NOT_FOUND")
+            .kind
+            .member_weight(member_id)
+    }
+
     /// Returns if given proposal kind is token weighted.
     pub fn is_token_weighted(&self, role: &String, proposal_kind_label: &String) -> bool {
         let role_info = self.internal_get_role(role).expect("ERR_ROLE_NOT_FOUND");
@@ -365,11 +949,54 @@ impl Policy {
             .unwrap_or(&self.default_vote_policy)
             .weight_kind
         {
-            WeightKind::TokenWeight => true,
+            WeightKind::TokenWeight | WeightKind::Quadratic => true,
             _ => false,
         }
     }
 
+    /// Returns weight of a vote with given role and token `amount` on proposals of given kind,
+    /// the square root of the amount for quadratic roles.
+    pub fn token_weight(
+        &self,
+        role: &String,
+        proposal_kind_label: &str,
+        amount: Balance,
+    ) -> Balance {
+        let role_info = self.internal_get_role(role).expect("ERR_ROLE_NOT_FOUND");
+        match role_info
+            .vote_policy
+            .get(proposal_kind_label)
+            .unwrap_or(&self.default_vote_policy)
+            .weight_kind
+        {
+            WeightKind::Quadratic => integer_sqrt(amount),
+            _ => amount,
+        }
+    }
+
+    /// Returns weight of a vote with given role on proposal with a weight snapshot, after the linear decay
+    /// of `weight_decay` of the vote policy over the voting period.
+    pub fn decayed_weight(&self, role: &String, proposal: &Proposal, weight: Balance) -> Balance {
+        let role_info = self.internal_get_role(role).expect("ERR_ROLE_NOT_FOUND");
+        let decay = role_info
+            .vote_policy
+            .get(proposal.kind.to_policy_label())
+            .unwrap_or(&self.default_vote_policy)
+            .weight_decay
+            .min(10_000);
+        if decay == 0 || proposal.weight_snapshot.is_none() {
+            return weight;
+        }
+        let period =
+            self.proposal_period_of(proposal.kind.to_policy_label()) + proposal.voting_extension.0;
+        let elapsed = self
+            .now()
+            .saturating_sub(self.voting_start(proposal))
+            .min(period);
+        let fraction = (elapsed as u128 * 10_000) / period.max(1) as u128;
+        weight.saturating_sub(weight.saturating_mul(decay as u128 * fraction) / 100_000_000)
+    }
+
     fn internal_get_role(&self, name: &String) -> Option<&RolePermission> {
         for role in self.roles.iter() {
             if role.name == *name {
@@ -379,6 +1006,231 @@ impl Policy {
         None
     }
 
+    /// Returns vote policy of given role for proposals of given kind.
+    fn vote_policy_of<'a>(
+        &'a self,
+        role: &'a RolePermission,
+        proposal_kind_label: &str,
+    ) -> &'a VotePolicy {
+        role.vote_policy
+            .get(proposal_kind_label)
+            .unwrap_or(&self.default_vote_policy)
+    }
+
+    /// Returns the vote policy of the highest transfer tier reached by given proposal kind, None if it's not
+    /// a `Transfer` or is below all tiers. Recurring transfers are tiered by the total of all their payouts.
+    pub fn transfer_tier_vote_policy(&self, kind: &ProposalKind) -> Option<VotePolicy> {
+        let (token_id, amount) = kind.transfer_total()?;
+        self.transfer_tiers
+            .iter()
+            .filter(|tier| &tier.token == token_id && tier.min_amount.0 <= amount)
+            .max_by_key(|tier| tier.min_amount.0)
+            .map(|tier| tier.vote_policy.clone())
+    }
+
+    /// Returns vote policy of given role for given proposal: the transfer tier matched on its creation
+    /// or last edit if any, with the weight kind and decay of the role.
+    fn proposal_vote_policy(&self, role: &RolePermission, proposal: &Proposal) -> VotePolicy {
+        let vote_policy = self.vote_policy_of(role, proposal.kind.to_policy_label());
+        match &proposal.tier_vote_policy {
+            Some(tier_vote_policy) => VotePolicy {
+                weight_kind: vote_policy.weight_kind.clone(),
+                weight_decay: vote_policy.weight_decay,
+                ..tier_vote_policy.clone()
+            },
+            None => vote_policy.clone(),
+        }
+    }
+
+    /// Computes weight required from given role to decide on proposal of given kind.
+    /// Returns None for roles that don't provide a total size (`RoleKind::Everyone`).
+    pub fn role_threshold(
+        &self,
+        role: &RolePermission,
+        proposal_kind_label: &str,
+        total_supply: Balance,
+    ) -> Option<RoleThreshold> {
+        self.role_threshold_with(
+            role,
+            self.vote_policy_of(role, proposal_kind_label),
+            total_supply,
+        )
+    }
+
+    /// Computes weight required from given role to decide on given proposal, see `role_threshold`.
+    pub fn proposal_role_threshold(
+        &self,
+        role: &RolePermission,
+        proposal: &Proposal,
+        total_supply: Balance,
+    ) -> Option<RoleThreshold> {
+        self.role_threshold_with(
+            role,
+            &self.proposal_vote_policy(role, proposal),
+            total_supply,
+        )
+    }
+
+    fn role_threshold_with(
+        &self,
+        role: &RolePermission,
+        vote_policy: &VotePolicy,
+        total_supply: Balance,
+    ) -> Option<RoleThreshold> {
+        let token_supply = if vote_policy.weight_kind == WeightKind::Quadratic {
+            integer_sqrt(total_supply)
+        } else {
+            total_supply
+        };
+        let total_weight = match &role.kind {
+            RoleKind::Everyone => return None,
+            RoleKind::Group(group) | RoleKind::Contracts(group) => {
+                if vote_policy.weight_kind == WeightKind::RoleWeight {
+                    group
+                        .iter()
+                        .filter(|member_id| !self.is_expired_member(&role.name, member_id))
+                        .count() as Balance
+                } else {
+                    token_supply
+                }
+            }
+            RoleKind::WeightedGroup(group) => {
+                if vote_policy.weight_kind == WeightKind::RoleWeight {
+                    group
+                        .iter()
+                        .filter(|(member_id, _)| !self.is_expired_member(&role.name, member_id))
+                        .map(|(_, weight)| weight.0)
+                        .sum()
+                } else {
+                    token_supply
+                }
+            }
+            RoleKind::Member(_) | RoleKind::TokenWeight(_) => token_supply,
+        };
+        let threshold = match (&vote_policy.weight_kind, &vote_policy.threshold) {
+            // Absolute amount of tokens is not capped by the current total supply.
+            (WeightKind::TokenWeight | WeightKind::Quadratic, WeightOrRatio::Weight(weight)) => {
+                weight.0
+            }
+            (_, threshold) => threshold.to_weight(total_weight),
+        };
+        Some(RoleThreshold {
+            role: role.name.clone(),
+            total_weight: U128(total_weight),
+            threshold: U128(threshold),
+            quorum: vote_policy.quorum,
+        })
+    }
+
+    /// Returns current time in `period_unit` of the policy.
+    pub fn now(&self) -> u64 {
+        match self.period_unit {
+            PeriodUnit::Nanoseconds => env::block_timestamp(),
+            PeriodUnit::Blocks => env::block_height(),
+        }
+    }
+
+    /// Returns the time when voting on given proposal ends, in `period_unit` of the policy.
+    pub fn voting_end(&self, proposal: &Proposal) -> u64 {
+        self.voting_start(proposal)
+            + self.proposal_period_of(proposal.kind.to_policy_label())
+            + proposal.voting_extension.0
+    }
+
+    /// Returns the voting period of proposals of given kind, in `period_unit` of the policy.
+    pub fn proposal_period_of(&self, proposal_kind_label: &str) -> u64 {
+        self.proposal_periods
+            .get(proposal_kind_label)
+            .unwrap_or(&self.proposal_period)
+            .0
+    }
+
+    /// Returns the proposal bond of members of given role for proposals of given kind, if set in `proposal_bonds`.
+    fn role_bond(&self, role: &str, proposal_kind_label: &str) -> Option<Balance> {
+        self.proposal_bonds
+            .get(role)
+            .and_then(|bonds| bonds.get(proposal_kind_label).or_else(|| bonds.get("*")))
+            .map(|bond| bond.0)
+    }
+
+    /// Returns the proposal bond of members of given role for proposals of given kind.
+    pub fn role_proposal_bond(&self, role: &str, proposal_kind_label: &str) -> Balance {
+        self.role_bond(role, proposal_kind_label)
+            .unwrap_or(self.proposal_bond.0)
+    }
+
+    /// Returns the bond given user attaches to add proposal of given kind: the lowest bond set in
+    /// `proposal_bonds` for the roles allowing the user to add it, or `proposal_bond` if none is set.
+    pub fn proposal_bond_of(&self, user: UserInfo, proposal_kind: &ProposalKind) -> Balance {
+        let label = proposal_kind.to_policy_label();
+        self.can_execute_action(user, proposal_kind, &Action::AddProposal)
+            .0
+            .iter()
+            .filter_map(|role| self.role_bond(role, label))
+            .min()
+            .unwrap_or(self.proposal_bond.0)
+    }
+
+    /// Returns the time when voting on given proposal started, in `period_unit` of the policy.
+    fn voting_start(&self, proposal: &Proposal) -> u64 {
+        match self.period_unit {
+            PeriodUnit::Nanoseconds => proposal.submission_time.0,
+            PeriodUnit::Blocks => proposal.submission_block.0,
+        }
+    }
+
+    /// Returns the next opening of execution windows of given kind, if proposals of this kind can't be executed now.
+    /// Windows that never open again don't restrict execution anymore.
+    pub fn next_execution_window(&self, kind: &ProposalKind) -> Option<u64> {
+        let label = kind.to_policy_label();
+        let now = self.now();
+        let opening = self
+            .execution_windows
+            .iter()
+            .filter(|window| window.kinds.iter().any(|k| k == label || k == "*"))
+            .filter_map(|window| window.next_opening(now))
+            .min()?;
+        if opening > now {
+            Some(opening)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if `Revoke` votes on given proposal reached the revoke threshold of any role
+    /// with the `Revoke` permission. Roles without weight, e.g. empty groups, can't revoke.
+    pub fn is_revoked(&self, proposal: &Proposal, total_supply: Balance) -> bool {
+        let revoke_grace = match &self.revoke_grace {
+            Some(revoke_grace) => revoke_grace,
+            None => return false,
+        };
+        self.roles
+            .iter()
+            .filter(|role| {
+                has_permission(
+                    &role.permissions,
+                    proposal.kind.to_policy_label(),
+                    &Action::Revoke.to_policy_label(),
+                )
+            })
+            .any(
+                |role| match self.proposal_role_threshold(role, proposal, total_supply) {
+                    Some(role_threshold) if role_threshold.total_weight.0 > 0 => {
+                        let threshold = revoke_grace
+                            .threshold
+                            .to_weight(role_threshold.total_weight.0);
+                        let revokes = proposal
+                            .revoke_counts
+                            .get(&role.name)
+                            .copied()
+                            .unwrap_or_default();
+                        revokes > 0 && revokes >= threshold
+                    }
+                    _ => false,
+                },
+            )
+    }
+
     /// Get proposal status for given proposal.
     /// Usually is called after changing it's state.
     pub fn proposal_status(
@@ -394,46 +1246,101 @@ impl Policy {
             ),
             "ERR_PROPOSAL_NOT_IN_PROGRESS"
         );
-        if proposal.submission_time.0 + self.proposal_period.0 < env::block_timestamp() {
+        if self.voting_end(proposal) < self.now() {
             // Proposal expired.
             return ProposalStatus::Expired;
         };
         for role in roles {
-            let role_info = self.internal_get_role(&role).expect("ERR_MISSING_ROLE");
-            let vote_policy = role_info
-                .vote_policy
-                .get(&proposal.kind.to_policy_label().to_string())
-                .unwrap_or(&self.default_vote_policy);
-            let total_weight = match &role_info.kind {
-                // Skip role that covers everyone as it doesn't provide a total size.
-                RoleKind::Everyone => continue,
-                RoleKind::Group(group) => {
-                    if vote_policy.weight_kind == WeightKind::RoleWeight {
-                        group.len() as Balance
-                    } else {
-                        total_supply
-                    }
-                }
-                RoleKind::Member(_) => total_supply,
-            };
-            let threshold = std::cmp::max(
-                vote_policy.quorum.0,
-                vote_policy.threshold.to_weight(total_weight),
-            );
-            // Check if there is anything voted above the threshold specified by policy for given role.
-            let vote_counts = proposal.vote_counts.get(&role).unwrap_or(&[0u128; 3]);
-            if vote_counts[Vote::Approve as usize] >= threshold {
-                return ProposalStatus::Approved;
-            } else if vote_counts[Vote::Reject as usize] >= threshold {
-                return ProposalStatus::Rejected;
-            } else if vote_counts[Vote::Remove as usize] >= threshold {
-                return ProposalStatus::Removed;
-            } else {
-                // continue to next role.
+            if let Some(status) = self.role_decision(proposal, &role, total_supply) {
+                return status;
             }
         }
         proposal.status.clone()
     }
+
+    /// Returns the status votes of given role decided given proposal on, None if they didn't decide it.
+    fn role_decision(
+        &self,
+        proposal: &Proposal,
+        role: &String,
+        total_supply: Balance,
+    ) -> Option<ProposalStatus> {
+        let label = proposal.kind.to_policy_label();
+        if let Some(committee) = self.committee_of(role) {
+            // Committees only decide on kinds delegated to them.
+            if !committee.kinds.iter().any(|kind| kind == label) {
+                return None;
+            }
+        }
+        let role_info = self.internal_get_role(role).expect("ERR_MISSING_ROLE");
+        // Skip role that covers everyone as it doesn't provide a total size.
+        let role_threshold = self.proposal_role_threshold(role_info, proposal, total_supply)?;
+        let vote_counts = proposal.vote_counts.get(role).unwrap_or(&[0u128; 4]);
+        let vote_policy = self.proposal_vote_policy(role_info, proposal);
+        let threshold = match (&vote_policy.participation_quorum, &vote_policy.threshold) {
+            (Some(quorum), threshold) => {
+                let cast: Balance = vote_counts.iter().sum();
+                if cast < quorum.to_weight(role_threshold.total_weight.0) {
+                    return None;
+                }
+                match threshold {
+                    WeightOrRatio::Ratio(..) => threshold
+                        .to_weight(cast - vote_counts[Vote::Abstain as usize])
+                        .max(1),
+                    WeightOrRatio::Weight(_) => role_threshold.threshold.0,
+                }
+            }
+            (None, _) => role_threshold.threshold.0,
+        };
+        // Abstentions count toward the quorum, but never decide on their own.
+        let decides = |vote: Vote| {
+            let count = vote_counts[vote as usize];
+            count >= threshold
+                && count + vote_counts[Vote::Abstain as usize] >= role_threshold.quorum.0
+        };
+        // Check if there is anything voted above the threshold specified by policy for given role.
+        if decides(Vote::Approve) {
+            Some(ProposalStatus::Approved)
+        } else if decides(Vote::Reject) {
+            Some(ProposalStatus::Rejected)
+        } else if decides(Vote::Remove) {
+            Some(ProposalStatus::Removed)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the committee of given role, if it's a committee role.
+    pub fn committee_of(&self, role: &str) -> Option<&Committee> {
+        self.committees
+            .iter()
+            .find(|committee| committee.role == role)
+    }
+
+    /// Returns the committee that approved given proposal, if no role outside committees approved it.
+    pub fn approving_committee(
+        &self,
+        proposal: &Proposal,
+        total_supply: Balance,
+    ) -> Option<&Committee> {
+        let approving: Vec<&RolePermission> = self
+            .roles
+            .iter()
+            .filter(|role| {
+                self.role_decision(proposal, &role.name, total_supply)
+                    == Some(ProposalStatus::Approved)
+            })
+            .collect();
+        if approving
+            .iter()
+            .any(|role| self.committee_of(&role.name).is_none())
+        {
+            return None;
+        }
+        approving
+            .first()
+            .and_then(|role| self.committee_of(&role.name))
+    }
 }
 
 #[cfg(test)]
@@ -454,6 +1361,87 @@ mod tests {
         assert_eq!(r2.to_weight(5), 5);
     }
 
+    #[test]
+    fn test_role_threshold() {
+        let mut policy = default_policy(vec![accounts(0), accounts(1), accounts(2)]);
+        let council = policy.roles[1].clone();
+        assert!(policy.role_threshold(&policy.roles[0], "vote", 0).is_none());
+        assert_eq!(
+            policy.role_threshold(&council, "vote", 0),
+            Some(RoleThreshold {
+                role: "council".to_string(),
+                total_weight: U128(3),
+                threshold: U128(2),
+                quorum: U128(0),
+            })
+        );
+        // Absolute token weight doesn't depend on the total supply.
+        policy.update_default_vote_policy(&VotePolicy {
+            weight_kind: WeightKind::TokenWeight,
+            quorum: U128(0),
+            threshold: WeightOrRatio::Weight(U128(1_000_000)),
+            weight_decay: 0,
+            participation_quorum: None,
+        });
+        assert_eq!(
+            policy
+                .role_threshold(&council, "vote", 10)
+                .unwrap()
+                .threshold,
+            U128(1_000_000)
+        );
+    }
+
+    #[test]
+    fn test_quadratic_weight() {
+        assert_eq!(integer_sqrt(0), 0);
+        assert_eq!(integer_sqrt(15), 3);
+        assert_eq!(integer_sqrt(16), 4);
+        assert_eq!(integer_sqrt(u128::MAX), u64::MAX as u128);
+        let mut policy = default_policy(vec![accounts(0), accounts(1), accounts(2)]);
+        let council = policy.roles[1].clone();
+        policy.update_default_vote_policy(&VotePolicy {
+            weight_kind: WeightKind::Quadratic,
+            quorum: U128(0),
+            threshold: WeightOrRatio::Ratio(1, 2),
+            weight_decay: 0,
+            participation_quorum: None,
+        });
+        assert!(policy.is_token_weighted(&council.name, &"vote".to_string()));
+        assert_eq!(policy.token_weight(&council.name, "vote", 10_000), 100);
+        assert_eq!(
+            policy.role_threshold(&council, "vote", 1_000_000),
+            Some(RoleThreshold {
+                role: "council".to_string(),
+                total_weight: U128(1_000),
+                threshold: U128(501),
+                quorum: U128(0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_roles_with_permission() {
+        let mut policy = default_policy(vec![accounts(0)]);
+        assert_eq!(
+            policy.roles_with_permission("transfer", &Action::VoteApprove),
+            vec!["council".to_string()]
+        );
+        assert!(policy
+            .roles_with_permission("transfer", &Action::RemoveProposal)
+            .is_empty());
+        policy.roles[0]
+            .permissions
+            .insert("transfer:RemoveProposal".to_string());
+        assert_eq!(
+            policy.roles_with_permission("transfer", &Action::RemoveProposal),
+            vec!["all".to_string()]
+        );
+        assert!(policy
+            .roles_with_permission("call", &Action::RemoveProposal)
+            .is_empty());
+    }
+
     #[test]
     fn test_add_role() {
         let council = vec![accounts(0), accounts(1)];
@@ -570,6 +1558,8 @@ mod tests {
             weight_kind: WeightKind::TokenWeight,
             quorum: U128(100),
             threshold: WeightOrRatio::Ratio(1, 4),
+            weight_decay: 0,
+            participation_quorum: None,
         };
         policy.update_default_vote_policy(&new_default_vote_policy);
         assert_eq!(
@@ -0,0 +1,154 @@
+//! Multi-choice polls: members who can vote on a `Poll` proposal select one of its options instead of
+//! approving or rejecting it. Polls are not executed, they expire once voting ends and `get_poll_result`
+//! returns the winning option.
+
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Votes for option with given index on `Poll` proposal, while voting is open.
+    /// Vote of a member weighs their token weight in their first role that is token weighted for polls, otherwise 1.
+    pub fn vote_poll(&mut self, id: u64, option: u32) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let options = match &proposal.kind {
+            ProposalKind::Poll { options } => options.len(),
+            _ => env::panic_str("ERR_NOT_POLL"),
+        };
+        assert!((option as usize) < options, "ERR_NO_POLL_OPTION");
+        let policy = self.policy.get().unwrap().to_policy();
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::InProgress,
+            "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
+        );
+        assert!(
+            policy.now() <= policy.voting_end(&proposal),
+            "ERR_POLL_ENDED"
+        );
+        let (roles, allowed) = policy.can_execute_action(
+            self.internal_voter_info(id, &proposal),
+            &proposal.kind,
+            &Action::VoteApprove,
+        );
+        assert!(allowed && !roles.is_empty(), "ERR_PERMISSION_DENIED");
+        let sender_id = env::predecessor_account_id();
+        assert!(
+            !proposal.poll_votes.contains_key(&sender_id),
+            "ERR_ALREADY_VOTED"
+        );
+        let label = proposal.kind.to_policy_label().to_string();
+        let weight = match roles
+            .iter()
+            .find(|role| policy.is_token_weighted(role, &label))
+        {
+            Some(role) => policy.token_weight(
+                role,
+                &label,
+                self.internal_vote_weight(id, &proposal, &sender_id),
+            ),
+            None => 1,
+        };
+        proposal.poll_tallies.resize(options, 0);
+        proposal.poll_tallies[option as usize] += weight;
+        proposal.poll_votes.insert(sender_id, option);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+    }
+
+    /// Returns index of the option of given poll with the largest tally, the first one on ties.
+    /// None if nobody voted yet.
+    pub fn get_poll_result(&self, id: u64) -> Option<u32> {
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        assert!(
+            matches!(proposal.kind, ProposalKind::Poll { .. }),
+            "ERR_NOT_POLL"
+        );
+        let mut winner: Option<(u32, Balance)> = None;
+        for (index, tally) in proposal.poll_tallies.iter().enumerate() {
+            if *tally > winner.map(|(_, best)| best).unwrap_or_default() {
+                winner = Some((index as u32, *tally));
+            }
+        }
+        winner.map(|(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn contract_with_poll(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        contract.add_proposal(ProposalInput {
+            description: "venue".to_string(),
+            kind: ProposalKind::Poll {
+                options: vec!["Lisbon".to_string(), "Denver".to_string()],
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract
+    }
+
+    #[test]
+    fn test_poll() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_poll(&mut context);
+        assert_eq!(contract.get_poll_result(0), None);
+        contract.vote_poll(0, 1);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.vote_poll(0, 0);
+        assert_eq!(contract.get_poll_result(0), Some(0));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.vote_poll(0, 1);
+        assert_eq!(contract.get_proposal(0).proposal.poll_tallies, vec![1, 2]);
+        assert_eq!(contract.get_poll_result(0), Some(1));
+    }
+
+    #[test]
+    fn test_poll_votes_hidden() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_poll(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.hide_votes_until_finalized = true;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.vote_poll(0, 1);
+        let proposal = contract.get_proposal(0).proposal;
+        assert!(proposal.poll_votes.is_empty());
+        assert_eq!(proposal.poll_tallies, vec![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_HAS_VOTES")]
+    fn test_withdraw_voted_poll() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_poll(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.vote_poll(0, 0);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(0, Action::Withdraw, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_POLL_VOTE")]
+    fn test_approve_poll() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_poll(&mut context);
+        contract.act_proposal(0, Action::VoteApprove, None);
+    }
+}
@@ -2,13 +2,24 @@ use std::collections::HashMap;
 
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128, U64};
+use near_sdk::serde_json::json;
 use near_sdk::{log, AccountId, Balance, Gas, PromiseOrValue};
 
+use crate::bounties::{
+    check_milestone, check_milestones, check_shares, filter_bounty_reviewer_role,
+};
+use crate::commit_reveal::filter_commit_reveal_roles;
+use crate::evm::is_evm_address;
+use crate::integrations::{check_scoped_role, internal_grant_contract_role};
+use crate::legacy::{stored_policy, ProposalV1};
 use crate::policy::UserInfo;
+use crate::recurring::check_recurrence;
+use crate::reviewers::filter_reviewer_role;
+use crate::tokens::internal_storage_deposit;
 use crate::types::{
-    convert_old_to_new_token, Action, Config, OldAccountId, GAS_FOR_FT_TRANSFER, OLD_BASE_TOKEN,
-    ONE_YOCTO_NEAR,
+    convert_old_to_new_token, require, Action, Config, DaoMetadataUpdate, OldAccountId,
+    GAS_FOR_FT_TRANSFER, OLD_BASE_TOKEN, ONE_YOCTO_NEAR,
 };
 use crate::upgrade::{upgrade_remote, upgrade_using_factory};
 use crate::*;
@@ -31,6 +42,91 @@ pub enum ProposalStatus {
     Moved,
     /// If proposal has failed when finalizing. Allowed to re-finalize again to either expire or approved.
     Failed,
+    /// Approved, waiting for the revoke grace period, the execution delay or an execution window to end.
+    /// Executed with `Execute` after it.
+    Queued,
+    /// If execution was revoked in the grace period after approval. Bond is not returned.
+    Revoked,
+    /// Waiting in the queue until fewer than `max_open_proposals` of the policy are in progress.
+    Waiting,
+    /// In the temperature check, until enough members signal support to move it to formal voting.
+    Signaling,
+    /// Cancelled by the guardian role after approval, before execution. Bond is returned.
+    Vetoed,
+    /// Added by an applicant, until a member of a sponsor role co-signs it with `sponsor_proposal`.
+    AwaitingSponsor,
+}
+
+/// State of the bond attached to a proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BondState {
+    /// Bond is held by the DAO until the proposal is resolved.
+    Locked,
+    /// Bond was returned to the proposer.
+    Returned,
+    /// Bond was kept by the DAO (e.g. proposal was removed as spam).
+    Forfeited,
+    /// Bond was burned, see `BondSlash::Burn`.
+    Burned,
+}
+
+/// Sensitive details of the proposal (e.g. compensation figures), encrypted off-chain.
+/// The contract never sees the plaintext, views return the ciphertext as is.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct EncryptedDetails {
+    pub ciphertext: Base64VecU8,
+    /// Hint for members which key decrypts the details, e.g. key id or fingerprint.
+    pub key_hint: String,
+}
+
+/// Evidence kept for removed proposals, whose description and kind are dropped to reclaim storage.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tombstone {
+    /// Hash of the removed description and kind, see `Proposal::content_hash`.
+    pub content_hash: Base58CryptoHash,
+    /// Account whose action removed the proposal.
+    pub removed_by: AccountId,
+    pub removed_at: U64,
+}
+
+/// Length to which return values of executed function calls are truncated.
+const MAX_EXECUTION_RESULT_LEN: usize = 256;
+
+/// Outcome of the last call of an executed `FunctionCall` or `MultiFunctionCall` proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExecutionResult {
+    pub success: bool,
+    /// Return value of the call, truncated to `MAX_EXECUTION_RESULT_LEN` bytes.
+    /// Empty if the call failed, as the runtime doesn't pass failure messages to callbacks.
+    pub value: Base64VecU8,
+    /// Whether the return value was truncated.
+    pub truncated: bool,
+}
+
+impl ExecutionResult {
+    fn new(result: &PromiseResult) -> Self {
+        match result {
+            PromiseResult::Successful(value) => Self {
+                success: true,
+                value: value[..value.len().min(MAX_EXECUTION_RESULT_LEN)]
+                    .to_vec()
+                    .into(),
+                truncated: value.len() > MAX_EXECUTION_RESULT_LEN,
+            },
+            _ => Self {
+                success: false,
+                value: vec![].into(),
+                truncated: false,
+            },
+        }
+    }
 }
 
 /// Function call arguments.
@@ -44,6 +140,15 @@ pub struct ActionCall {
     gas: U64,
 }
 
+/// Calls to a single contract, executed in one promise.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractCall {
+    pub receiver_id: AccountId,
+    pub actions: Vec<ActionCall>,
+}
+
 /// Function call arguments.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
@@ -59,6 +164,7 @@ pub struct PolicyParameters {
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
 #[serde(crate = "near_sdk::serde")]
+#[allow(clippy::large_enum_variant)]
 pub enum ProposalKind {
     /// Change the DAO config.
     ChangeConfig { config: Config },
@@ -91,6 +197,15 @@ pub enum ProposalKind {
         receiver_id: AccountId,
         amount: U128,
         msg: Option<String>,
+        /// Spending category from the policy. Required if the policy has spending categories.
+        #[serde(default)]
+        category: Option<String>,
+        /// Key of the batch this transfer is executed with, if the policy has `transfer_batch_window`.
+        #[serde(default)]
+        batch: Option<String>,
+        /// Pays the transfer out again every `interval`, see `trigger_recurring`.
+        #[serde(default)]
+        recurrence: Option<Recurrence>,
     },
     /// Sets staking contract. Can only be proposed if staking contract is not set yet.
     SetStakingContract { staking_id: AccountId },
@@ -100,6 +215,15 @@ pub enum ProposalKind {
     BountyDone {
         bounty_id: u64,
         receiver_id: AccountId,
+        /// Milestone of the bounty that is done, if it has milestones.
+        #[serde(default)]
+        milestone: Option<u32>,
+        /// Shares of the payout going to co-contributors of the receiver, see `bounty_done_shared`.
+        #[serde(default)]
+        shares: Vec<BountyShare>,
+        /// Rejected `BountyDone` proposal disputed by this one, see `bounty_dispute`. Disputes can't be disputed again.
+        #[serde(default)]
+        dispute_of: Option<u64>,
     },
     /// Just a signaling vote, with no execution.
     Vote,
@@ -113,9 +237,157 @@ pub enum ProposalKind {
     ChangePolicyUpdateDefaultVotePolicy { vote_policy: VotePolicy },
     /// Update the parameters from the policy. This is short cut to updating the whole policy.
     ChangePolicyUpdateParameters { parameters: PolicyParameters },
+    /// Creates or replaces budget with given name.
+    SetBudget { name: String, budget: Budget },
+    /// Update the structured metadata from the config. This is short cut to updating the whole config.
+    ChangeConfigUpdateDaoMetadata { dao_metadata: DaoMetadataUpdate },
+    /// Calls several contracts one after another, each with list of method names in a single promise.
+    /// Next contract is only called if the previous call succeeded, but effects of succeeded calls are not reverted.
+    MultiFunctionCall { calls: Vec<ContractCall> },
+    /// Calls `storage_unregister` on given token to recover the storage deposit of this DAO.
+    /// With `force`, the remaining balance of the DAO in this token is burnt.
+    StorageUnregister { token_id: AccountId, force: bool },
+    /// Registers this DAO in the storage of given token, and adds the token to the whitelist once registered.
+    AddTokenToWhitelist { token_id: AccountId },
+    /// Removes given token from the whitelist.
+    RemoveTokenFromWhitelist { token_id: AccountId },
+    /// Sets keys which can execute proposal kinds without voting in an emergency, see `execute_emergency`.
+    SetEmergencySigners { emergency_signers: EmergencySigners },
+    /// Calls `target` contract on given EVM chain with `calldata`, through the EVM bridge of the policy.
+    EvmCall {
+        chain_id: u64,
+        target: String,
+        calldata: Base64VecU8,
+    },
+    /// Forwards `payload` to `destination_chain` through one of `chain_message_bridges` of the policy.
+    SendChainMessage {
+        bridge_id: AccountId,
+        destination_chain: String,
+        payload: Base64VecU8,
+    },
+    /// Attests report with given hash for the period of the checkpoint schedule. Only added by `trigger_checkpoint`.
+    Checkpoint {
+        name: String,
+        period: u64,
+        report_hash: Base58CryptoHash,
+    },
+    /// Swaps `amount_in` of fungible token `token_in` to `token_out` on `exchange_id`, by `ft_transfer_call` with `msg`.
+    /// Limited by `diversification_limit` of the policy.
+    Swap {
+        exchange_id: AccountId,
+        token_in: AccountId,
+        amount_in: U128,
+        token_out: AccountId,
+        msg: String,
+    },
+    /// Sponsors storage of records of `account_id` on this DAO with `amount` from the treasury.
+    SponsorStorage { account_id: AccountId, amount: U128 },
+    /// Multi-choice poll, voted with `vote_poll` instead of approving or rejecting. Has no execution.
+    Poll { options: Vec<String> },
+    /// Sets parameters of the bounty subsystem, without changing the policy.
+    ChangeBountyConfig { bounty_config: BountyConfig },
+    /// Removes given bounty, returning bonds of its claims and paying `kill_fee` in the bounty token to each claimer.
+    BountyCancel {
+        bounty_id: u64,
+        kill_fee: Option<U128>,
+    },
+    /// Add new role to the policy, unlike `ChangePolicyAddOrUpdateRole` never replacing an existing one.
+    ChangePolicyAddRole { role: RolePermission },
+    /// Add and remove permissions of given role, leaving its other permissions unchanged.
+    ChangePolicyUpdateRolePermissions {
+        role: String,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Set vote policy of given role for given proposal kind label, or remove it with None to use the default.
+    ChangePolicyUpdateVotePolicy {
+        role: String,
+        proposal_kind: String,
+        vote_policy: Option<VotePolicy>,
+    },
 }
 
+/// Labels of all supported kinds of proposals, see `ProposalKind::to_policy_label`.
+pub const PROPOSAL_KIND_LABELS: &[&str] = &[
+    "config",
+    "policy",
+    "add_member_to_role",
+    "remove_member_from_role",
+    "call",
+    "upgrade_self",
+    "upgrade_remote",
+    "transfer",
+    "set_vote_token",
+    "add_bounty",
+    "bounty_done",
+    "vote",
+    "factory_info_update",
+    "policy_add_or_update_role",
+    "policy_remove_role",
+    "policy_update_default_vote_policy",
+    "policy_update_parameters",
+    "set_budget",
+    "config_update_dao_metadata",
+    "multi_call",
+    "storage_unregister",
+    "add_token_to_whitelist",
+    "remove_token_from_whitelist",
+    "set_emergency_signers",
+    "evm_call",
+    "send_chain_message",
+    "checkpoint",
+    "swap",
+    "sponsor_storage",
+    "poll",
+    "bounty_config",
+    "bounty_cancel",
+    "policy_add_role",
+    "policy_update_role_permissions",
+    "policy_update_vote_policy",
+];
+
 impl ProposalKind {
+    /// Returns canonical hash of the kind contents, same for proposals doing the same thing.
+    pub fn hash(&self) -> CryptoHash {
+        env::sha256_array(&self.try_to_vec().unwrap())
+    }
+
+    /// Returns token and total amount paid out by a `Transfer`, including all its recurring payouts.
+    pub(crate) fn transfer_total(&self) -> Option<(&OldAccountId, Balance)> {
+        match self {
+            ProposalKind::Transfer {
+                token_id,
+                amount,
+                recurrence,
+                ..
+            } => Some((
+                token_id,
+                amount.0 * recurrence.as_ref().map_or(1, |recurrence| recurrence.times) as Balance,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns accounts that receive funds or calls from this proposal, for `Transfer` and function calls.
+    pub fn receivers(&self) -> Vec<&AccountId> {
+        match self {
+            ProposalKind::Transfer { receiver_id, .. }
+            | ProposalKind::FunctionCall { receiver_id, .. } => vec![receiver_id],
+            ProposalKind::SendChainMessage { bridge_id, .. } => vec![bridge_id],
+            ProposalKind::Swap { exchange_id, .. } => vec![exchange_id],
+            ProposalKind::MultiFunctionCall { calls } => {
+                let mut receivers: Vec<&AccountId> = vec![];
+                for call in calls {
+                    if !receivers.contains(&&call.receiver_id) {
+                        receivers.push(&call.receiver_id);
+                    }
+                }
+                receivers
+            }
+            _ => vec![],
+        }
+    }
+
     /// Returns label of policy for given type of proposal.
     pub fn to_policy_label(&self) -> &str {
         match self {
@@ -138,17 +410,39 @@ impl ProposalKind {
                 "policy_update_default_vote_policy"
             }
             ProposalKind::ChangePolicyUpdateParameters { .. } => "policy_update_parameters",
+            ProposalKind::SetBudget { .. } => "set_budget",
+            ProposalKind::ChangeConfigUpdateDaoMetadata { .. } => "config_update_dao_metadata",
+            ProposalKind::MultiFunctionCall { .. } => "multi_call",
+            ProposalKind::StorageUnregister { .. } => "storage_unregister",
+            ProposalKind::AddTokenToWhitelist { .. } => "add_token_to_whitelist",
+            ProposalKind::RemoveTokenFromWhitelist { .. } => "remove_token_from_whitelist",
+            ProposalKind::SetEmergencySigners { .. } => "set_emergency_signers",
+            ProposalKind::EvmCall { .. } => "evm_call",
+            ProposalKind::SendChainMessage { .. } => "send_chain_message",
+            ProposalKind::Checkpoint { .. } => "checkpoint",
+            ProposalKind::Swap { .. } => "swap",
+            ProposalKind::SponsorStorage { .. } => "sponsor_storage",
+            ProposalKind::Poll { .. } => "poll",
+            ProposalKind::ChangeBountyConfig { .. } => "bounty_config",
+            ProposalKind::BountyCancel { .. } => "bounty_cancel",
+            ProposalKind::ChangePolicyAddRole { .. } => "policy_add_role",
+            ProposalKind::ChangePolicyUpdateRolePermissions { .. } => {
+                "policy_update_role_permissions"
+            }
+            ProposalKind::ChangePolicyUpdateVotePolicy { .. } => "policy_update_vote_policy",
         }
     }
 }
 
 /// Votes recorded in the proposal.
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Vote {
     Approve = 0x0,
     Reject = 0x1,
     Remove = 0x2,
+    /// Counts toward the quorum, but neither approves, rejects nor removes the proposal.
+    Abstain = 0x3,
 }
 
 impl From<Action> for Vote {
@@ -157,11 +451,24 @@ impl From<Action> for Vote {
             Action::VoteApprove => Vote::Approve,
             Action::VoteReject => Vote::Reject,
             Action::VoteRemove => Vote::Remove,
+            Action::VoteAbstain => Vote::Abstain,
+            Action::ChangeVote(vote) => vote,
             _ => unreachable!(),
         }
     }
 }
 
+impl From<Vote> for Action {
+    fn from(vote: Vote) -> Self {
+        match vote {
+            Vote::Approve => Action::VoteApprove,
+            Vote::Reject => Action::VoteReject,
+            Vote::Remove => Action::VoteRemove,
+            Vote::Abstain => Action::VoteAbstain,
+        }
+    }
+}
+
 /// Proposal that are sent to this DAO.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
@@ -176,30 +483,136 @@ pub struct Proposal {
     /// Current status of the proposal.
     pub status: ProposalStatus,
     /// Count of votes per role per decision: yes / no / spam.
-    pub vote_counts: HashMap<String, [Balance; 3]>,
+    pub vote_counts: HashMap<String, [Balance; 4]>,
     /// Map of who voted and how.
     pub votes: HashMap<AccountId, Vote>,
     /// Submission time (for voting period).
     pub submission_time: U64,
+    /// Block height at submission, for voting period measured in blocks.
+    pub submission_block: U64,
+    /// Time added to the voting period after submission.
+    pub voting_extension: U64,
+    /// Number of automatic voting extensions applied to this proposal.
+    pub auto_extensions: u32,
+    /// Bond locked when the proposal was submitted.
+    pub bond: U128,
+    /// Whether the bond is still locked, or was already returned or forfeited.
+    pub bond_state: BondState,
+    /// Encrypted details of private proposals.
+    pub encrypted_details: Option<EncryptedDetails>,
+    /// Hash of the proposal kind, see `ProposalKind::hash`.
+    pub kind_hash: Base58CryptoHash,
+    /// Time when the proposal was successfully executed.
+    pub executed_at: Option<U64>,
+    /// When queued proposals can be executed: end of the revoke grace period or opening of the execution window,
+    /// in `period_unit` of the policy.
+    pub execution_after: Option<U64>,
+    /// Weight of `Revoke` votes per role.
+    pub revoke_counts: HashMap<String, Balance>,
+    /// Accounts that voted to revoke.
+    pub revoked_by: Vec<AccountId>,
+    /// Role from whose allowance the DAO fronted the bond, if the proposer didn't attach it.
+    pub bond_fronted_by: Option<String>,
+    /// Time when execution waiting for a callback was dispatched. Cleared once the execution is settled.
+    pub execution_started_at: Option<U64>,
+    /// Whether the pending execution was settled by `reconcile_proposal`, so its callback is ignored.
+    pub reconciled: bool,
+    /// Balance of the receiver of the fungible token payout when the execution was dispatched,
+    /// once the token returned it. See `reconcile_proposal`.
+    pub payout_balance_before: Option<U128>,
+    /// Account that was paid `finalizer_reward` of the policy for finalizing this proposal.
+    pub finalized_by: Option<AccountId>,
+    /// Reviewer assigned by `reviewer_assignment` of the policy. Only they can vote with the reviewer role.
+    pub reviewer: Option<AccountId>,
+    /// Snapshot of vote weights, if the proposal was added with one.
+    pub weight_snapshot: Option<WeightSnapshot>,
+    /// Set once the proposal is removed.
+    pub tombstone: Option<Tombstone>,
+    /// Sentiment signaled by members in the temperature check: true for support.
+    pub signals: HashMap<AccountId, bool>,
+    /// Whether token weight of votes is taken from delegated balances before the submission,
+    /// set if the policy had a `TokenWeight` role when the proposal was added.
+    #[serde(default)]
+    pub balance_snapshot: bool,
+    /// Commitments to votes with commit-reveal roles that are not revealed yet.
+    #[serde(default)]
+    pub commitments: HashMap<AccountId, Base58CryptoHash>,
+    /// Options selected by voters of a `Poll`.
+    #[serde(default)]
+    pub poll_votes: HashMap<AccountId, u32>,
+    /// Weight of votes per option of a `Poll`.
+    #[serde(default)]
+    pub poll_tallies: Vec<Balance>,
+    /// Weight each vote added to the vote counts per role, removed if the vote is changed.
+    #[serde(default)]
+    pub vote_weights: HashMap<AccountId, HashMap<String, Balance>>,
+    /// Outcome of the last execution of `FunctionCall` and `MultiFunctionCall` proposals.
+    #[serde(default)]
+    pub execution_result: Option<ExecutionResult>,
+    /// Weight provider of the policy when the proposal was added, whose weights are used for voting on it.
+    #[serde(default)]
+    pub provider_snapshot: Option<ProviderSnapshot>,
+    /// Proposal that must be `Approved` before this one can be executed.
+    #[serde(default)]
+    pub depends_on: Option<u64>,
+    /// Member who co-signed this proposal of an applicant, see `sponsor_proposal`.
+    #[serde(default)]
+    pub sponsor: Option<AccountId>,
+    /// Reviewer who approved this `BountyDone` proposal without a vote, see `bounty_review`. Cleared if challenged.
+    #[serde(default)]
+    pub reviewed_by: Option<AccountId>,
+    /// Budget of the committee that approved this transfer on its own and paid it, see `Committee`.
+    #[serde(default)]
+    pub committee_budget: Option<String>,
+    /// Vote policy of the transfer tier matched on creation or the last edit, see `TransferTier`.
+    #[serde(default)]
+    pub tier_vote_policy: Option<VotePolicy>,
+    /// Delegate who cast the vote of each delegator that voted through it, see `delegate_voting`.
+    #[serde(default)]
+    pub delegated_votes: HashMap<AccountId, AccountId>,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(BorshSerialize, BorshDeserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
-#[serde(crate = "near_sdk::serde")]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedProposal {
-    Default(Proposal),
+    /// Stored before versioning, see `legacy`.
+    Default(ProposalV1),
+    V2(Proposal),
 }
 
 impl From<VersionedProposal> for Proposal {
     fn from(v: VersionedProposal) -> Self {
         match v {
-            VersionedProposal::Default(p) => p,
+            VersionedProposal::Default(p) => p.upgrade(&stored_policy()),
+            VersionedProposal::V2(p) => p,
         }
     }
 }
 
 impl Proposal {
+    /// Returns sha256 of borsh serialized description and kind.
+    pub fn content_hash(&self) -> CryptoHash {
+        env::sha256_array(&(&self.description, &self.kind).try_to_vec().unwrap())
+    }
+
+    /// Marks the proposal as removed by given account, keeping only the hash of its description and kind.
+    /// Votes, bond and timestamps are kept, so removed proposal stays as evidence.
+    pub fn remove(&mut self, removed_by: &AccountId) {
+        self.tombstone = Some(Tombstone {
+            content_hash: self.content_hash().into(),
+            removed_by: removed_by.clone(),
+            removed_at: U64::from(env::block_timestamp()),
+        });
+        self.status = ProposalStatus::Removed;
+        self.description = String::new();
+        self.kind = ProposalKind::Vote;
+        self.encrypted_details = None;
+    }
+
     /// Adds vote of the given user with given `amount` of weight. If user already voted, fails.
+    /// Also votes for `delegators` who delegated voting to the user and haven't voted, in the roles they share
+    /// with the user.
     pub fn update_votes(
         &mut self,
         account_id: &AccountId,
@@ -207,20 +620,153 @@ impl Proposal {
         vote: Vote,
         policy: &Policy,
         user_weight: Balance,
+        delegators: &[UserInfo],
+    ) {
+        self.add_vote_weight(account_id, roles, &vote, policy, user_weight);
+        assert!(
+            self.votes
+                .insert(account_id.clone(), vote.clone())
+                .is_none(),
+            "ERR_ALREADY_VOTED"
+        );
+        let action = Action::from(vote.clone());
+        for delegator in delegators {
+            let (delegator_roles, allowed) = policy.can_execute_action(
+                UserInfo {
+                    account_id: delegator.account_id.clone(),
+                    amount: delegator.amount,
+                },
+                &self.kind,
+                &action,
+            );
+            let shared_roles: Vec<String> = delegator_roles
+                .into_iter()
+                .filter(|role| roles.contains(role))
+                .collect();
+            if !allowed || shared_roles.is_empty() {
+                continue;
+            }
+            self.add_vote_weight(
+                &delegator.account_id,
+                &shared_roles,
+                &vote,
+                policy,
+                delegator.amount,
+            );
+            self.votes
+                .insert(delegator.account_id.clone(), vote.clone());
+            self.delegated_votes
+                .insert(delegator.account_id.clone(), account_id.clone());
+        }
+    }
+
+    /// Removes vote of given account, and the votes it cast for its delegators, from the vote counts,
+    /// before it votes again with `vote`. Delegators then vote along with it in `update_votes`.
+    pub fn remove_vote(&mut self, account_id: &AccountId, vote: &Vote) {
+        let previous = self.votes.get(account_id).expect("ERR_NO_VOTE");
+        assert_ne!(previous, vote, "ERR_SAME_VOTE");
+        let delegators: Vec<AccountId> = self
+            .delegated_votes
+            .iter()
+            .filter(|(_, delegate_id)| *delegate_id == account_id)
+            .map(|(delegator_id, _)| delegator_id.clone())
+            .collect();
+        for voter_id in std::iter::once(account_id).chain(delegators.iter()) {
+            let previous = self.votes.remove(voter_id).expect("ERR_NO_VOTE");
+            let weights = self
+                .vote_weights
+                .remove(voter_id)
+                .expect("ERR_VOTE_WEIGHT_UNKNOWN");
+            for (role, amount) in weights {
+                if let Some(counts) = self.vote_counts.get_mut(&role) {
+                    counts[previous.clone() as usize] -= amount;
+                }
+            }
+            self.delegated_votes.remove(voter_id);
+        }
+    }
+
+    fn add_vote_weight(
+        &mut self,
+        account_id: &AccountId,
+        roles: &[String],
+        vote: &Vote,
+        policy: &Policy,
+        weight: Balance,
     ) {
+        let mut weights = HashMap::new();
         for role in roles {
-            let amount = if policy.is_token_weighted(role, &self.kind.to_policy_label().to_string())
-            {
-                user_weight
+            let label = self.kind.to_policy_label().to_string();
+            let amount = if policy.is_token_weighted(role, &label) {
+                policy.decayed_weight(role, self, policy.token_weight(role, &label, weight))
             } else {
-                1
+                policy.role_member_weight(role, account_id)
             };
-            self.vote_counts.entry(role.clone()).or_insert([0u128; 3])[vote.clone() as usize] +=
+            self.vote_counts.entry(role.clone()).or_insert([0u128; 4])[vote.clone() as usize] +=
                 amount;
+            weights.insert(role.clone(), amount);
         }
-        assert!(
-            self.votes.insert(account_id.clone(), vote).is_none(),
-            "ERR_ALREADY_VOTED"
+        self.vote_weights.insert(account_id.clone(), weights);
+    }
+
+    /// Adds revoke vote of the given user with given `amount` of weight. If user already voted to revoke, fails.
+    pub fn update_revokes(
+        &mut self,
+        account_id: &AccountId,
+        roles: &[String],
+        policy: &Policy,
+        user_weight: Balance,
+    ) {
+        assert!(!self.revoked_by.contains(account_id), "ERR_ALREADY_VOTED");
+        for role in roles {
+            let label = self.kind.to_policy_label().to_string();
+            let amount = if policy.is_token_weighted(role, &label) {
+                policy.token_weight(role, &label, user_weight)
+            } else {
+                policy.role_member_weight(role, account_id)
+            };
+            *self.revoke_counts.entry(role.clone()).or_default() += amount;
+        }
+        self.revoked_by.push(account_id.clone());
+    }
+
+    /// Returns the vote with the largest total weight across all roles, or None if there is a tie.
+    /// Abstentions never lead.
+    pub fn leading_vote(&self) -> Option<Vote> {
+        let mut totals = [0u128; 4];
+        for counts in self.vote_counts.values() {
+            for (total, count) in totals.iter_mut().zip(counts.iter()) {
+                *total += count;
+            }
+        }
+        let max = *totals.iter().max().unwrap();
+        let mut leaders = vec![Vote::Approve, Vote::Reject, Vote::Remove]
+            .into_iter()
+            .filter(|vote| totals[vote.clone() as usize] == max);
+        match (max, leaders.next(), leaders.next()) {
+            (0, _, _) | (_, _, Some(_)) => None,
+            (_, leader, None) => leader,
+        }
+    }
+
+    /// Extends voting by the policy's increment if the leading vote has changed
+    /// within the extension window before the end of voting.
+    pub fn extend_voting_on_flip(&mut self, policy: &Policy, previous_leader: Option<Vote>) {
+        let extension = match &policy.vote_extension {
+            Some(extension) => extension,
+            None => return,
+        };
+        if self.auto_extensions >= extension.max_extensions
+            || self.leading_vote() == previous_leader
+            || policy.now() + extension.window.0 < policy.voting_end(self)
+        {
+            return;
+        }
+        self.voting_extension = U64(self.voting_extension.0 + extension.increment.0);
+        self.auto_extensions += 1;
+        log!(
+            "Voting extended until {} after outcome change",
+            policy.voting_end(self)
         );
     }
 }
@@ -232,6 +778,13 @@ pub struct ProposalInput {
     pub description: String,
     /// Kind of proposal with relevant information.
     pub kind: ProposalKind,
+    /// Client-generated key. Resubmission with the same key by the same account
+    /// within `IDEMPOTENCY_WINDOW` returns id of the existing proposal instead of adding a new one.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// Proposal that must be `Approved` before this one can be executed.
+    #[serde(default)]
+    pub depends_on: Option<u64>,
 }
 
 impl From<ProposalInput> for Proposal {
@@ -239,16 +792,117 @@ impl From<ProposalInput> for Proposal {
         Self {
             proposer: env::predecessor_account_id(),
             description: input.description,
+            kind_hash: input.kind.hash().into(),
             kind: input.kind,
             status: ProposalStatus::InProgress,
             vote_counts: HashMap::default(),
             votes: HashMap::default(),
             submission_time: U64::from(env::block_timestamp()),
+            submission_block: U64::from(env::block_height()),
+            voting_extension: U64(0),
+            auto_extensions: 0,
+            bond: U128(env::attached_deposit()),
+            bond_state: BondState::Locked,
+            encrypted_details: None,
+            executed_at: None,
+            execution_after: None,
+            revoke_counts: HashMap::default(),
+            revoked_by: vec![],
+            bond_fronted_by: None,
+            execution_started_at: None,
+            reconciled: false,
+            payout_balance_before: None,
+            finalized_by: None,
+            reviewer: None,
+            weight_snapshot: None,
+            tombstone: None,
+            signals: HashMap::default(),
+            balance_snapshot: false,
+            commitments: HashMap::default(),
+            poll_votes: HashMap::default(),
+            poll_tallies: vec![],
+            vote_weights: HashMap::default(),
+            execution_result: None,
+            provider_snapshot: None,
+            depends_on: input.depends_on,
+            sponsor: None,
+            reviewed_by: None,
+            committee_budget: None,
+            tier_vote_policy: None,
+            delegated_votes: HashMap::default(),
         }
     }
 }
 
+const GAS_FOR_STORAGE_UNREGISTER: Gas = Gas(10_000_000_000_000);
+
+const GAS_FOR_SEND_CHAIN_MESSAGE: Gas = Gas(20_000_000_000_000);
+
+/// Gas for checking result of a call of `MultiFunctionCall` and dispatching the next one.
+const GAS_FOR_CALL_STEP: Gas = Gas(10_000_000_000_000);
+
+/// Checks that proposal with given id can depend on `depends_on`: only on proposals added before it,
+/// so dependencies can't form cycles.
+fn assert_dependency(depends_on: Option<u64>, id: u64) {
+    if let Some(dependency_id) = depends_on {
+        assert!(dependency_id < id, "ERR_INVALID_DEPENDENCY");
+    }
+}
+
+/// Promise calling given actions on `receiver_id`.
+fn function_call_promise(receiver_id: &AccountId, actions: &[ActionCall]) -> Promise {
+    let mut promise = Promise::new(receiver_id.clone());
+    for action in actions {
+        promise = promise.function_call(
+            action.method_name.clone(),
+            action.args.clone().into(),
+            action.deposit.0,
+            Gas(action.gas.0),
+        )
+    }
+    promise
+}
+
 impl Contract {
+    /// Dispatches call `step` of `MultiFunctionCall`, followed by the check of its result if more calls are left.
+    fn internal_call_step(&self, proposal_id: u64, calls: &[ContractCall], step: usize) -> Promise {
+        let promise = function_call_promise(&calls[step].receiver_id, &calls[step].actions);
+        let rest = &calls[step + 1..];
+        if rest.is_empty() {
+            return promise;
+        }
+        let gas_for_rest = rest
+            .iter()
+            .flat_map(|call| call.actions.iter())
+            .map(|action| action.gas.0)
+            .sum::<u64>()
+            + GAS_FOR_CALL_STEP.0 * rest.len() as u64;
+        promise.then(ext_self::on_call_step(
+            proposal_id,
+            (step + 1) as u32,
+            env::current_account_id(),
+            0,
+            Gas(gas_for_rest),
+        ))
+    }
+
+    /// Starts the temperature check of given new proposal if its kind has one, otherwise opens it for voting.
+    pub(crate) fn internal_start_proposal(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) {
+        if policy
+            .temperature_checks
+            .contains_key(proposal.kind.to_policy_label())
+        {
+            proposal.status = ProposalStatus::Signaling;
+        } else {
+            self.internal_open_or_enqueue(policy, proposal, proposal_id);
+        }
+    }
+
     /// Execute payout of given token to given user.
     pub(crate) fn internal_payout(
         &mut self,
@@ -285,24 +939,134 @@ impl Contract {
         }
     }
 
-    fn internal_return_bonds(&mut self, policy: &Policy, proposal: &Proposal) -> Promise {
-        match &proposal.kind {
-            ProposalKind::BountyDone { .. } => {
-                self.locked_amount -= policy.bounty_bond.0;
-                Promise::new(proposal.proposer.clone()).transfer(policy.bounty_bond.0);
+    /// Pays `finalizer_reward` of the policy to the account that finalized the proposal, once per proposal.
+    /// Not paid if the DAO doesn't have enough available balance.
+    pub(crate) fn internal_reward_finalizer(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        finalizer_id: &AccountId,
+    ) {
+        let reward = match policy.finalizer_reward {
+            Some(reward) if proposal.finalized_by.is_none() => reward.0,
+            _ => return,
+        };
+        if self.get_available_amount().0 < reward {
+            return;
+        }
+        proposal.finalized_by = Some(finalizer_id.clone());
+        Promise::new(finalizer_id.clone()).transfer(reward);
+    }
+
+    /// Returns the bond to the proposer if `refund` is true, otherwise the DAO keeps it.
+    /// Bonds fronted by the DAO are released back to the allowance of the role either way.
+    /// Bond is settled at most once, so repeated resolution of the proposal can't double-refund it.
+    fn internal_settle_bond(&mut self, proposal: &mut Proposal, refund: bool) {
+        if proposal.bond_state != BondState::Locked {
+            return;
+        }
+        self.locked_amount -= proposal.bond.0;
+        if let Some(role) = &proposal.bond_fronted_by {
+            // Bond belongs to the DAO, just release the allowance.
+            let fronted = self.fronted_bonds.get(role).unwrap_or_default();
+            self.fronted_bonds
+                .insert(role, &fronted.saturating_sub(proposal.bond.0));
+        } else if refund && proposal.bond.0 > 0 {
+            Promise::new(proposal.proposer.clone()).transfer(proposal.bond.0);
+        }
+        proposal.bond_state = if refund {
+            BondState::Returned
+        } else {
+            BondState::Forfeited
+        };
+    }
+
+    /// Rejects removed proposal, forfeiting its bond. Pays `forfeited_bond_share` of the policy from the bond
+    /// to the account whose action removed it. Bonds fronted by the DAO are not shared.
+    pub(crate) fn internal_forfeit_proposal(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        remover_id: &AccountId,
+    ) {
+        let shared = proposal.bond_state == BondState::Locked && proposal.bond_fronted_by.is_none();
+        self.internal_reject_proposal(proposal, false);
+        let share = proposal.bond.0 / 10_000 * policy.forfeited_bond_share.min(10_000) as u128;
+        if shared && share > 0 {
+            Promise::new(remover_id.clone()).transfer(share);
+        }
+    }
+
+    /// Slashes the bond of given proposal instead of returning it. Bonds fronted by the DAO are just released.
+    fn internal_slash_bond(&mut self, proposal: &mut Proposal, slash: BondSlash) {
+        let burned = matches!(slash, BondSlash::Burn)
+            && proposal.bond_state == BondState::Locked
+            && proposal.bond_fronted_by.is_none();
+        self.internal_settle_bond(proposal, false);
+        if burned {
+            self.burned_amount += proposal.bond.0;
+            proposal.bond_state = BondState::Burned;
+        }
+    }
+
+    /// Rejects proposal rejected by votes, slashing its bond if the policy has `bond_slash_on_reject`.
+    /// Claims of rejected `BountyDone` proposals are kept while they can be disputed, see `bounty_dispute`.
+    pub(crate) fn internal_reject_by_votes(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) {
+        if let Some(slash) = policy.bond_slash_on_reject {
+            self.internal_slash_bond(proposal, slash);
+        }
+        if self.internal_open_bounty_dispute(policy, proposal, proposal_id) {
+            self.internal_settle_bond(proposal, true);
+        } else {
+            self.internal_reject_proposal(proposal, true);
+        }
+    }
+
+    /// Removes given proposal as spam, slashing its bond as set by `bond_slash_on_spam` of the policy.
+    pub(crate) fn internal_remove_spam(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        remover_id: &AccountId,
+    ) {
+        match policy.bond_slash_on_spam {
+            Some(BondSlash::Burn) => {
+                self.internal_slash_bond(proposal, BondSlash::Burn);
+                self.internal_reject_proposal(proposal, false);
             }
-            _ => {}
+            _ => self.internal_forfeit_proposal(policy, proposal, remover_id),
         }
+        proposal.remove(remover_id);
+    }
 
-        self.locked_amount -= policy.proposal_bond.0;
-        Promise::new(proposal.proposer.clone()).transfer(policy.proposal_bond.0)
+    /// Fronts given proposal bond from the allowance of the first role of the caller that can cover it.
+    /// Returns the role, or None if no allowance of caller's roles can cover it.
+    fn internal_front_bond(&mut self, policy: &Policy, bond: Balance) -> Option<String> {
+        let user = self.internal_user_info();
+        for role in policy.roles.iter() {
+            let allowance = match policy.bond_allowances.get(&role.name) {
+                Some(allowance) if role.kind.match_user(&user) => allowance.0,
+                _ => continue,
+            };
+            let fronted = self.fronted_bonds.get(&role.name).unwrap_or_default();
+            if fronted + bond <= allowance {
+                self.fronted_bonds.insert(&role.name, &(fronted + bond));
+                return Some(role.name.clone());
+            }
+        }
+        None
     }
 
     /// Executes given proposal and updates the contract's state.
-    fn internal_execute_proposal(
+    pub(crate) fn internal_execute_proposal(
         &mut self,
         policy: &Policy,
-        proposal: &Proposal,
+        proposal: &mut Proposal,
         proposal_id: u64,
     ) -> PromiseOrValue<()> {
         let result = match &proposal.kind {
@@ -310,37 +1074,52 @@ impl Contract {
                 self.config.set(config);
                 PromiseOrValue::Value(())
             }
-            ProposalKind::ChangePolicy { policy } => {
-                self.policy.set(policy);
+            ProposalKind::ChangePolicy {
+                policy: versioned_policy,
+            } => {
+                let new_policy = versioned_policy.clone().to_policy();
+                self.internal_burn_dropped_seats(policy, &new_policy);
+                self.policy.set(versioned_policy);
                 PromiseOrValue::Value(())
             }
+            ProposalKind::AddMemberToRole { member_id, role } if policy.is_contract_role(role) => {
+                internal_grant_contract_role(member_id, role).into()
+            }
             ProposalKind::AddMemberToRole { member_id, role } => {
+                if !policy.is_group_member(role, member_id) && policy.is_role_full(role) {
+                    // No free seat: proposal can be finalized again once a member leaves.
+                    log!("ERR_ROLE_FULL:{}", role);
+                    proposal.status = ProposalStatus::Failed;
+                    return PromiseOrValue::Value(());
+                }
                 let mut new_policy = policy.clone();
                 new_policy.add_member_to_role(role, &member_id.clone().into());
+                if !policy.is_group_member(role, member_id)
+                    && new_policy.is_group_member(role, member_id)
+                {
+                    self.internal_mint_seat(&new_policy, member_id, role);
+                    self.internal_onboard(&new_policy, member_id, role);
+                    self.internal_record_joined(member_id, role);
+                }
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
             ProposalKind::RemoveMemberFromRole { member_id, role } => {
                 let mut new_policy = policy.clone();
                 new_policy.remove_member_from_role(role, &member_id.clone().into());
+                if policy.is_group_member(role, member_id)
+                    && !new_policy.is_group_member(role, member_id)
+                {
+                    self.internal_burn_seat(&new_policy, member_id, role);
+                    self.internal_archive_member(policy, member_id, role);
+                }
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
             ProposalKind::FunctionCall {
                 receiver_id,
                 actions,
-            } => {
-                let mut promise = Promise::new(receiver_id.clone().into());
-                for action in actions {
-                    promise = promise.function_call(
-                        action.method_name.clone().into(),
-                        action.args.clone().into(),
-                        action.deposit.0,
-                        Gas(action.gas.0),
-                    )
-                }
-                promise.into()
-            }
+            } => function_call_promise(receiver_id, actions).into(),
             ProposalKind::UpgradeSelf { hash } => {
                 upgrade_using_factory(hash.clone());
                 PromiseOrValue::Value(())
@@ -358,13 +1137,23 @@ impl Contract {
                 receiver_id,
                 amount,
                 msg,
-            } => self.internal_payout(
-                &convert_old_to_new_token(token_id),
-                &receiver_id,
-                amount.0,
-                proposal.description.clone(),
-                msg.clone(),
-            ),
+                ..
+            } => {
+                let (token_id, receiver_id, amount, msg) =
+                    (token_id.clone(), receiver_id.clone(), amount.0, msg.clone());
+                if !self.internal_debit_committee_budget(policy, proposal) {
+                    // Committee budget is exhausted: proposal can be finalized again after a top-up.
+                    proposal.status = ProposalStatus::Failed;
+                    return PromiseOrValue::Value(());
+                }
+                self.internal_payout(
+                    &convert_old_to_new_token(&token_id),
+                    &receiver_id,
+                    amount,
+                    proposal.description.clone(),
+                    msg,
+                )
+            }
             ProposalKind::SetStakingContract { staking_id } => {
                 assert!(self.staking_id.is_none(), "ERR_INVALID_STAKING_CHANGE");
                 self.staking_id = Some(staking_id.clone().into());
@@ -377,8 +1166,18 @@ impl Contract {
             ProposalKind::BountyDone {
                 bounty_id,
                 receiver_id,
-            } => self.internal_execute_bounty_payout(*bounty_id, &receiver_id.clone().into(), true),
-            ProposalKind::Vote => PromiseOrValue::Value(()),
+                milestone,
+                shares,
+                ..
+            } => {
+                if !self.internal_debit_bounty_budget(*bounty_id, *milestone) {
+                    // Budget is exhausted: keep the claim, proposal can be finalized again later.
+                    proposal.status = ProposalStatus::Failed;
+                    return PromiseOrValue::Value(());
+                }
+                self.internal_execute_bounty_payout(*bounty_id, receiver_id, *milestone, shares)
+            }
+            ProposalKind::Vote | ProposalKind::Poll { .. } => PromiseOrValue::Value(()),
             ProposalKind::FactoryInfoUpdate { factory_info } => {
                 internal_set_factory_info(factory_info);
                 PromiseOrValue::Value(())
@@ -386,12 +1185,14 @@ impl Contract {
             ProposalKind::ChangePolicyAddOrUpdateRole { role } => {
                 let mut new_policy = policy.clone();
                 new_policy.add_or_update_role(role);
+                self.internal_burn_dropped_seats(policy, &new_policy);
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
             ProposalKind::ChangePolicyRemoveRole { role } => {
                 let mut new_policy = policy.clone();
                 new_policy.remove_role(role);
+                self.internal_burn_dropped_seats(policy, &new_policy);
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
@@ -407,66 +1208,468 @@ impl Contract {
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
-        };
-        match result {
-            PromiseOrValue::Promise(promise) => promise
-                .then(ext_self::on_proposal_callback(
-                    proposal_id,
-                    env::current_account_id(),
+            ProposalKind::SetBudget { name, budget } => {
+                self.budgets.insert(name, budget);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangeConfigUpdateDaoMetadata { dao_metadata } => {
+                let mut config = self.config.get().unwrap();
+                config.dao_metadata.update(dao_metadata);
+                self.config.set(&config);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::MultiFunctionCall { calls } => {
+                self.internal_call_step(proposal_id, calls, 0).into()
+            }
+            ProposalKind::StorageUnregister { token_id, force } => Promise::new(token_id.clone())
+                .function_call(
+                    "storage_unregister".to_string(),
+                    json!({ "force": force }).to_string().into_bytes(),
+                    ONE_YOCTO_NEAR,
+                    GAS_FOR_STORAGE_UNREGISTER,
+                )
+                .into(),
+            ProposalKind::AddTokenToWhitelist { token_id } => {
+                internal_storage_deposit(token_id, &env::current_account_id()).into()
+            }
+            ProposalKind::RemoveTokenFromWhitelist { token_id } => {
+                self.token_whitelist.remove(token_id);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::SetEmergencySigners { emergency_signers } => {
+                self.emergency_signers = Some(emergency_signers.clone());
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangeBountyConfig { bounty_config } => {
+                self.bounty_config = Some(bounty_config.clone());
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::BountyCancel {
+                bounty_id,
+                kill_fee,
+            } => {
+                if !self.internal_cancel_bounty(*bounty_id, kill_fee.map_or(0, |fee| fee.0)) {
+                    // Claims reported done wait for their proposals, cancel can be finalized again after.
+                    proposal.status = ProposalStatus::Failed;
+                    return PromiseOrValue::Value(());
+                }
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangePolicyAddRole { role } => {
+                let mut new_policy = policy.clone();
+                new_policy.add_role(role);
+                self.policy.set(&VersionedPolicy::Current(new_policy));
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangePolicyUpdateRolePermissions { role, add, remove } => {
+                let mut new_policy = policy.clone();
+                new_policy.update_role_permissions(role, add, remove);
+                self.policy.set(&VersionedPolicy::Current(new_policy));
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ChangePolicyUpdateVotePolicy {
+                role,
+                proposal_kind,
+                vote_policy,
+            } => {
+                let mut new_policy = policy.clone();
+                new_policy.update_role_vote_policy(role, proposal_kind, vote_policy);
+                self.policy.set(&VersionedPolicy::Current(new_policy));
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::EvmCall {
+                chain_id,
+                target,
+                calldata,
+            } => {
+                self.internal_emit_evm_decision(proposal_id, *chain_id, target, calldata);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::SendChainMessage {
+                bridge_id,
+                destination_chain,
+                payload,
+            } => Promise::new(bridge_id.clone())
+                .function_call(
+                    "send_message".to_string(),
+                    json!({ "destination_chain": destination_chain, "payload": payload })
+                        .to_string()
+                        .into_bytes(),
                     0,
-                    GAS_FOR_FT_TRANSFER,
-                ))
+                    GAS_FOR_SEND_CHAIN_MESSAGE,
+                )
                 .into(),
-            PromiseOrValue::Value(()) => self.internal_return_bonds(&policy, &proposal).into(),
+            ProposalKind::Checkpoint { .. } => {
+                self.internal_settle_checkpoint(proposal);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::Swap {
+                exchange_id,
+                token_in,
+                amount_in,
+                token_out,
+                msg,
+            } => {
+                if !self.internal_check_diversification(policy, token_in, amount_in.0, token_out) {
+                    // Swap would concentrate the treasury: proposal can be finalized again once holdings or prices change.
+                    proposal.status = ProposalStatus::Failed;
+                    return PromiseOrValue::Value(());
+                }
+                self.internal_payout(
+                    &Some(token_in.clone()),
+                    exchange_id,
+                    amount_in.0,
+                    format!("Swap proposal {}", proposal_id),
+                    Some(msg.clone()),
+                )
+            }
+            ProposalKind::SponsorStorage { account_id, amount } => {
+                if self.get_available_amount().0 < amount.0 {
+                    proposal.status = ProposalStatus::Failed;
+                    return PromiseOrValue::Value(());
+                }
+                self.internal_sponsor_storage(account_id, amount.0);
+                PromiseOrValue::Value(())
+            }
+        };
+        match result {
+            PromiseOrValue::Promise(promise) => {
+                proposal.execution_started_at = Some(U64::from(env::block_timestamp()));
+                proposal.reconciled = false;
+                self.internal_query_payout_balance(proposal_id, proposal);
+                promise
+                    .then(ext_self::on_proposal_callback(
+                        proposal_id,
+                        env::current_account_id(),
+                        0,
+                        GAS_FOR_FT_TRANSFER,
+                    ))
+                    .into()
+            }
+            PromiseOrValue::Value(()) => {
+                proposal.executed_at = Some(U64::from(env::block_timestamp()));
+                self.internal_settle_bond(proposal, true);
+                PromiseOrValue::Value(())
+            }
         }
     }
 
     pub(crate) fn internal_callback_proposal_success(
         &mut self,
+        proposal_id: u64,
         proposal: &mut Proposal,
     ) -> PromiseOrValue<()> {
-        let policy = self.policy.get().unwrap().to_policy();
-        if let ProposalKind::BountyDone { bounty_id, .. } = proposal.kind {
+        if let ProposalKind::Transfer {
+            token_id,
+            amount,
+            category,
+            ..
+        } = &proposal.kind
+        {
+            self.internal_record_spending(category, token_id, amount.0);
+            self.internal_schedule_recurring(proposal_id, proposal);
+        }
+        if let ProposalKind::AddTokenToWhitelist { token_id } = &proposal.kind {
+            self.token_whitelist.insert(token_id);
+            self.treasury_tokens.insert(token_id);
+        }
+        if let ProposalKind::AddMemberToRole { member_id, role } = &proposal.kind {
+            // Only grants of contract roles wait for the callback.
+            let mut policy = self.policy.get().unwrap().to_policy();
+            policy.add_member_to_role(role, member_id);
+            self.policy.set(&VersionedPolicy::Current(policy));
+            self.internal_record_joined(member_id, role);
+        }
+        if let ProposalKind::BountyDone {
+            bounty_id,
+            receiver_id,
+            milestone,
+            shares,
+            ..
+        } = &proposal.kind
+        {
+            let bounty_id = *bounty_id;
             let mut bounty: Bounty = self.bounties.get(&bounty_id).expect("ERR_NO_BOUNTY").into();
-            if bounty.times == 0 {
-                self.bounties.remove(&bounty_id);
+            if !bounty.is_last_milestone(*milestone) {
+                // Claim stays until the last milestone is paid out.
+                self.internal_complete_milestone(bounty_id, receiver_id, shares);
             } else {
-                bounty.times -= 1;
-                self.bounties
-                    .insert(&bounty_id, &VersionedBounty::Default(bounty));
+                self.internal_remove_claim(bounty_id, receiver_id, true);
+                for share in shares {
+                    self.internal_remove_claim(bounty_id, &share.account_id, true);
+                }
+                if bounty.times == 0 {
+                    self.bounties.remove(&bounty_id);
+                } else {
+                    bounty.times -= 1;
+                    self.bounties
+                        .insert(&bounty_id, &VersionedBounty::V2(bounty));
+                }
             }
         }
         proposal.status = ProposalStatus::Approved;
-        self.internal_return_bonds(&policy, &proposal).into()
+        proposal.executed_at = Some(U64::from(env::block_timestamp()));
+        proposal.execution_started_at = None;
+        self.internal_settle_bond(proposal, true);
+        PromiseOrValue::Value(())
     }
 
     pub(crate) fn internal_callback_proposal_fail(
         &mut self,
         proposal: &mut Proposal,
     ) -> PromiseOrValue<()> {
+        if let ProposalKind::BountyDone {
+            bounty_id,
+            milestone,
+            ..
+        } = proposal.kind
+        {
+            self.internal_credit_bounty_budget(bounty_id, milestone);
+        }
+        self.internal_credit_committee_budget(proposal);
         proposal.status = ProposalStatus::Failed;
+        proposal.execution_started_at = None;
         PromiseOrValue::Value(())
     }
 
-    /// Process rejecting proposal.
-    fn internal_reject_proposal(
-        &mut self,
-        policy: &Policy,
-        proposal: &Proposal,
-        return_bonds: bool,
-    ) -> PromiseOrValue<()> {
-        if return_bonds {
-            // Return bond to the proposer.
-            self.internal_return_bonds(policy, proposal);
+    /// Validates parameters of proposal of given kind.
+    pub(crate) fn internal_validate_proposal(&self, policy: &Policy, kind: &ProposalKind) {
+        if let Err(err) = self.internal_check_proposal(policy, kind) {
+            panic!("{}", err);
         }
-        match &proposal.kind {
+    }
+
+    /// Returns the error parameters of proposal of given kind are invalid with, if any.
+    pub(crate) fn internal_check_proposal(
+        &self,
+        policy: &Policy,
+        kind: &ProposalKind,
+    ) -> Result<(), &'static str> {
+        match kind {
+            ProposalKind::ChangePolicy { policy } => match policy {
+                VersionedPolicy::Current(policy) => {
+                    policy.roles.iter().try_for_each(check_scoped_role)
+                }
+                _ => Err("ERR_INVALID_POLICY"),
+            },
+            ProposalKind::ChangePolicyAddOrUpdateRole { role } => check_scoped_role(role),
+            ProposalKind::Transfer {
+                token_id,
+                msg,
+                amount,
+                category,
+                recurrence,
+                ..
+            } => {
+                require(
+                    !(token_id == OLD_BASE_TOKEN) || msg.is_none(),
+                    "ERR_BASE_TOKEN_NO_MSG",
+                )?;
+                self.internal_check_amount(policy, token_id, amount.0)?;
+                self.internal_check_spending_category(policy, category)?;
+                check_recurrence(recurrence)
+            }
+            ProposalKind::SetStakingContract { .. } => require(
+                self.staking_id.is_none(),
+                "ERR_STAKING_CONTRACT_CANT_CHANGE",
+            ),
+            ProposalKind::MultiFunctionCall { calls } => require(!calls.is_empty(), "ERR_NO_CALLS"),
+            ProposalKind::Poll { options } => require(options.len() >= 2, "ERR_POLL_OPTIONS"),
+            ProposalKind::ChangePolicyAddRole { role } => {
+                require(
+                    policy.roles.iter().all(|r| r.name != role.name),
+                    "ERR_ROLE_EXISTS",
+                )?;
+                check_scoped_role(role)
+            }
+            ProposalKind::ChangePolicyUpdateRolePermissions { role, add, remove } => {
+                let mut new_policy = policy.clone();
+                new_policy.update_role_permissions(role, add, remove);
+                let role = new_policy
+                    .roles
+                    .iter()
+                    .find(|r| &r.name == role)
+                    .ok_or("ERR_ROLE_NOT_FOUND")?;
+                check_scoped_role(role)
+            }
+            ProposalKind::ChangePolicyUpdateVotePolicy { role, .. } => require(
+                policy.roles.iter().any(|r| &r.name == role),
+                "ERR_ROLE_NOT_FOUND",
+            ),
+            ProposalKind::BountyCancel {
+                bounty_id,
+                kill_fee,
+            } => {
+                let bounty: Bounty = self.bounties.get(bounty_id).ok_or("ERR_NO_BOUNTY")?.into();
+                match kill_fee {
+                    Some(kill_fee) => self.internal_check_amount(policy, &bounty.token, kill_fee.0),
+                    None => Ok(()),
+                }
+            }
+            ProposalKind::ChangeBountyConfig { bounty_config } => {
+                match &bounty_config.reviewer_role {
+                    Some(reviewer_role) => require(
+                        policy.roles.iter().any(|role| &role.name == reviewer_role),
+                        "ERR_MISSING_ROLE",
+                    ),
+                    None => Ok(()),
+                }
+            }
+            ProposalKind::EvmCall {
+                chain_id, target, ..
+            } => {
+                let bridge = policy.evm_bridge.as_ref().ok_or("ERR_NO_EVM_BRIDGE")?;
+                require(bridge.chain_ids.contains(chain_id), "ERR_UNKNOWN_EVM_CHAIN")?;
+                require(is_evm_address(target), "ERR_INVALID_EVM_ADDRESS")
+            }
+            ProposalKind::SetEmergencySigners { emergency_signers } => require(
+                emergency_signers.threshold > 0
+                    && emergency_signers.threshold as usize <= emergency_signers.public_keys.len(),
+                "ERR_INVALID_EMERGENCY_THRESHOLD",
+            ),
+            ProposalKind::Checkpoint { .. } => Err("ERR_CHECKPOINT_SCHEDULED_ONLY"),
+            ProposalKind::SendChainMessage { bridge_id, .. } => require(
+                policy.chain_message_bridges.contains(bridge_id),
+                "ERR_UNKNOWN_BRIDGE",
+            ),
+            ProposalKind::AddBounty { bounty } => {
+                self.internal_check_amount(policy, &bounty.token, bounty.amount.0)?;
+                check_milestones(bounty)?;
+                match &bounty.budget {
+                    Some(name) => {
+                        let budget = self.budgets.get(name).ok_or("ERR_NO_BUDGET")?;
+                        require(budget.token == bounty.token, "ERR_BUDGET_WRONG_TOKEN")
+                    }
+                    None => Ok(()),
+                }
+            }
             ProposalKind::BountyDone {
                 bounty_id,
                 receiver_id,
+                milestone,
+                shares,
+                ..
             } => {
-                self.internal_execute_bounty_payout(*bounty_id, &receiver_id.clone().into(), false)
+                let bounty: Bounty = self.bounties.get(bounty_id).ok_or("ERR_NO_BOUNTY")?.into();
+                check_milestone(&bounty, *milestone)?;
+                check_shares(receiver_id, shares)
+            }
+            // TODO: add more verifications.
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks there is no pending proposal of given kind, if the policy rejects duplicates.
+    fn internal_assert_not_duplicate(&self, policy: &Policy, kind: &ProposalKind) {
+        assert!(
+            !self.internal_is_duplicate(policy, kind),
+            "ERR_DUPLICATE_PROPOSAL"
+        );
+    }
+
+    /// Returns true if the policy rejects duplicates and there is a pending proposal of given kind.
+    pub(crate) fn internal_is_duplicate(&self, policy: &Policy, kind: &ProposalKind) -> bool {
+        if !policy.reject_duplicate_proposals {
+            return false;
+        }
+        self.proposals_by_hash
+            .all(&kind.hash())
+            .iter()
+            .filter_map(|same_kind_id| self.proposals.get(same_kind_id))
+            .any(|other| {
+                matches!(
+                    Proposal::from(other).status,
+                    ProposalStatus::InProgress | ProposalStatus::Failed
+                )
+            })
+    }
+
+    /// Adds given proposal to the indices by hash of its kind and by receivers.
+    fn internal_index_proposal(&mut self, id: u64, kind: &ProposalKind) {
+        self.proposals_by_hash.insert(&kind.hash(), id);
+        for receiver_id in kind.receivers() {
+            self.proposals_by_receiver.insert(receiver_id, id);
+        }
+    }
+
+    /// Removes given proposal from the indices by hash of its kind and by receivers.
+    fn internal_unindex_proposal(&mut self, id: u64, kind: &ProposalKind) {
+        self.proposals_by_hash.remove(&kind.hash(), id);
+        for receiver_id in kind.receivers() {
+            self.proposals_by_receiver.remove(receiver_id, id);
+        }
+    }
+
+    /// Process rejecting proposal.
+    /// Bonds of the proposal and of the bounty claim it reports are returned if `return_bonds` is true.
+    pub(crate) fn internal_reject_proposal(&mut self, proposal: &mut Proposal, return_bonds: bool) {
+        self.internal_settle_bond(proposal, return_bonds);
+        self.internal_settle_checkpoint(proposal);
+        if let ProposalKind::BountyDone {
+            bounty_id,
+            receiver_id,
+            shares,
+            ..
+        } = &proposal.kind
+        {
+            self.internal_remove_claim(*bounty_id, receiver_id, return_bonds);
+            for share in shares {
+                self.internal_remove_claim(*bounty_id, &share.account_id, return_bonds);
             }
-            _ => PromiseOrValue::Value(()),
+        }
+    }
+
+    /// Executes just approved proposal, or queues it for the revoke grace period or the execution delay
+    /// if the policy has them.
+    pub(crate) fn internal_approve_proposal(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) {
+        let revoke_period = policy.revoke_grace.as_ref().map(|grace| grace.period.0);
+        let execution_delay = policy.execution_delay.map(|delay| delay.0);
+        // Queued proposals can be revoked until they can be executed.
+        if let Some(delay) = std::cmp::max(revoke_period, execution_delay) {
+            proposal.status = ProposalStatus::Queued;
+            proposal.execution_after = Some(U64(policy.now() + delay));
+        } else {
+            self.internal_execute_in_window(policy, proposal, proposal_id);
+        }
+    }
+
+    /// Returns true if the proposal given proposal depends on, if any, is approved.
+    fn internal_dependency_approved(&self, proposal: &Proposal) -> bool {
+        match proposal.depends_on {
+            Some(dependency_id) => {
+                let dependency: Proposal = self
+                    .proposals
+                    .get(&dependency_id)
+                    .expect("ERR_NO_PROPOSAL")
+                    .into();
+                dependency.status == ProposalStatus::Approved
+            }
+            None => true,
+        }
+    }
+
+    /// Executes approved proposal if the execution window of its kind is open, otherwise queues it until it opens.
+    /// Proposals whose dependency isn't approved yet are queued until it is.
+    fn internal_execute_in_window(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) {
+        if self.paused || !self.internal_dependency_approved(proposal) {
+            // Executed with `Execute` once unpaused and the dependency is approved.
+            proposal.status = ProposalStatus::Queued;
+            proposal.execution_after = Some(U64(policy.now()));
+        } else if let Some(opening) = policy.next_execution_window(&proposal.kind) {
+            proposal.status = ProposalStatus::Queued;
+            proposal.execution_after = Some(U64(opening));
+        } else if !self.internal_batch_transfer(policy, proposal, proposal_id) {
+            self.internal_execute_proposal(policy, proposal, proposal_id);
         }
     }
 
@@ -484,35 +1687,33 @@ impl Contract {
     /// Add proposal to this DAO.
     #[payable]
     pub fn add_proposal(&mut self, proposal: ProposalInput) -> u64 {
-        // 0. validate bond attached.
+        self.assert_not_read_only_unless_upgrade(&proposal.kind);
+        self.assert_not_paused();
+        // 0. Return proposal already added with the same idempotency key.
+        if let Some(key) = &proposal.idempotency_key {
+            if let Some(id) = self.internal_resubmitted_proposal(key) {
+                return id;
+            }
+        }
+
+        // 0. validate bond attached, unless the DAO fronts it from the allowance of caller's role.
         // TODO: consider bond in the token of this DAO.
         let policy = self.policy.get().unwrap().to_policy();
 
-        assert_eq!(
-            env::attached_deposit(),
-            policy.proposal_bond.0,
-            "ERR_MIN_BOND"
-        );
+        let bond = policy.proposal_bond_of(self.internal_user_info(), &proposal.kind);
+        let bond_fronted_by = if env::attached_deposit() == 0 && bond > 0 {
+            Some(
+                self.internal_front_bond(&policy, bond)
+                    .expect("ERR_MIN_BOND"),
+            )
+        } else {
+            assert_eq!(env::attached_deposit(), bond, "ERR_MIN_BOND");
+            None
+        };
 
         // 1. Validate proposal.
-        match &proposal.kind {
-            ProposalKind::ChangePolicy { policy } => match policy {
-                VersionedPolicy::Current(_) => {}
-                _ => panic!("ERR_INVALID_POLICY"),
-            },
-            ProposalKind::Transfer { token_id, msg, .. } => {
-                assert!(
-                    !(token_id == OLD_BASE_TOKEN) || msg.is_none(),
-                    "ERR_BASE_TOKEN_NO_MSG"
-                );
-            }
-            ProposalKind::SetStakingContract { .. } => assert!(
-                self.staking_id.is_none(),
-                "ERR_STAKING_CONTRACT_CANT_CHANGE"
-            ),
-            // TODO: add more verifications.
-            _ => {}
-        };
+        self.internal_validate_proposal(&policy, &proposal.kind);
+        assert_dependency(proposal.depends_on, self.last_proposal_id);
 
         // 2. Check permission of caller to add this type of proposal.
         assert!(
@@ -526,56 +1727,224 @@ impl Contract {
             "ERR_PERMISSION_DENIED"
         );
 
-        // 3. Actually add proposal to the current list of proposals.
+        // 3. Check there is no pending proposal doing the same.
+        self.internal_assert_not_duplicate(&policy, &proposal.kind);
+
+        // 4. Rate limit proposals of applicants, which need a sponsor.
+        let awaits_sponsor =
+            self.internal_rate_limit_applicant(&policy, &env::predecessor_account_id());
+
+        // 5. Actually add proposal to the current list of proposals.
         let id = self.last_proposal_id;
-        self.proposals
-            .insert(&id, &VersionedProposal::Default(proposal.into()));
+        self.internal_index_proposal(id, &proposal.kind);
+        self.internal_index_proposal_keywords(id, &proposal.description);
+        if let Some(key) = proposal.idempotency_key.clone() {
+            self.internal_record_idempotency_key(key, id);
+        }
+        let mut proposal: Proposal = proposal.into();
+        self.internal_record_authored(&policy, &proposal.proposer);
+        if bond_fronted_by.is_some() {
+            proposal.bond = U128(bond);
+            proposal.bond_fronted_by = bond_fronted_by;
+        }
+        proposal.reviewer = self.internal_assign_reviewer(&policy, &proposal, id);
+        proposal.balance_snapshot = policy.has_token_weight_role();
+        proposal.provider_snapshot = self.internal_provider_snapshot(&policy, id);
+        proposal.tier_vote_policy = policy.transfer_tier_vote_policy(&proposal.kind);
+        if awaits_sponsor {
+            proposal.status = ProposalStatus::AwaitingSponsor;
+        } else {
+            self.internal_start_proposal(&policy, &mut proposal, id);
+        }
+        self.internal_record_transition(id, &proposal.status);
+        self.locked_amount += proposal.bond.0;
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
         self.last_proposal_id += 1;
-        self.locked_amount += env::attached_deposit();
         id
     }
 
+    /// Add proposal with sensitive details encrypted by the proposer.
+    /// Details are stored and returned by views only as ciphertext, and memos on such proposals are not logged.
+    /// Only `Vote` proposals can be private, as the kind of any other proposal is stored in plaintext to be executed.
+    #[payable]
+    pub fn add_private_proposal(
+        &mut self,
+        proposal: ProposalInput,
+        encrypted_details: EncryptedDetails,
+    ) -> u64 {
+        self.assert_not_read_only();
+        assert!(
+            matches!(proposal.kind, ProposalKind::Vote),
+            "ERR_PRIVATE_PROPOSAL_NOT_VOTE"
+        );
+        assert!(
+            !encrypted_details.ciphertext.0.is_empty(),
+            "ERR_EMPTY_CIPHERTEXT"
+        );
+        let expected_id = self.last_proposal_id;
+        let id = self.add_proposal(proposal);
+        if id != expected_id {
+            // Resubmission with the same idempotency key.
+            return id;
+        }
+        let mut proposal: Proposal = self.proposals.get(&id).unwrap().into();
+        proposal.encrypted_details = Some(encrypted_details);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        id
+    }
+
+    /// Replaces description, kind and dependency of own proposal as long as no votes were cast on it,
+    /// keeping its id and submission time. The kind must stay of the same policy label.
+    /// Idempotency key of the input is ignored.
+    pub fn update_proposal(&mut self, id: u64, proposal: ProposalInput) {
+        self.assert_not_read_only();
+        self.assert_not_paused();
+        let mut current: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        assert_eq!(
+            current.proposer,
+            env::predecessor_account_id(),
+            "ERR_NOT_PROPOSER"
+        );
+        assert!(
+            matches!(
+                current.status,
+                ProposalStatus::InProgress
+                    | ProposalStatus::Waiting
+                    | ProposalStatus::AwaitingSponsor
+            ),
+            "ERR_PROPOSAL_NOT_IN_PROGRESS"
+        );
+        assert!(
+            current.votes.is_empty()
+                && current.commitments.is_empty()
+                && current.poll_votes.is_empty(),
+            "ERR_PROPOSAL_HAS_VOTES"
+        );
+        assert_eq!(
+            proposal.kind.to_policy_label(),
+            current.kind.to_policy_label(),
+            "ERR_WRONG_KIND"
+        );
+        self.internal_validate_proposal(&policy, &proposal.kind);
+        assert_dependency(proposal.depends_on, id);
+        assert!(
+            policy
+                .can_execute_action(
+                    self.internal_user_info(),
+                    &proposal.kind,
+                    &Action::AddProposal
+                )
+                .1,
+            "ERR_PERMISSION_DENIED"
+        );
+        self.internal_unindex_proposal(id, &current.kind);
+        self.internal_assert_not_duplicate(&policy, &proposal.kind);
+        self.internal_index_proposal(id, &proposal.kind);
+        self.internal_unindex_proposal_keywords(id, &current.description);
+        self.internal_index_proposal_keywords(id, &proposal.description);
+        current.description = proposal.description;
+        current.kind_hash = proposal.kind.hash().into();
+        current.kind = proposal.kind;
+        current.tier_vote_policy = policy.transfer_tier_vote_policy(&current.kind);
+        current.depends_on = proposal.depends_on;
+        self.proposals.insert(&id, &VersionedProposal::V2(current));
+    }
+
     /// Act on given proposal by id, if permissions allow.
     /// Memo is logged but not stored in the state. Can be used to leave notes or explain the action.
     pub fn act_proposal(&mut self, id: u64, action: Action, memo: Option<String>) {
         let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        self.assert_not_read_only_unless_upgrade(&proposal.kind);
         let policy = self.policy.get().unwrap().to_policy();
         // Check permissions for the given action.
-        let (roles, allowed) =
-            policy.can_execute_action(self.internal_user_info(), &proposal.kind, &action);
-        assert!(allowed, "ERR_PERMISSION_DENIED");
+        let (mut roles, allowed) = policy.can_execute_action(
+            self.internal_voter_info(id, &proposal),
+            &proposal.kind,
+            &action,
+        );
+        assert!(
+            allowed || matches!(action, Action::Withdraw),
+            "ERR_PERMISSION_DENIED"
+        );
         let sender_id = env::predecessor_account_id();
+        let total_weight = self.internal_total_weight(&proposal);
         // Update proposal given action. Returns true if should be updated in storage.
+        let private = proposal.encrypted_details.is_some();
+        let was_open = proposal.status == ProposalStatus::InProgress;
+        let status_before = proposal.status.clone();
+        let removing = matches!(action, Action::RemoveProposal | Action::MarkSpam);
         let update = match action {
             Action::AddProposal => env::panic_str("ERR_WRONG_ACTION"),
             Action::RemoveProposal => {
-                self.proposals.remove(&id);
-                false
+                assert!(proposal.tombstone.is_none(), "ERR_PROPOSAL_REMOVED");
+                if proposal.bond_state == BondState::Locked {
+                    // Bond is still locked until the callback of execution arrives.
+                    assert_ne!(
+                        proposal.status,
+                        ProposalStatus::Approved,
+                        "ERR_PROPOSAL_EXECUTION_PENDING"
+                    );
+                    self.internal_forfeit_proposal(&policy, &mut proposal, &sender_id);
+                }
+                proposal.remove(&sender_id);
+                true
             }
-            Action::VoteApprove | Action::VoteReject | Action::VoteRemove => {
+            Action::VoteApprove
+            | Action::VoteReject
+            | Action::VoteRemove
+            | Action::VoteAbstain
+            | Action::ChangeVote(_) => {
                 assert!(
                     matches!(proposal.status, ProposalStatus::InProgress),
                     "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
                 );
+                filter_reviewer_role(&policy, &proposal, &sender_id, &mut roles);
+                filter_bounty_reviewer_role(&self.bounty_config, &proposal, &mut roles);
+                filter_commit_reveal_roles(&policy, &mut roles);
+                let leading_vote = proposal.leading_vote();
+                let changing = matches!(action, Action::ChangeVote(_));
+                let vote = Vote::from(action);
+                assert!(
+                    vote == Vote::Remove || !matches!(proposal.kind, ProposalKind::Poll { .. }),
+                    "ERR_POLL_VOTE"
+                );
+                let counts_before = proposal.vote_counts.clone();
+                let user_weight = self.internal_vote_weight(id, &proposal, &sender_id);
+                if changing {
+                    proposal.remove_vote(&sender_id, &vote);
+                }
                 proposal.update_votes(
                     &sender_id,
                     &roles,
-                    Vote::from(action),
+                    vote.clone(),
                     &policy,
-                    self.get_user_weight(&sender_id),
+                    user_weight,
+                    &self.internal_resolve_delegators(id, &proposal, &sender_id),
+                );
+                self.internal_record_approval(&policy, &sender_id, &vote);
+                self.internal_record_delegate_vote(&sender_id, id, vote);
+                self.internal_log_vote(
+                    id,
+                    &proposal,
+                    &sender_id,
+                    &roles,
+                    &counts_before,
+                    memo.as_ref(),
                 );
                 // Updates proposal status with new votes using the policy.
-                proposal.status =
-                    policy.proposal_status(&proposal, roles, self.total_delegation_amount);
+                proposal.status = policy.proposal_status(&proposal, roles, total_weight);
+                if proposal.status == ProposalStatus::InProgress {
+                    proposal.extend_voting_on_flip(&policy, leading_vote);
+                }
                 if proposal.status == ProposalStatus::Approved {
-                    self.internal_execute_proposal(&policy, &proposal, id);
+                    self.internal_approve_proposal(&policy, &mut proposal, id);
                     true
                 } else if proposal.status == ProposalStatus::Removed {
-                    self.internal_reject_proposal(&policy, &proposal, false);
-                    self.proposals.remove(&id);
-                    false
+                    self.internal_remove_spam(&policy, &mut proposal, &sender_id);
+                    true
                 } else if proposal.status == ProposalStatus::Rejected {
-                    self.internal_reject_proposal(&policy, &proposal, true);
+                    self.internal_reject_by_votes(&policy, &mut proposal, id);
                     true
                 } else {
                     // Still in progress or expired.
@@ -589,33 +1958,167 @@ impl Contract {
             //  - if proposal expired during the failed state - it will be marked as expired.
             //  - if the number of votes in the group has changed (new members has been added) -
             //      the proposal can loose it's approved state. In this case new proposal needs to be made, this one can only expire.
+            Action::Execute | Action::Finalize if proposal.status == ProposalStatus::Queued => {
+                assert!(
+                    !self.internal_is_batched(&proposal, id),
+                    "ERR_PROPOSAL_BATCHED"
+                );
+                assert!(
+                    policy.now() >= proposal.execution_after.unwrap().0,
+                    "ERR_PROPOSAL_IN_GRACE_PERIOD"
+                );
+                proposal.status = ProposalStatus::Approved;
+                self.internal_execute_in_window(&policy, &mut proposal, id);
+                if proposal.status != ProposalStatus::Queued {
+                    self.internal_reward_finalizer(&policy, &mut proposal, &sender_id);
+                }
+                true
+            }
+            Action::Finalize
+                if matches!(
+                    proposal.status,
+                    ProposalStatus::Signaling | ProposalStatus::AwaitingSponsor
+                ) =>
+            {
+                assert!(
+                    policy.voting_end(&proposal) < policy.now(),
+                    "ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED"
+                );
+                proposal.status = ProposalStatus::Expired;
+                self.internal_reject_proposal(&mut proposal, true);
+                self.internal_reward_finalizer(&policy, &mut proposal, &sender_id);
+                true
+            }
             Action::Finalize => {
                 proposal.status = policy.proposal_status(
                     &proposal,
                     policy.roles.iter().map(|r| r.name.clone()).collect(),
-                    self.total_delegation_amount,
+                    total_weight,
                 );
                 match proposal.status {
                     ProposalStatus::Approved => {
-                        self.internal_execute_proposal(&policy, &proposal, id);
+                        self.internal_execute_in_window(&policy, &mut proposal, id);
                     }
                     ProposalStatus::Expired => {
-                        self.internal_reject_proposal(&policy, &proposal, true);
+                        self.internal_reject_proposal(&mut proposal, true);
                     }
                     _ => {
                         env::panic_str("ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED");
                     }
                 }
+                self.internal_reward_finalizer(&policy, &mut proposal, &sender_id);
+                true
+            }
+            Action::Execute => env::panic_str("ERR_PROPOSAL_NOT_QUEUED"),
+            Action::Veto => {
+                let guardian_role = policy.guardian_role.as_ref().expect("ERR_NO_GUARDIAN_ROLE");
+                assert!(roles.contains(guardian_role), "ERR_NOT_GUARDIAN");
+                assert_eq!(
+                    proposal.status,
+                    ProposalStatus::Queued,
+                    "ERR_PROPOSAL_NOT_QUEUED"
+                );
+                proposal.status = ProposalStatus::Vetoed;
+                self.internal_reject_proposal(&mut proposal, true);
+                true
+            }
+            Action::MarkSpam => {
+                assert!(
+                    matches!(
+                        proposal.status,
+                        ProposalStatus::InProgress
+                            | ProposalStatus::Waiting
+                            | ProposalStatus::Signaling
+                            | ProposalStatus::AwaitingSponsor
+                    ),
+                    "ERR_PROPOSAL_NOT_IN_PROGRESS"
+                );
+                self.internal_remove_spam(&policy, &mut proposal, &sender_id);
+                true
+            }
+            Action::ExtendVoting { extra_time } => {
+                assert_eq!(
+                    proposal.status,
+                    ProposalStatus::InProgress,
+                    "ERR_PROPOSAL_NOT_IN_PROGRESS"
+                );
+                assert!(
+                    policy.voting_end(&proposal) >= policy.now(),
+                    "ERR_PROPOSAL_EXPIRED"
+                );
+                assert!(extra_time.0 > 0, "ERR_INVALID_EXTRA_TIME");
+                proposal.voting_extension = U64(proposal.voting_extension.0 + extra_time.0);
+                log!("Voting extended until {}", policy.voting_end(&proposal));
+                true
+            }
+            Action::Withdraw => {
+                assert_eq!(proposal.proposer, sender_id, "ERR_NOT_PROPOSER");
+                assert_eq!(
+                    proposal.status,
+                    ProposalStatus::InProgress,
+                    "ERR_PROPOSAL_NOT_IN_PROGRESS"
+                );
+                assert!(
+                    proposal.votes.is_empty()
+                        && proposal.commitments.is_empty()
+                        && proposal.poll_votes.is_empty(),
+                    "ERR_PROPOSAL_HAS_VOTES"
+                );
+                self.internal_reject_proposal(&mut proposal, true);
+                proposal.remove(&sender_id);
                 true
             }
             Action::MoveToHub => false,
+            Action::Pause | Action::Unpause | Action::Rebalance => {
+                env::panic_str("ERR_NOT_PROPOSAL_ACTION")
+            }
+            Action::Revoke => {
+                assert!(
+                    proposal.status == ProposalStatus::Queued
+                        && policy.now() < proposal.execution_after.unwrap().0,
+                    "ERR_PROPOSAL_NOT_IN_GRACE_PERIOD"
+                );
+                proposal.update_revokes(
+                    &sender_id,
+                    &roles,
+                    &policy,
+                    self.internal_vote_weight(id, &proposal, &sender_id),
+                );
+                if policy.is_revoked(&proposal, total_weight) {
+                    proposal.status = ProposalStatus::Revoked;
+                    self.internal_reject_proposal(&mut proposal, false);
+                }
+                true
+            }
         };
+        if removing {
+            self.internal_record_transition(id, &ProposalStatus::Removed);
+        } else if proposal.status != status_before {
+            self.internal_record_transition(id, &proposal.status);
+        }
+        let closed = was_open && (removing || proposal.status != ProposalStatus::InProgress);
+        if removing && status_before == ProposalStatus::Waiting {
+            self.internal_dequeue();
+        }
         if update {
-            self.proposals
-                .insert(&id, &VersionedProposal::Default(proposal));
+            self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        }
+        if closed {
+            self.internal_close_proposal();
         }
         if let Some(memo) = memo {
-            log!("Memo: {}", memo);
+            if !private {
+                log!("Memo: {}", memo);
+            }
+        }
+    }
+
+    /// Act on several proposals at once, e.g. to approve all pending proposals in one transaction.
+    /// Fails as a whole if any of the actions is not allowed.
+    pub fn act_proposals(&mut self, actions: Vec<(u64, Action)>) {
+        assert!(!actions.is_empty(), "ERR_NO_ACTIONS");
+        for (id, action) in actions {
+            self.act_proposal(id, action, None);
         }
     }
 
@@ -635,13 +2138,52 @@ impl Contract {
             1,
             "ERR_UNEXPECTED_CALLBACK_PROMISES"
         );
-        let result = match env::promise_result(0) {
+        if proposal.reconciled {
+            // Already settled by `reconcile_proposal`.
+            return PromiseOrValue::Value(());
+        }
+        let promise_result = env::promise_result(0);
+        if matches!(
+            proposal.kind,
+            ProposalKind::FunctionCall { .. } | ProposalKind::MultiFunctionCall { .. }
+        ) {
+            proposal.execution_result = Some(ExecutionResult::new(&promise_result));
+        }
+        let result = match promise_result {
             PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Successful(_) => self.internal_callback_proposal_success(&mut proposal),
+            PromiseResult::Successful(_) => {
+                self.internal_callback_proposal_success(proposal_id, &mut proposal)
+            }
             PromiseResult::Failed => self.internal_callback_proposal_fail(&mut proposal),
         };
+        self.internal_record_transition(proposal_id, &proposal.status);
         self.proposals
-            .insert(&proposal_id, &VersionedProposal::Default(proposal.into()));
+            .insert(&proposal_id, &VersionedProposal::V2(proposal));
         result
     }
+    /// Receiving result of the previous call of `MultiFunctionCall`, dispatches call `step` if it succeeded.
+    /// Fails otherwise, so the proposal fails without calling the rest.
+    #[private]
+    pub fn on_call_step(&mut self, proposal_id: u64, step: u32) -> Promise {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        assert!(
+            !matches!(env::promise_result(0), PromiseResult::Failed),
+            "ERR_CALL_FAILED"
+        );
+        let proposal: Proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("ERR_NO_PROPOSAL")
+            .into();
+        match &proposal.kind {
+            ProposalKind::MultiFunctionCall { calls } => {
+                self.internal_call_step(proposal_id, calls, step as usize)
+            }
+            _ => env::panic_str("ERR_WRONG_KIND"),
+        }
+    }
 }
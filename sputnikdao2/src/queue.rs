@@ -0,0 +1,128 @@
+//! Queue of proposals waiting to be opened for voting while `max_open_proposals` of the policy are in progress.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U64;
+
+use crate::*;
+
+/// FIFO queue of waiting proposal ids, stored by position between `head` and `tail`.
+/// Proposals removed while waiting are skipped when they reach the head.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct WaitingQueue {
+    ids: LookupMap<u64, u64>,
+    head: u64,
+    tail: u64,
+    /// Number of proposals in the queue that are still waiting.
+    len: u64,
+}
+
+impl WaitingQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            ids: LookupMap::new(StorageKeys::WaitingProposals),
+            head: 0,
+            tail: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn push(&mut self, proposal_id: u64) {
+        self.ids.insert(&self.tail, &proposal_id);
+        self.tail += 1;
+        self.len += 1;
+    }
+
+    /// Removes the id at the head, which can be of a proposal that is no longer waiting.
+    fn pop(&mut self) -> Option<u64> {
+        if self.head == self.tail {
+            return None;
+        }
+        let id = self.ids.remove(&self.head);
+        self.head += 1;
+        id
+    }
+}
+
+impl Contract {
+    /// Opens new proposal for voting, or puts it in the queue if the policy cap of open proposals is reached
+    /// or other proposals are already waiting.
+    pub(crate) fn internal_open_or_enqueue(
+        &mut self,
+        policy: &Policy,
+        proposal: &mut Proposal,
+        proposal_id: u64,
+    ) {
+        let is_full = match policy.max_open_proposals {
+            Some(max_open_proposals) => {
+                self.open_proposals >= max_open_proposals as u64
+                    || !self.waiting_proposals.is_empty()
+            }
+            None => false,
+        };
+        if is_full {
+            proposal.status = ProposalStatus::Waiting;
+            self.waiting_proposals.push(proposal_id);
+        } else {
+            self.open_proposals += 1;
+        }
+    }
+
+    /// Records that a waiting proposal was removed. Its id is skipped once it reaches the head of the queue.
+    pub(crate) fn internal_dequeue(&mut self) {
+        self.waiting_proposals.len = self.waiting_proposals.len.saturating_sub(1);
+    }
+
+    /// Returns the proposal with given id if it's still waiting.
+    fn internal_waiting_proposal(&self, id: u64) -> Option<Proposal> {
+        self.proposals
+            .get(&id)
+            .map(Proposal::from)
+            .filter(|proposal| proposal.status == ProposalStatus::Waiting)
+    }
+
+    /// Records that an open proposal was finalized, and opens waiting proposals while under the cap.
+    /// Voting period of opened proposals starts now.
+    pub(crate) fn internal_close_proposal(&mut self) {
+        self.open_proposals = self.open_proposals.saturating_sub(1);
+        let policy = self.policy.get().unwrap().to_policy();
+        while !self.waiting_proposals.is_empty() {
+            if let Some(max_open_proposals) = policy.max_open_proposals {
+                if self.open_proposals >= max_open_proposals as u64 {
+                    break;
+                }
+            }
+            let id = match self.waiting_proposals.pop() {
+                Some(id) => id,
+                None => break,
+            };
+            if let Some(mut proposal) = self.internal_waiting_proposal(id) {
+                proposal.status = ProposalStatus::InProgress;
+                proposal.submission_time = U64::from(env::block_timestamp());
+                proposal.submission_block = U64::from(env::block_height());
+                self.internal_record_transition(id, &proposal.status);
+                self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+                self.open_proposals += 1;
+                self.waiting_proposals.len -= 1;
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns ids of proposals waiting to be opened for voting, in order they will be opened.
+    /// Takes up to `limit` positions of the queue, starting from `from_index` positions after its head.
+    pub fn get_waiting_proposals(&self, from_index: u64, limit: u64) -> Vec<u64> {
+        let queue = &self.waiting_proposals;
+        let start = queue.head.saturating_add(from_index).min(queue.tail);
+        (start..queue.tail.min(start.saturating_add(limit)))
+            .filter_map(|position| queue.ids.get(&position))
+            .filter(|id| self.internal_waiting_proposal(*id).is_some())
+            .collect()
+    }
+}
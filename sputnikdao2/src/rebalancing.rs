@@ -0,0 +1,303 @@
+//! Systematic treasury management: the policy sets target allocations of fungible tokens in the treasury value,
+//! and `rebalance` (e.g. called by a cron integration with the `Rebalance` permission) swaps tokens through
+//! the exchange of the policy to restore them. Small swaps are executed directly, larger ones are proposed to the DAO.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::types::OldAccountId;
+use crate::*;
+
+/// Placeholder in `swap_msg` of allocation targets, replaced with the min amount the swap must return.
+pub const MIN_AMOUNT_OUT: &str = "{min_amount_out}";
+
+/// Target share of a fungible token in the treasury value.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AllocationTarget {
+    pub token_id: AccountId,
+    /// Target share of the treasury value, in basis points.
+    pub target_bps: u32,
+    /// Message of `ft_transfer_call` to the exchange when swapping into this token. Must contain `{min_amount_out}`,
+    /// replaced with the amount expected from the prices less `max_slippage_bps`, so the exchange fails worse swaps.
+    pub swap_msg: String,
+}
+
+/// Rebalancing of the treasury towards target allocations.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RebalancingPolicy {
+    /// Token holdings are valued in. Can be "" for $NEAR.
+    pub reference_token: OldAccountId,
+    pub targets: Vec<AllocationTarget>,
+    /// Deviation from a target that doesn't need rebalancing, in basis points of the treasury value.
+    pub tolerance_bps: u32,
+    /// Exchange tokens are swapped on.
+    pub exchange_id: AccountId,
+    /// Swaps worth up to this value in the reference token are executed without a proposal.
+    pub max_direct_value: U128,
+    /// Min time between rebalances, in nanoseconds.
+    pub interval: U64,
+    /// Max shortfall of the swap output from the amount expected from the prices, in basis points.
+    #[serde(default)]
+    pub max_slippage_bps: u32,
+}
+
+impl Contract {
+    /// Converts given value in the reference token to amount of given token, using the stored price.
+    fn internal_amount_of_value(
+        &self,
+        token_id: &AccountId,
+        reference_token: &OldAccountId,
+        value: u128,
+    ) -> u128 {
+        if token_id.as_str() == reference_token {
+            return value;
+        }
+        let price = self
+            .token_prices
+            .get(&(token_id.to_string(), reference_token.clone()))
+            .expect("ERR_NO_PRICE");
+        value.saturating_mul(10u128.pow(price.decimals as u32)) / price.multiplier.0
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Swaps the most overweight token of `rebalancing` targets into the most underweight one, by the smaller
+    /// of their deviations. Executes the swap directly if it's within `max_direct_value`, otherwise adds
+    /// a `Swap` proposal and returns its id. Caller needs the `Rebalance` permission, once per `interval`.
+    pub fn rebalance(&mut self) -> Option<u64> {
+        self.assert_not_read_only();
+        self.assert_not_paused();
+        self.assert_can(&Action::Rebalance);
+        let policy = self.policy.get().unwrap().to_policy();
+        let rebalancing = policy
+            .rebalancing
+            .clone()
+            .expect("ERR_NO_REBALANCING_POLICY");
+        let now = env::block_timestamp();
+        assert!(
+            !matches!(self.last_rebalance, Some(last) if now < last + rebalancing.interval.0),
+            "ERR_REBALANCE_NOT_DUE"
+        );
+        let valuation = self.get_treasury_valuation(rebalancing.reference_token.clone());
+        assert!(!valuation.stale, "ERR_STALE_VALUATION");
+        let total = valuation.total.0;
+        let tolerance = total.saturating_mul(rebalancing.tolerance_bps as u128) / 10_000;
+        // Deviation of each target from its allocation, positive if overweight.
+        let deviations: Vec<(&AllocationTarget, i128)> = rebalancing
+            .targets
+            .iter()
+            .map(|target| {
+                let held = valuation
+                    .holdings
+                    .iter()
+                    .find(|holding| holding.token_id == target.token_id.as_str())
+                    .and_then(|holding| holding.value)
+                    .map(|value| value.0)
+                    .unwrap_or_default();
+                let allocation = total.saturating_mul(target.target_bps as u128) / 10_000;
+                (target, held as i128 - allocation as i128)
+            })
+            .collect();
+        let over = deviations
+            .iter()
+            .max_by_key(|(_, deviation)| *deviation)
+            .filter(|(_, deviation)| *deviation > tolerance as i128);
+        let under = deviations
+            .iter()
+            .min_by_key(|(_, deviation)| *deviation)
+            .filter(|(_, deviation)| -*deviation > tolerance as i128);
+        let ((token_in, excess), (token_out, deficit)) = match (over, under) {
+            (Some(over), Some(under)) => (over, under),
+            _ => env::panic_str("ERR_WITHIN_TOLERANCE"),
+        };
+        assert!(
+            token_out.swap_msg.contains(MIN_AMOUNT_OUT),
+            "ERR_SWAP_MSG_WITHOUT_MIN_AMOUNT_OUT"
+        );
+        let value = (*excess).min(-*deficit) as u128;
+        let amount_in =
+            self.internal_amount_of_value(&token_in.token_id, &rebalancing.reference_token, value);
+        let min_amount_out = self
+            .internal_amount_of_value(&token_out.token_id, &rebalancing.reference_token, value)
+            .saturating_mul(10_000 - rebalancing.max_slippage_bps.min(10_000) as u128)
+            / 10_000;
+        let msg = token_out
+            .swap_msg
+            .replace(MIN_AMOUNT_OUT, &min_amount_out.to_string());
+        self.last_rebalance = Some(now);
+
+        if value <= rebalancing.max_direct_value.0
+            && self.internal_check_diversification(
+                &policy,
+                &token_in.token_id,
+                amount_in,
+                &token_out.token_id,
+            )
+        {
+            let balance = self
+                .token_balances
+                .get(&token_in.token_id)
+                .expect("ERR_NO_BALANCE");
+            self.token_balances
+                .insert(&token_in.token_id, &balance.saturating_sub(amount_in));
+            self.internal_payout(
+                &Some(token_in.token_id.clone()),
+                &rebalancing.exchange_id,
+                amount_in,
+                "Rebalancing".to_string(),
+                Some(msg),
+            );
+            return None;
+        }
+
+        let id = self.last_proposal_id;
+        let mut proposal: Proposal = ProposalInput {
+            description: format!(
+                "Rebalance {} of {} to {}",
+                amount_in, token_in.token_id, token_out.token_id
+            ),
+            kind: ProposalKind::Swap {
+                exchange_id: rebalancing.exchange_id.clone(),
+                token_in: token_in.token_id.clone(),
+                amount_in: U128(amount_in),
+                token_out: token_out.token_id.clone(),
+                msg,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        }
+        .into();
+        proposal.bond = U128(0);
+        self.internal_open_or_enqueue(&policy, &mut proposal, id);
+        self.internal_record_transition(id, &proposal.status);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        self.last_proposal_id += 1;
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn contract_with_rebalancing(
+        context: &mut VMContextBuilder,
+        max_direct_value: u128,
+    ) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.roles[1]
+            .permissions
+            .insert("*:Rebalance".to_string());
+        policy_mut.price_oracle = Some(PriceOracle {
+            oracle_id: accounts(2),
+            max_price_age: U64(1_000),
+        });
+        let target = |token_id| AllocationTarget {
+            token_id,
+            target_bps: 5_000,
+            swap_msg: "{\"min_amount_out\":\"{min_amount_out}\"}".to_string(),
+        };
+        policy_mut.rebalancing = Some(RebalancingPolicy {
+            reference_token: accounts(3).to_string(),
+            targets: vec![target(accounts(3)), target(accounts(4))],
+            tolerance_bps: 1_000,
+            exchange_id: accounts(5),
+            max_direct_value: U128(max_direct_value),
+            interval: U64(1_000),
+            max_slippage_bps: 100,
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.token_balances.insert(&accounts(3), &900);
+        contract.token_balances.insert(&accounts(4), &50);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        let price = |token_id: String, multiplier| ReportedPrice {
+            token_id,
+            reference_token: accounts(3).to_string(),
+            multiplier: U128(multiplier),
+            decimals: 0,
+        };
+        contract.report_prices(vec![
+            price(OLD_BASE_TOKEN.to_string(), 0),
+            price(accounts(4).to_string(), 2),
+        ]);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract
+    }
+
+    #[test]
+    fn test_rebalance_proposes_swap() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rebalancing(&mut context, 100);
+        let id = contract.rebalance().unwrap();
+        // 400 of the reference token are 200 units of the underweight token, less 1% of slippage.
+        match contract.get_proposal(id).proposal.kind {
+            ProposalKind::Swap {
+                token_in,
+                amount_in,
+                token_out,
+                msg,
+                ..
+            } => {
+                assert_eq!(token_in, accounts(3));
+                assert_eq!(amount_in, U128(400));
+                assert_eq!(token_out, accounts(4));
+                assert_eq!(msg, "{\"min_amount_out\":\"198\"}");
+            }
+            _ => panic!("ERR_WRONG_KIND"),
+        }
+    }
+
+    #[test]
+    fn test_rebalance_directly() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rebalancing(&mut context, 1_000);
+        assert_eq!(contract.rebalance(), None);
+        assert_eq!(contract.last_proposal_id, 0);
+        assert_eq!(contract.token_balances.get(&accounts(3)), Some(500));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_rebalance_without_permission() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rebalancing(&mut context, 1_000);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.rebalance();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PAUSED")]
+    fn test_rebalance_paused() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rebalancing(&mut context, 1_000);
+        contract.paused = true;
+        contract.rebalance();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_REBALANCE_NOT_DUE")]
+    fn test_rebalance_not_due() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_rebalancing(&mut context, 1_000);
+        contract.rebalance();
+        contract.rebalance();
+    }
+}
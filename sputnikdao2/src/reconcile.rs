@@ -0,0 +1,365 @@
+//! Settling proposals whose execution callback never arrived.
+
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, serde_json, AccountId, Gas, PromiseOrValue};
+
+use crate::bounties::split_bounty_payout;
+use crate::types::{convert_old_to_new_token, GAS_FOR_FT_TRANSFER};
+use crate::*;
+
+/// Time after dispatching the execution when the callback is considered lost.
+pub const CALLBACK_TIMEOUT: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+const GAS_FOR_FT_BALANCE_OF: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_ON_PAYOUT_BALANCE: Gas = Gas(5_000_000_000_000);
+
+impl Proposal {
+    /// Whether the execution of this proposal waits for the callback for longer than `CALLBACK_TIMEOUT`.
+    pub fn is_stuck(&self) -> bool {
+        self.status == ProposalStatus::Approved
+            && matches!(
+                self.execution_started_at,
+                Some(started_at) if env::block_timestamp() > started_at.0 + CALLBACK_TIMEOUT
+            )
+    }
+}
+
+impl Contract {
+    /// Returns token, receiver and amount of fungible token payout done by the proposal, if any.
+    fn internal_ft_payout(&self, proposal: &Proposal) -> Option<(AccountId, AccountId, Balance)> {
+        let (token_id, receiver_id, amount) = match &proposal.kind {
+            ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                ..
+            } => (token_id.clone(), receiver_id.clone(), amount.0),
+            ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+                milestone,
+                shares,
+                ..
+            } => {
+                let bounty: Bounty = self.bounties.get(bounty_id)?.into();
+                // Only the part of the receiver is checked for split payouts.
+                let (_, amount) =
+                    split_bounty_payout(receiver_id, bounty.payout_amount(*milestone), shares)
+                        .pop()
+                        .unwrap();
+                (bounty.token, receiver_id.clone(), amount)
+            }
+            _ => return None,
+        };
+        convert_old_to_new_token(&token_id).map(|token_id| (token_id, receiver_id, amount))
+    }
+
+    /// Queries balance of the receiver of fungible token payout of given proposal, for `reconcile_proposal`
+    /// to check the balance delta. Called when the execution is dispatched, before the payout promise is created,
+    /// so the query reaches the token first.
+    pub(crate) fn internal_query_payout_balance(&self, proposal_id: u64, proposal: &mut Proposal) {
+        proposal.payout_balance_before = None;
+        if let Some((token_id, receiver_id, _)) = self.internal_ft_payout(proposal) {
+            ext_fungible_token::ft_balance_of(receiver_id, token_id, 0, GAS_FOR_FT_BALANCE_OF)
+                .then(ext_self::on_payout_balance(
+                    proposal_id,
+                    env::current_account_id(),
+                    0,
+                    GAS_FOR_ON_PAYOUT_BALANCE,
+                ));
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Settles proposal which is stuck waiting for the callback of its execution.
+    /// Fungible token payouts are checked against the balance delta of the receiver: the payout is considered
+    /// successful if the receiver holds at least the paid amount more than when the execution was dispatched.
+    /// A lower delta doesn't prove that the payout failed, as the receiver may have moved the tokens since,
+    /// so the proposal then stays stuck, as it does if the balance at dispatch wasn't recorded. It's never settled
+    /// as failed, that would allow to execute it again. Grants of contract roles are considered failed, as the member
+    /// didn't confirm them. Other executions can't be checked and are considered successful.
+    /// Requires permission to finalize the proposal.
+    pub fn reconcile_proposal(&mut self, id: u64) -> PromiseOrValue<()> {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        let (_, allowed) =
+            policy.can_execute_action(self.internal_user_info(), &proposal.kind, &Action::Finalize);
+        assert!(allowed, "ERR_PERMISSION_DENIED");
+        assert!(proposal.is_stuck(), "ERR_PROPOSAL_NOT_STUCK");
+        proposal.reconciled = true;
+        match self.internal_ft_payout(&proposal) {
+            Some((token_id, receiver_id, amount)) => {
+                self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+                ext_fungible_token::ft_balance_of(receiver_id, token_id, 0, GAS_FOR_FT_BALANCE_OF)
+                    .then(ext_self::on_reconcile_proposal(
+                        id,
+                        U128(amount),
+                        env::current_account_id(),
+                        0,
+                        GAS_FOR_FT_TRANSFER,
+                    ))
+                    .into()
+            }
+            None => {
+                let result = if let ProposalKind::AddMemberToRole { .. } = proposal.kind {
+                    self.internal_callback_proposal_fail(&mut proposal)
+                } else {
+                    self.internal_callback_proposal_success(id, &mut proposal)
+                };
+                self.internal_record_transition(id, &proposal.status);
+                self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+                result
+            }
+        }
+    }
+
+    /// Receiving balance of the receiver requested in `reconcile_proposal`.
+    #[private]
+    pub fn on_reconcile_proposal(&mut self, proposal_id: u64, amount: U128) -> PromiseOrValue<()> {
+        let mut proposal: Proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("ERR_NO_PROPOSAL")
+            .into();
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        if !proposal.is_stuck() {
+            // Execution callback arrived or was settled in the meantime.
+            return PromiseOrValue::Value(());
+        }
+        let balance = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                serde_json::from_slice::<U128>(&value)
+                    .expect("ERR_INVALID_FT_BALANCE")
+                    .0
+            }
+            _ => env::panic_str("ERR_NO_FT_BALANCE"),
+        };
+        let confirmed = matches!(
+            proposal.payout_balance_before,
+            Some(before) if balance.saturating_sub(before.0) >= amount.0
+        );
+        if !confirmed {
+            // Payout can't be confirmed: the execution callback can still settle it.
+            proposal.reconciled = false;
+            self.proposals
+                .insert(&proposal_id, &VersionedProposal::V2(proposal));
+            return PromiseOrValue::Value(());
+        }
+        let result = self.internal_callback_proposal_success(proposal_id, &mut proposal);
+        self.internal_record_transition(proposal_id, &proposal.status);
+        self.proposals
+            .insert(&proposal_id, &VersionedProposal::V2(proposal));
+        result
+    }
+
+    /// Receiving balance of the receiver of a payout queried when its execution was dispatched.
+    #[private]
+    pub fn on_payout_balance(&mut self, proposal_id: u64) {
+        let mut proposal: Proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("ERR_NO_PROPOSAL")
+            .into();
+        if let PromiseResult::Successful(value) = env::promise_result(0) {
+            if let Ok(balance) = serde_json::from_slice::<U128>(&value) {
+                proposal.payout_balance_before = Some(balance);
+                self.proposals
+                    .insert(&proposal_id, &VersionedProposal::V2(proposal));
+            }
+        }
+    }
+
+    /// Get proposals stuck waiting for the callback of their execution, see `reconcile_proposal`.
+    pub fn get_stuck_proposals(&self, from_index: u64, limit: u64) -> Vec<ProposalOutput> {
+        let hide_votes = self.internal_hide_votes();
+        (from_index..std::cmp::min(self.last_proposal_id, from_index + limit))
+            .filter_map(|id| {
+                let proposal: Proposal = self.proposals.get(&id)?.into();
+                if proposal.is_stuck() {
+                    Some(Self::proposal_output(
+                        id,
+                        VersionedProposal::V2(proposal),
+                        hide_votes,
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::BondState;
+    use crate::Config;
+
+    use super::*;
+
+    fn approved_transfer(context: &mut VMContextBuilder, token_id: &str) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: token_id.to_string(),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("100")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract
+    }
+
+    fn callback_env(context: &mut VMContextBuilder, result: PromiseResult) {
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    #[test]
+    fn test_reconcile_proposal() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = approved_transfer(&mut context, OLD_BASE_TOKEN);
+        assert!(contract
+            .get_proposal(0)
+            .proposal
+            .execution_started_at
+            .is_some());
+        assert!(contract.get_stuck_proposals(0, 10).is_empty());
+
+        testing_env!(context.block_timestamp(CALLBACK_TIMEOUT + 1).build());
+        assert_eq!(contract.get_stuck_proposals(0, 10)[0].id, 0);
+        contract.reconcile_proposal(0);
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+        assert!(proposal.executed_at.is_some());
+        assert_eq!(proposal.bond_state, BondState::Returned);
+        assert!(contract.get_stuck_proposals(0, 10).is_empty());
+
+        // Late callback doesn't settle the proposal again.
+        callback_env(&mut context, PromiseResult::Failed);
+        contract.on_proposal_callback(0);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    /// Records balance of the receiver of the approved transfer at dispatch.
+    fn payout_balance(context: &mut VMContextBuilder, contract: &mut Contract, balance: &str) {
+        callback_env(
+            context,
+            PromiseResult::Successful(serde_json::to_vec(&U128(to_yocto(balance))).unwrap()),
+        );
+        contract.on_payout_balance(0);
+    }
+
+    #[test]
+    fn test_reconcile_ft_payout_unconfirmed() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = approved_transfer(&mut context, accounts(3).as_str());
+        payout_balance(&mut context, &mut contract, "0");
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(CALLBACK_TIMEOUT + 1)
+            .build());
+        contract.reconcile_proposal(0);
+        assert!(contract
+            .get_proposal(0)
+            .proposal
+            .execution_started_at
+            .is_some());
+
+        callback_env(
+            &mut context,
+            PromiseResult::Successful(serde_json::to_vec(&U128(to_yocto("1"))).unwrap()),
+        );
+        contract.on_reconcile_proposal(0, U128(to_yocto("100")));
+        // Receiver may have moved the tokens: proposal isn't settled as failed, that would pay it again.
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+        assert_eq!(proposal.bond_state, BondState::Locked);
+        assert_eq!(contract.get_stuck_proposals(0, 10)[0].id, 0);
+
+        callback_env(
+            &mut context,
+            PromiseResult::Successful(serde_json::to_vec(&U128(to_yocto("100"))).unwrap()),
+        );
+        contract.on_reconcile_proposal(0, U128(to_yocto("100")));
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+        assert_eq!(proposal.bond_state, BondState::Returned);
+        assert!(contract.get_stuck_proposals(0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_ft_payout_prefunded_receiver() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = approved_transfer(&mut context, accounts(3).as_str());
+        payout_balance(&mut context, &mut contract, "100");
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(CALLBACK_TIMEOUT + 1)
+            .build());
+        contract.reconcile_proposal(0);
+
+        // Receiver already held the amount: its balance doesn't confirm the payout.
+        callback_env(
+            &mut context,
+            PromiseResult::Successful(serde_json::to_vec(&U128(to_yocto("100"))).unwrap()),
+        );
+        contract.on_reconcile_proposal(0, U128(to_yocto("100")));
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.bond_state, BondState::Locked);
+        assert_eq!(contract.get_stuck_proposals(0, 10)[0].id, 0);
+
+        callback_env(
+            &mut context,
+            PromiseResult::Successful(serde_json::to_vec(&U128(to_yocto("200"))).unwrap()),
+        );
+        contract.on_reconcile_proposal(0, U128(to_yocto("100")));
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.bond_state, BondState::Returned);
+        assert!(contract.get_stuck_proposals(0, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_NOT_STUCK")]
+    fn test_reconcile_proposal_not_stuck() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = approved_transfer(&mut context, OLD_BASE_TOKEN);
+        contract.reconcile_proposal(0);
+    }
+}
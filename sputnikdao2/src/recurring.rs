@@ -0,0 +1,307 @@
+//! Recurring `Transfer` proposals, e.g. monthly contributor payments: once the first payout of a transfer with
+//! `recurrence` succeeds, anyone can call `trigger_recurring` to pay it out again every `interval`,
+//! until it was paid out `times` in total.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, Gas, PromiseOrValue};
+
+use crate::types::{convert_old_to_new_token, require};
+use crate::*;
+
+const GAS_FOR_ON_RECURRING_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+/// Schedule of a recurring `Transfer`, approved with the proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Recurrence {
+    /// Time between payouts, in `period_unit` of the policy.
+    pub interval: U64,
+    /// Number of payouts, including the one on execution of the proposal.
+    pub times: u32,
+}
+
+/// Payouts left of an executed recurring transfer.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecurringTransfer {
+    /// When the next payout is due, in `period_unit` of the policy.
+    pub next_at: U64,
+    pub remaining: u32,
+}
+
+/// Checks that given recurrence has at least one payout and a non-zero interval.
+pub(crate) fn check_recurrence(recurrence: &Option<Recurrence>) -> Result<(), &'static str> {
+    match recurrence {
+        Some(recurrence) => require(
+            recurrence.interval.0 > 0 && recurrence.times > 0,
+            "ERR_INVALID_RECURRENCE",
+        ),
+        None => Ok(()),
+    }
+}
+
+impl Contract {
+    /// Schedules the next payouts of given transfer once its first payout succeeded.
+    pub(crate) fn internal_schedule_recurring(&mut self, proposal_id: u64, proposal: &Proposal) {
+        let recurrence = match &proposal.kind {
+            ProposalKind::Transfer {
+                recurrence: Some(recurrence),
+                ..
+            } => recurrence,
+            _ => return,
+        };
+        // Failed proposals that are executed again don't schedule twice.
+        if recurrence.times <= 1 || self.recurring_transfers.get(&proposal_id).is_some() {
+            return;
+        }
+        let policy = self.policy.get().unwrap().to_policy();
+        self.recurring_transfers.insert(
+            &proposal_id,
+            &RecurringTransfer {
+                next_at: U64(policy.now() + recurrence.interval.0),
+                remaining: recurrence.times - 1,
+            },
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Pays out the next payout of given recurring transfer, once it's due. Callable by anyone.
+    pub fn trigger_recurring(&mut self, id: u64) -> PromiseOrValue<()> {
+        self.assert_not_read_only();
+        self.assert_not_paused();
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let schedule = self
+            .recurring_transfers
+            .get(&id)
+            .expect("ERR_NOT_RECURRING");
+        let (token_id, receiver_id, amount, msg, recurrence) = match &proposal.kind {
+            ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+                recurrence: Some(recurrence),
+                ..
+            } => (token_id, receiver_id, amount, msg, recurrence),
+            _ => env::panic_str("ERR_NOT_RECURRING"),
+        };
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(policy.now() >= schedule.next_at.0, "ERR_RECURRING_NOT_DUE");
+        if schedule.remaining > 1 {
+            self.recurring_transfers.insert(
+                &id,
+                &RecurringTransfer {
+                    next_at: U64(schedule.next_at.0 + recurrence.interval.0),
+                    remaining: schedule.remaining - 1,
+                },
+            );
+        } else {
+            self.recurring_transfers.remove(&id);
+        }
+        match self.internal_payout(
+            &convert_old_to_new_token(token_id),
+            receiver_id,
+            amount.0,
+            proposal.description.clone(),
+            msg.clone(),
+        ) {
+            PromiseOrValue::Promise(promise) => promise
+                .then(ext_self::on_recurring_transfer(
+                    id,
+                    schedule,
+                    env::current_account_id(),
+                    0,
+                    GAS_FOR_ON_RECURRING_TRANSFER,
+                ))
+                .into(),
+            value => value,
+        }
+    }
+
+    /// Returns payouts left of given recurring transfer, None once all were paid out.
+    pub fn get_recurring_transfer(&self, id: u64) -> Option<RecurringTransfer> {
+        self.recurring_transfers.get(&id)
+    }
+
+    /// Receiving result of a payout of recurring transfer, with the schedule before it was triggered.
+    /// If it failed, the payout is credited back to the current schedule and can be triggered again:
+    /// other payouts of the transfer may have been triggered meanwhile.
+    #[private]
+    pub fn on_recurring_transfer(&mut self, proposal_id: u64, schedule: RecurringTransfer) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        match env::promise_result(0) {
+            PromiseResult::Successful(_) => {
+                let proposal: Proposal = self
+                    .proposals
+                    .get(&proposal_id)
+                    .expect("ERR_NO_PROPOSAL")
+                    .into();
+                if let ProposalKind::Transfer {
+                    token_id,
+                    amount,
+                    category,
+                    ..
+                } = &proposal.kind
+                {
+                    self.internal_record_spending(category, token_id, amount.0);
+                }
+            }
+            _ => {
+                let schedule = match self.recurring_transfers.get(&proposal_id) {
+                    Some(current) => RecurringTransfer {
+                        next_at: U64(current.next_at.0.min(schedule.next_at.0)),
+                        remaining: current.remaining + 1,
+                    },
+                    None => RecurringTransfer {
+                        next_at: schedule.next_at,
+                        remaining: 1,
+                    },
+                };
+                self.recurring_transfers.insert(&proposal_id, &schedule);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn callback_env(context: &mut VMContextBuilder, result: PromiseResult) {
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![result],
+        );
+    }
+
+    fn contract_with_recurring(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "monthly".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: Some(Recurrence {
+                    interval: U64(100),
+                    times: 3,
+                }),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        callback_env(context, PromiseResult::Successful(vec![]));
+        contract.on_proposal_callback(id);
+        contract
+    }
+
+    #[test]
+    fn test_recurring_transfer() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_recurring(&mut context);
+        assert_eq!(
+            contract.get_recurring_transfer(0),
+            Some(RecurringTransfer {
+                next_at: U64(100),
+                remaining: 2,
+            })
+        );
+
+        testing_env!(context.block_timestamp(100).build());
+        contract.trigger_recurring(0);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(2)));
+        // Failed payout can be triggered again.
+        callback_env(&mut context, PromiseResult::Failed);
+        contract.on_recurring_transfer(
+            0,
+            RecurringTransfer {
+                next_at: U64(100),
+                remaining: 2,
+            },
+        );
+        assert_eq!(contract.get_recurring_transfer(0).unwrap().remaining, 2);
+
+        testing_env!(context.block_timestamp(200).build());
+        contract.trigger_recurring(0);
+        contract.trigger_recurring(0);
+        assert_eq!(contract.get_recurring_transfer(0), None);
+    }
+
+    #[test]
+    fn test_recurring_transfer_failed_in_flight() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_recurring(&mut context);
+        // Both payouts are due, and triggered before either callback arrives.
+        testing_env!(context.block_timestamp(200).build());
+        contract.trigger_recurring(0);
+        contract.trigger_recurring(0);
+        assert_eq!(contract.get_recurring_transfer(0), None);
+
+        // First one fails, second one succeeds: only the failed payout is left.
+        callback_env(&mut context, PromiseResult::Failed);
+        contract.on_recurring_transfer(
+            0,
+            RecurringTransfer {
+                next_at: U64(100),
+                remaining: 2,
+            },
+        );
+        callback_env(&mut context, PromiseResult::Successful(vec![]));
+        contract.on_recurring_transfer(
+            0,
+            RecurringTransfer {
+                next_at: U64(200),
+                remaining: 1,
+            },
+        );
+        assert_eq!(
+            contract.get_recurring_transfer(0),
+            Some(RecurringTransfer {
+                next_at: U64(100),
+                remaining: 1,
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RECURRING_NOT_DUE")]
+    fn test_recurring_transfer_not_due() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_recurring(&mut context);
+        testing_env!(context.block_timestamp(99).build());
+        contract.trigger_recurring(0);
+    }
+}
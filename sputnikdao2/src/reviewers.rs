@@ -0,0 +1,80 @@
+//! Rotating reviewers, randomly assigned to proposals from a group role.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+use crate::*;
+
+/// Assignment of a single reviewer from `role` to proposals of given kinds.
+/// Only the assigned reviewer can vote with this role on the proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReviewerAssignment {
+    /// Group role reviewers are selected from.
+    pub role: String,
+    /// Labels of proposal kinds that get a reviewer assigned, e.g. "bounty_done".
+    pub kinds: Vec<String>,
+}
+
+impl Contract {
+    /// Selects reviewer of new proposal if the policy assigns reviewers to its kind.
+    /// Proposer and, if there are other candidates, the previous reviewer from this role are not selected.
+    /// Without candidates no reviewer is assigned, and any member of the role can vote.
+    pub(crate) fn internal_assign_reviewer(
+        &mut self,
+        policy: &Policy,
+        proposal: &Proposal,
+        proposal_id: u64,
+    ) -> Option<AccountId> {
+        let assignment = policy.reviewer_assignment.as_ref()?;
+        if !assignment
+            .kinds
+            .iter()
+            .any(|kind| kind == proposal.kind.to_policy_label())
+        {
+            return None;
+        }
+        let mut candidates: Vec<AccountId> = policy
+            .roles
+            .iter()
+            .filter(|role| role.name == assignment.role)
+            .flat_map(|role| role.kind.get_group_members().unwrap_or_default())
+            .filter(|member_id| *member_id != &proposal.proposer)
+            .cloned()
+            .collect();
+        candidates.sort();
+        let last_reviewer = self.last_reviewers.get(&assignment.role);
+        if candidates.len() > 1 {
+            candidates.retain(|member_id| Some(member_id) != last_reviewer.as_ref());
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        let mut entropy = env::random_seed();
+        entropy.extend_from_slice(&proposal_id.to_le_bytes());
+        let hash = env::sha256_array(&entropy);
+        let mut index = [0u8; 8];
+        index.copy_from_slice(&hash[..8]);
+        let reviewer =
+            candidates[(u64::from_le_bytes(index) % candidates.len() as u64) as usize].clone();
+        self.last_reviewers.insert(&assignment.role, &reviewer);
+        Some(reviewer)
+    }
+}
+
+/// Removes the reviewer role from roles of the voter, unless they are the assigned reviewer.
+pub(crate) fn filter_reviewer_role(
+    policy: &Policy,
+    proposal: &Proposal,
+    voter_id: &AccountId,
+    roles: &mut Vec<String>,
+) {
+    if let (Some(assignment), Some(reviewer)) = (&policy.reviewer_assignment, &proposal.reviewer) {
+        if reviewer != voter_id {
+            roles.retain(|role| role != &assignment.role);
+            assert!(!roles.is_empty(), "ERR_NOT_ASSIGNED_REVIEWER");
+        }
+    }
+}
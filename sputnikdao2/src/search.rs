@@ -0,0 +1,181 @@
+//! Keyword indices of proposals and bounties, so light frontends can search governance history with views.
+//! Descriptions (and required tags of bounties) are split into lowercase alphanumeric keywords, and up to
+//! `MAX_KEYWORDS` of them are indexed per item by their sha256.
+
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{near_bindgen, CryptoHash};
+
+use crate::*;
+
+/// Max number of keywords indexed per proposal or bounty.
+const MAX_KEYWORDS: usize = 16;
+
+/// Keywords shorter than this are not indexed.
+const MIN_KEYWORD_LEN: usize = 3;
+
+/// Hash a keyword is indexed by.
+pub fn keyword_hash(keyword: &str) -> CryptoHash {
+    env::sha256_array(keyword.to_lowercase().as_bytes())
+}
+
+/// Hashes of distinct keywords of given texts, at most `MAX_KEYWORDS`.
+fn keyword_hashes<'a>(texts: impl Iterator<Item = &'a str>) -> Vec<CryptoHash> {
+    let mut hashes = vec![];
+    for keyword in texts.flat_map(|text| text.split(|c: char| !c.is_alphanumeric())) {
+        if hashes.len() == MAX_KEYWORDS {
+            break;
+        }
+        if keyword.chars().count() < MIN_KEYWORD_LEN {
+            continue;
+        }
+        let hash = keyword_hash(keyword);
+        if !hashes.contains(&hash) {
+            hashes.push(hash);
+        }
+    }
+    hashes
+}
+
+impl Contract {
+    /// Indexes keywords of the description of given proposal.
+    pub(crate) fn internal_index_proposal_keywords(&mut self, id: u64, description: &str) {
+        for hash in keyword_hashes(std::iter::once(description)) {
+            let mut ids = self.proposals_by_keyword.get(&hash).unwrap_or_default();
+            ids.push(id);
+            self.proposals_by_keyword.insert(&hash, &ids);
+        }
+    }
+
+    /// Removes given proposal from the index of keywords of its description.
+    pub(crate) fn internal_unindex_proposal_keywords(&mut self, id: u64, description: &str) {
+        for hash in keyword_hashes(std::iter::once(description)) {
+            let mut ids = self.proposals_by_keyword.get(&hash).unwrap_or_default();
+            ids.retain(|other| *other != id);
+            self.proposals_by_keyword.insert(&hash, &ids);
+        }
+    }
+
+    /// Indexes keywords of the description and required tags of given bounty.
+    pub(crate) fn internal_index_bounty_keywords(&mut self, id: u64, bounty: &Bounty) {
+        let texts = std::iter::once(bounty.description.as_str())
+            .chain(bounty.required_tags.iter().map(String::as_str));
+        for hash in keyword_hashes(texts) {
+            let mut ids = self.bounties_by_keyword.get(&hash).unwrap_or_default();
+            ids.push(id);
+            self.bounties_by_keyword.insert(&hash, &ids);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns hash given keyword is indexed by: sha256 of the lowercase keyword.
+    pub fn get_keyword_hash(&self, keyword: String) -> Base58CryptoHash {
+        keyword_hash(&keyword).into()
+    }
+
+    /// Get proposals whose description has the keyword with given hash, in paginated view.
+    /// `from_index` and `limit` paginate over the matching proposals, not over all proposals.
+    pub fn search_proposals(
+        &self,
+        keyword_hash: Base58CryptoHash,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<ProposalOutput> {
+        let hide_votes = self.internal_hide_votes();
+        self.proposals_by_keyword
+            .get(&keyword_hash.into())
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|id| {
+                self.proposals
+                    .get(&id)
+                    .map(|proposal| Self::proposal_output(id, proposal, hide_votes))
+            })
+            .collect()
+    }
+
+    /// Get bounties whose description or required tags have the keyword with given hash, in paginated view.
+    pub fn search_bounties(
+        &self,
+        keyword_hash: Base58CryptoHash,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<BountyOutput> {
+        self.bounties_by_keyword
+            .get(&keyword_hash.into())
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|id| {
+                self.bounties.get(&id).map(|bounty| BountyOutput {
+                    id,
+                    bounty: bounty.into(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_keyword_hashes() {
+        let hashes = keyword_hashes(std::iter::once("Audit of the Token, audit report"));
+        assert_eq!(
+            hashes,
+            vec![
+                keyword_hash("audit"),
+                keyword_hash("the"),
+                keyword_hash("token"),
+                keyword_hash("report"),
+            ]
+        );
+        let long = (0..20)
+            .map(|i| format!("word{}", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(
+            keyword_hashes(std::iter::once(long.as_str())).len(),
+            MAX_KEYWORDS
+        );
+    }
+
+    #[test]
+    fn test_search_proposals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        for description in ["Fund the audit", "Pay the designer"] {
+            contract.add_proposal(ProposalInput {
+                description: description.to_string(),
+                kind: ProposalKind::Vote,
+                idempotency_key: None,
+                depends_on: None,
+            });
+        }
+        let audit = contract.get_keyword_hash("AUDIT".to_string());
+        let found = contract.search_proposals(audit, 0, 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, 0);
+        let the = contract.get_keyword_hash("the".to_string());
+        assert_eq!(contract.search_proposals(the, 1, 10).len(), 1);
+    }
+}
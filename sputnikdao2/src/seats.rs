@@ -0,0 +1,155 @@
+//! Non-transferable seat tokens for members of governing roles, minted by an external contract.
+
+use near_sdk::{AccountId, Gas};
+
+use crate::*;
+
+const GAS_FOR_SEAT_NFT: Gas = Gas(10_000_000_000_000);
+
+#[ext_contract(ext_seat_minter)]
+pub trait SeatMinter {
+    /// Mints non-transferable token of a seat in `role` to `account_id`.
+    fn mint_seat(&mut self, account_id: AccountId, role: String);
+    /// Burns token of a seat in `role` owned by `account_id`.
+    fn burn_seat(&mut self, account_id: AccountId, role: String);
+}
+
+impl Contract {
+    /// Returns minter of seat tokens for given role, if the policy has one.
+    fn seat_minter(policy: &Policy, role: &String) -> Option<AccountId> {
+        policy
+            .seat_nft
+            .as_ref()
+            .filter(|seat_nft| seat_nft.roles.contains(role))
+            .map(|seat_nft| seat_nft.minter_id.clone())
+    }
+
+    /// Mints seat token to the new member. Failure of minting doesn't affect the membership.
+    pub(crate) fn internal_mint_seat(&self, policy: &Policy, member_id: &AccountId, role: &String) {
+        if let Some(minter_id) = Self::seat_minter(policy, role) {
+            ext_seat_minter::mint_seat(
+                member_id.clone(),
+                role.clone(),
+                minter_id,
+                0,
+                GAS_FOR_SEAT_NFT,
+            );
+        }
+    }
+
+    /// Burns seat token of the removed member. Failure of burning doesn't affect the membership.
+    pub(crate) fn internal_burn_seat(&self, policy: &Policy, member_id: &AccountId, role: &String) {
+        if let Some(minter_id) = Self::seat_minter(policy, role) {
+            ext_seat_minter::burn_seat(
+                member_id.clone(),
+                role.clone(),
+                minter_id,
+                0,
+                GAS_FOR_SEAT_NFT,
+            );
+        }
+    }
+
+    /// Burns seat tokens of members that a policy change from `old_policy` to `new_policy`
+    /// dropped from their seat roles.
+    pub(crate) fn internal_burn_dropped_seats(&self, old_policy: &Policy, new_policy: &Policy) {
+        let seat_roles = match &old_policy.seat_nft {
+            Some(seat_nft) => &seat_nft.roles,
+            None => return,
+        };
+        for role in old_policy
+            .roles
+            .iter()
+            .filter(|role| seat_roles.contains(&role.name))
+        {
+            for member_id in role.kind.get_group_members().unwrap_or_default() {
+                if !new_policy.is_group_member(&role.name, member_id) {
+                    self.internal_burn_seat(old_policy, member_id, &role.name);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::policy::{RoleKind, SeatNft};
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::Config;
+
+    use super::*;
+
+    fn seat_contract(context: &mut VMContextBuilder, council: Vec<AccountId>) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(council).upgrade();
+        policy.to_policy_mut().seat_nft = Some(SeatNft {
+            minter_id: accounts(4),
+            roles: vec!["council".to_string()],
+        });
+        Contract::new(Config::test_config(), policy)
+    }
+
+    fn seat_calls(method_name: &str) -> usize {
+        get_created_receipts()
+            .iter()
+            .filter(|receipt| {
+                receipt.receiver_id == accounts(4)
+                    && receipt.actions.iter().any(|action| {
+                        matches!(
+                            action,
+                            near_sdk::mock::VmAction::FunctionCall { function_name, .. }
+                                if function_name == method_name
+                        )
+                    })
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_seat_nft() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = seat_contract(&mut context, vec![accounts(1)]);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(contract
+            .get_policy()
+            .is_group_member("council", &accounts(2)));
+        assert_eq!(seat_calls("mint_seat"), 1);
+    }
+
+    #[test]
+    fn test_seat_nft_burned_on_policy_change() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = seat_contract(&mut context, vec![accounts(1), accounts(2)]);
+        let mut council = contract.get_policy().roles[1].clone();
+        council.kind = RoleKind::Group(vec![accounts(1)].into_iter().collect());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicyAddOrUpdateRole { role: council },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(!contract
+            .get_policy()
+            .is_group_member("council", &accounts(2)));
+        assert_eq!(seat_calls("burn_seat"), 1);
+    }
+}
@@ -0,0 +1,113 @@
+//! Temperature check before formal voting: proposals of kinds listed in `temperature_checks` of the policy
+//! start in `Signaling`, where members register sentiment without weight. Once enough members support
+//! the proposal, it moves to formal voting.
+
+use near_sdk::json_types::U64;
+use near_sdk::near_bindgen;
+
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Registers sentiment of the caller on proposal in the temperature check. Caller must be a member of any group role.
+    /// Signal can be changed until the proposal moves to formal voting, which starts once enough members support it.
+    pub fn signal_proposal(&mut self, id: u64, support: bool) {
+        self.assert_not_read_only();
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::Signaling,
+            "ERR_PROPOSAL_NOT_SIGNALING"
+        );
+        let policy = self.policy.get().unwrap().to_policy();
+        let account_id = env::predecessor_account_id();
+        assert!(policy.is_member(&account_id), "ERR_NOT_MEMBER");
+        proposal.signals.insert(account_id, support);
+        let min_signals = policy
+            .temperature_checks
+            .get(proposal.kind.to_policy_label())
+            .copied()
+            .unwrap_or_default();
+        let supporting = proposal
+            .signals
+            .values()
+            .filter(|support| **support)
+            .count();
+        if supporting >= min_signals as usize {
+            // Voting period starts once the proposal moves to formal voting.
+            proposal.status = ProposalStatus::InProgress;
+            proposal.submission_time = U64::from(env::block_timestamp());
+            proposal.submission_block = U64::from(env::block_height());
+            self.internal_open_or_enqueue(&policy, &mut proposal, id);
+            self.internal_record_transition(id, &proposal.status);
+        }
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn contract_with_signaling(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        policy
+            .to_policy_mut()
+            .temperature_checks
+            .insert("vote".to_string(), 2);
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract
+    }
+
+    #[test]
+    fn test_temperature_check() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_signaling(&mut context);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Signaling
+        );
+        contract.signal_proposal(0, true);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.signal_proposal(0, false);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Signaling
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(1_000)
+            .build());
+        contract.signal_proposal(0, true);
+        let proposal = contract.get_proposal(0).proposal;
+        assert_eq!(proposal.status, ProposalStatus::InProgress);
+        assert_eq!(proposal.submission_time, U64(1_000));
+        contract.act_proposal(0, Action::VoteApprove, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_NOT_READY_FOR_VOTE")]
+    fn test_vote_while_signaling() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_signaling(&mut context);
+        contract.act_proposal(0, Action::VoteApprove, None);
+    }
+}
@@ -0,0 +1,153 @@
+//! Skill tags of bounty claimers, matched on-chain against tags required by bounties.
+
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::views::BountyOutput;
+use crate::*;
+
+/// Max number of skill tags per account.
+pub const MAX_SKILL_TAGS: usize = 16;
+
+/// Max length of a skill tag in bytes.
+pub const MAX_SKILL_TAG_LEN: usize = 32;
+
+/// Checks that given skill tags cover all tags required by the bounty.
+fn has_required_tags(tags: &[String], bounty: &Bounty) -> bool {
+    bounty.required_tags.iter().all(|tag| tags.contains(tag))
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets skill tags of the caller, replacing previous ones. Empty list removes them.
+    /// Storage is paid from storage sponsored for the caller or the attached deposit, see `internal_pay_storage`.
+    #[payable]
+    pub fn set_skill_tags(&mut self, tags: Vec<String>) {
+        self.assert_not_read_only();
+        assert!(tags.len() <= MAX_SKILL_TAGS, "ERR_TOO_MANY_SKILL_TAGS");
+        assert!(
+            tags.iter()
+                .all(|tag| !tag.is_empty() && tag.len() <= MAX_SKILL_TAG_LEN),
+            "ERR_INVALID_SKILL_TAG"
+        );
+        let account_id = env::predecessor_account_id();
+        let storage_before = env::storage_usage();
+        if tags.is_empty() {
+            self.skill_tags.remove(&account_id);
+        } else {
+            self.skill_tags.insert(&account_id, &tags);
+        }
+        self.internal_pay_storage(&account_id, storage_before);
+    }
+
+    /// Returns skill tags of given account.
+    pub fn get_skill_tags(&self, account_id: AccountId) -> Vec<String> {
+        self.skill_tags.get(&account_id).unwrap_or_default()
+    }
+
+    /// Returns bounties that can still be claimed and whose required tags given account has.
+    pub fn get_matching_bounties(&self, account_id: AccountId) -> Vec<BountyOutput> {
+        let tags = self.get_skill_tags(account_id);
+        (0..self.last_bounty_id)
+            .filter_map(|id| {
+                let bounty: Bounty = self.bounties.get(&id)?.into();
+                let claims_count = self.bounty_claims_count.get(&id).unwrap_or_default();
+                if claims_count < bounty.times && has_required_tags(&tags, &bounty) {
+                    Some(BountyOutput { id, bounty })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns accounts with skill tags that have all tags required by given bounty.
+    pub fn get_matching_claimers(&self, bounty_id: u64) -> Vec<AccountId> {
+        let bounty: Bounty = self.bounties.get(&bounty_id).expect("ERR_NO_BOUNTY").into();
+        self.skill_tags
+            .iter()
+            .filter(|(_, tags)| has_required_tags(tags, &bounty))
+            .map(|(account_id, _)| account_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::{U128, U64};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn test_matching_bounties() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "audit".to_string(),
+                    token: String::from(OLD_BASE_TOKEN),
+                    amount: U128(to_yocto("10")),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: tags(&["rust", "security"]),
+                    milestones: vec![],
+                    requires_assignment: false,
+                },
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.set_skill_tags(tags(&["rust", "security", "frontend"]));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.set_skill_tags(tags(&["rust"]));
+
+        assert_eq!(contract.get_matching_claimers(0), vec![accounts(2)]);
+        assert_eq!(contract.get_matching_bounties(accounts(2)).len(), 1);
+        assert!(contract.get_matching_bounties(accounts(3)).is_empty());
+
+        // Fully claimed bounties don't match.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.bounty_claim(0, U64::from(500));
+        assert!(contract.get_matching_bounties(accounts(2)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_DEPOSIT")]
+    fn test_skill_tags_storage_not_paid() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.set_skill_tags(tags(&["rust"]));
+    }
+}
@@ -0,0 +1,328 @@
+//! Voting with weights from an off-chain snapshot, committed as a merkle root when the proposal is created.
+//! Voters prove their weight with a merkle proof, so balances of large token distributions are not stored on the DAO.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, AccountId, CryptoHash};
+
+use crate::policy::UserInfo;
+use crate::*;
+
+/// Merkle root of snapshotted weights and their total, used as total supply of the proposal.
+/// Leaves are `sha256(account_id || weight)` with weight as 16 little endian bytes,
+/// and each node is `sha256` of its two children, the smaller one first.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct WeightSnapshot {
+    pub root: Base58CryptoHash,
+    pub total_weight: U128,
+}
+
+/// Leaf of the snapshot tree for given account and weight.
+pub fn snapshot_leaf(account_id: &AccountId, weight: Balance) -> CryptoHash {
+    let mut leaf = account_id.as_bytes().to_vec();
+    leaf.extend_from_slice(&weight.to_le_bytes());
+    env::sha256_array(&leaf)
+}
+
+/// Parent node of two nodes of the snapshot tree.
+pub fn snapshot_node(left: &CryptoHash, right: &CryptoHash) -> CryptoHash {
+    let (first, second) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    let mut node = first.to_vec();
+    node.extend_from_slice(second);
+    env::sha256_array(&node)
+}
+
+/// Checks that `proof` leads from the leaf of given account and weight to the root of the snapshot.
+fn verify_weight_proof(
+    snapshot: &WeightSnapshot,
+    account_id: &AccountId,
+    weight: Balance,
+    proof: &[Base58CryptoHash],
+) -> bool {
+    let root: CryptoHash = snapshot.root.into();
+    let computed = proof
+        .iter()
+        .fold(snapshot_leaf(account_id, weight), |node, sibling| {
+            snapshot_node(&node, &(*sibling).into())
+        });
+    computed == root
+}
+
+impl Contract {
+    /// Weight `account_id` votes with on given proposal: weight proven against the snapshot or fetched from
+    /// the weight provider if the proposal has one, otherwise the delegated weight.
+    pub(crate) fn internal_vote_weight(
+        &self,
+        proposal_id: u64,
+        proposal: &Proposal,
+        account_id: &AccountId,
+    ) -> Balance {
+        if proposal.weight_snapshot.is_some() || proposal.provider_snapshot.is_some() {
+            self.snapshot_weights
+                .get(&(proposal_id, account_id.clone()))
+                .unwrap_or_default()
+        } else if proposal.balance_snapshot {
+            self.internal_delegation_before(account_id, proposal.submission_block.0)
+        } else {
+            self.get_user_weight(account_id)
+        }
+    }
+
+    /// Total weight of votes on given proposal: total of the snapshot or of the weight provider,
+    /// or the total delegated amount.
+    pub(crate) fn internal_total_weight(&self, proposal: &Proposal) -> Balance {
+        if let Some(snapshot) = &proposal.provider_snapshot {
+            return snapshot.total_weight.map_or(0, |weight| weight.0);
+        }
+        match &proposal.weight_snapshot {
+            Some(snapshot) => snapshot.total_weight.0,
+            None if proposal.balance_snapshot => {
+                self.internal_total_delegation_before(proposal.submission_block.0)
+            }
+            None => self.total_delegation_amount,
+        }
+    }
+
+    /// Caller info for acting on given proposal, with weight from the snapshot if the proposal has one.
+    pub(crate) fn internal_voter_info(&self, proposal_id: u64, proposal: &Proposal) -> UserInfo {
+        let account_id = env::predecessor_account_id();
+        UserInfo {
+            amount: self.internal_vote_weight(proposal_id, proposal, &account_id),
+            account_id,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Add proposal whose votes are weighted by given snapshot instead of delegated tokens.
+    /// Caller must be a member of `snapshot_committer_role` of the policy.
+    #[payable]
+    pub fn add_proposal_with_snapshot(
+        &mut self,
+        proposal: ProposalInput,
+        snapshot: WeightSnapshot,
+    ) -> u64 {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let committer_role = policy
+            .snapshot_committer_role
+            .as_ref()
+            .expect("ERR_NO_SNAPSHOT_COMMITTER");
+        let user = self.internal_user_info();
+        assert!(
+            policy
+                .roles
+                .iter()
+                .any(|role| &role.name == committer_role && role.kind.match_user(&user)),
+            "ERR_NOT_SNAPSHOT_COMMITTER"
+        );
+        let expected_id = self.last_proposal_id;
+        let id = self.add_proposal(proposal);
+        if id != expected_id {
+            // Resubmission with the same idempotency key.
+            return id;
+        }
+        let mut proposal: Proposal = self.proposals.get(&id).unwrap().into();
+        proposal.weight_snapshot = Some(snapshot);
+        self.proposals.insert(&id, &VersionedProposal::V2(proposal));
+        id
+    }
+
+    /// Proves weight of the caller in the snapshot of given proposal. Proven weight is used for voting on it.
+    pub fn prove_vote_weight(&mut self, id: u64, weight: U128, proof: Vec<Base58CryptoHash>) {
+        self.assert_not_read_only();
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let snapshot = proposal.weight_snapshot.expect("ERR_NO_SNAPSHOT");
+        let account_id = env::predecessor_account_id();
+        assert!(
+            verify_weight_proof(&snapshot, &account_id, weight.0, &proof),
+            "ERR_INVALID_WEIGHT_PROOF"
+        );
+        self.snapshot_weights.insert(&(id, account_id), &weight.0);
+    }
+
+    /// Proves weight of the caller in the snapshot of given proposal and acts on it, see `act_proposal`.
+    pub fn act_proposal_with_proof(
+        &mut self,
+        id: u64,
+        action: Action,
+        weight: U128,
+        proof: Vec<Base58CryptoHash>,
+        memo: Option<String>,
+    ) {
+        self.assert_not_read_only();
+        self.prove_vote_weight(id, weight, proof);
+        self.act_proposal(id, action, memo);
+    }
+
+    /// Returns weight proven by given account in the snapshot of given proposal.
+    pub fn get_snapshot_weight(&self, id: u64, account_id: AccountId) -> Option<U128> {
+        self.snapshot_weights.get(&(id, account_id)).map(U128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::policy::{WeightKind, WeightOrRatio};
+    use crate::proposals::ProposalStatus;
+    use crate::{Config, RoleKind, RolePermission, VotePolicy};
+
+    use super::*;
+
+    fn contract_with_snapshot(
+        context: &mut VMContextBuilder,
+        weight_decay: u32,
+    ) -> (Contract, Vec<CryptoHash>) {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.snapshot_committer_role = Some("council".to_string());
+        // Holders of snapshotted tokens, matched by their proven weight.
+        policy_mut.roles.push(RolePermission {
+            name: "holders".to_string(),
+            kind: RoleKind::Member(U128(1)),
+            permissions: vec!["transfer:VoteApprove".to_string()]
+                .into_iter()
+                .collect(),
+            vote_policy: vec![(
+                "transfer".to_string(),
+                VotePolicy {
+                    weight_kind: WeightKind::TokenWeight,
+                    quorum: U128(0),
+                    threshold: WeightOrRatio::Ratio(1, 2),
+                    weight_decay,
+                    participation_quorum: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let leaves = vec![
+            snapshot_leaf(&accounts(2), 30),
+            snapshot_leaf(&accounts(3), 40),
+            snapshot_leaf(&accounts(4), 30),
+        ];
+        let root = snapshot_node(&snapshot_node(&leaves[0], &leaves[1]), &leaves[2]);
+        contract.add_proposal_with_snapshot(
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: String::from(OLD_BASE_TOKEN),
+                    receiver_id: accounts(2),
+                    amount: U128(to_yocto("1")),
+                    msg: None,
+                    category: None,
+                    batch: None,
+                    recurrence: None,
+                },
+                idempotency_key: None,
+                depends_on: None,
+            },
+            WeightSnapshot {
+                root: root.into(),
+                total_weight: U128(100),
+            },
+        );
+        (contract, leaves)
+    }
+
+    #[test]
+    fn test_vote_with_snapshot_proof() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, leaves) = contract_with_snapshot(&mut context, 0);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal_with_proof(
+            0,
+            Action::VoteApprove,
+            U128(30),
+            vec![leaves[1].into(), leaves[2].into()],
+            None,
+        );
+        assert_eq!(contract.get_snapshot_weight(0, accounts(2)), Some(U128(30)));
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        let node = snapshot_node(&leaves[0], &leaves[1]);
+        contract.act_proposal_with_proof(0, Action::VoteApprove, U128(30), vec![node.into()], None);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_snapshot_resubmission() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, leaves) = contract_with_snapshot(&mut context, 0);
+        let mut add = |root: CryptoHash| {
+            contract.add_proposal_with_snapshot(
+                ProposalInput {
+                    description: "test".to_string(),
+                    kind: ProposalKind::Vote,
+                    idempotency_key: Some("key".to_string()),
+                    depends_on: None,
+                },
+                WeightSnapshot {
+                    root: root.into(),
+                    total_weight: U128(100),
+                },
+            )
+        };
+        let id = add(leaves[0]);
+        assert_eq!(add(leaves[1]), id);
+        let snapshot = contract.get_proposal(id).proposal.weight_snapshot.unwrap();
+        assert_eq!(snapshot.root, leaves[0].into());
+    }
+
+    #[test]
+    fn test_vote_weight_decay() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, leaves) = contract_with_snapshot(&mut context, 5_000);
+
+        // Half of the voting period passed, so half of the 50% decay applies.
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(1_000_000_000 * 60 * 60 * 24 * 7 / 2)
+            .build());
+        contract.act_proposal_with_proof(
+            0,
+            Action::VoteApprove,
+            U128(40),
+            vec![leaves[0].into(), leaves[2].into()],
+            None,
+        );
+        assert_eq!(
+            contract.get_proposal(0).proposal.vote_counts["holders"][0],
+            30
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_WEIGHT_PROOF")]
+    fn test_vote_with_wrong_weight() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, leaves) = contract_with_snapshot(&mut context, 0);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.prove_vote_weight(0, U128(60), vec![leaves[1].into(), leaves[2].into()]);
+    }
+}
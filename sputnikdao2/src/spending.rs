@@ -0,0 +1,170 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near_bindgen;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::types::{require, OldAccountId};
+use crate::*;
+
+/// Governed list of spending categories that `Transfer` proposals must be tagged with.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SpendingCategories {
+    pub categories: Vec<String>,
+    /// Length of the reporting period. Spending is accounted in period `timestamp / period`.
+    pub period: U64,
+}
+
+/// Total amount of given token spent in the category.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CategorySpending {
+    pub category: String,
+    /// Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    pub amount: U128,
+}
+
+impl Contract {
+    /// Fails if the policy has spending categories and `category` is not one of them.
+    pub(crate) fn internal_check_spending_category(
+        &self,
+        policy: &Policy,
+        category: &Option<String>,
+    ) -> Result<(), &'static str> {
+        match &policy.spending_categories {
+            Some(spending) => {
+                let category = category.as_ref().ok_or("ERR_NO_SPENDING_CATEGORY")?;
+                require(
+                    spending.categories.contains(category),
+                    "ERR_UNKNOWN_SPENDING_CATEGORY",
+                )
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Adds executed transfer to the spending of the current period.
+    pub(crate) fn internal_record_spending(
+        &mut self,
+        category: &Option<String>,
+        token_id: &OldAccountId,
+        amount: Balance,
+    ) {
+        let policy = self.policy.get().unwrap().to_policy();
+        let (spending, category) = match (&policy.spending_categories, category) {
+            (Some(spending), Some(category)) => (spending, category),
+            _ => return,
+        };
+        let period = env::block_timestamp() / spending.period.0.max(1);
+        let mut totals = self.spending.get(&period).unwrap_or_default();
+        match totals
+            .iter_mut()
+            .find(|total| &total.category == category && &total.token_id == token_id)
+        {
+            Some(total) => total.amount = U128(total.amount.0 + amount),
+            None => totals.push(CategorySpending {
+                category: category.clone(),
+                token_id: token_id.clone(),
+                amount: U128(amount),
+            }),
+        }
+        self.spending.insert(&period, &totals);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns spending per category and token in given period, see `SpendingCategories::period`.
+    pub fn get_spending_by_category(&self, period: u64) -> Vec<CategorySpending> {
+        self.spending.get(&period).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .block_timestamp(1_500)
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().spending_categories = Some(SpendingCategories {
+            categories: vec!["grants".to_string(), "salaries".to_string()],
+            period: U64(1_000),
+        });
+        Contract::new(Config::test_config(), policy)
+    }
+
+    fn transfer(contract: &mut Contract, category: Option<&str>) -> u64 {
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: OLD_BASE_TOKEN.to_string(),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("10")),
+                msg: None,
+                category: category.map(|category| category.to_string()),
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        })
+    }
+
+    #[test]
+    fn test_spending_by_category() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup(&mut context);
+        for _ in 0..2 {
+            let id = transfer(&mut contract, Some("grants"));
+            contract.act_proposal(id, Action::VoteApprove, None);
+            testing_env!(
+                context.build(),
+                near_sdk::VMConfig::test(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![PromiseResult::Successful(vec![])],
+            );
+            contract.on_proposal_callback(id);
+        }
+        assert_eq!(
+            contract.get_spending_by_category(1),
+            vec![CategorySpending {
+                category: "grants".to_string(),
+                token_id: OLD_BASE_TOKEN.to_string(),
+                amount: U128(to_yocto("20")),
+            }]
+        );
+        assert!(contract.get_spending_by_category(0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_UNKNOWN_SPENDING_CATEGORY")]
+    fn test_unknown_spending_category() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup(&mut context);
+        transfer(&mut contract, Some("parties"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_SPENDING_CATEGORY")]
+    fn test_no_spending_category() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup(&mut context);
+        transfer(&mut contract, None);
+    }
+}
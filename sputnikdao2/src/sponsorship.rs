@@ -0,0 +1,130 @@
+//! Storage sponsored for accounts: per-user records on the DAO (e.g. skill tags) are paid from the storage
+//! sponsored for the account before its attached deposit, so contributors without $NEAR can take part.
+//! Sponsored amounts are locked in the DAO balance until the storage is used.
+
+use near_sdk::json_types::U128;
+use near_sdk::{near_bindgen, AccountId, Promise, StorageUsage};
+
+use crate::*;
+
+impl Contract {
+    /// Adds given amount to the storage sponsored for given account.
+    pub(crate) fn internal_sponsor_storage(&mut self, account_id: &AccountId, amount: Balance) {
+        let sponsored = self
+            .storage_sponsorships
+            .get(account_id)
+            .unwrap_or_default();
+        self.storage_sponsorships
+            .insert(account_id, &(sponsored + amount));
+        self.locked_amount += amount;
+    }
+
+    /// Pays for storage used by records of `account_id` since `storage_before`: from the storage sponsored
+    /// for the account first, then from the attached deposit. The rest of the deposit is refunded.
+    /// Released storage is returned to the sponsorship if the account has one, otherwise refunded.
+    pub(crate) fn internal_pay_storage(
+        &mut self,
+        account_id: &AccountId,
+        storage_before: StorageUsage,
+    ) {
+        let storage_after = env::storage_usage();
+        let sponsored = self.storage_sponsorships.get(account_id);
+        let mut refund = env::attached_deposit();
+        if storage_after > storage_before {
+            let storage_cost = (storage_after - storage_before) as u128 * env::storage_byte_cost();
+            let from_sponsorship = storage_cost.min(sponsored.unwrap_or_default());
+            if from_sponsorship > 0 {
+                self.storage_sponsorships
+                    .insert(account_id, &(sponsored.unwrap() - from_sponsorship));
+                self.locked_amount -= from_sponsorship;
+            }
+            assert!(
+                refund >= storage_cost - from_sponsorship,
+                "ERR_NOT_ENOUGH_DEPOSIT"
+            );
+            refund -= storage_cost - from_sponsorship;
+        } else {
+            let released = (storage_before - storage_after) as u128 * env::storage_byte_cost();
+            if sponsored.is_some() {
+                self.internal_sponsor_storage(account_id, released);
+            } else {
+                refund += released;
+            }
+        }
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sponsors storage of given account on this DAO with the attached deposit.
+    #[payable]
+    pub fn sponsor_storage(&mut self, account_id: AccountId) {
+        self.assert_not_read_only();
+        assert!(env::attached_deposit() > 0, "ERR_NO_DEPOSIT");
+        self.internal_sponsor_storage(&account_id, env::attached_deposit());
+    }
+
+    /// Returns amount of storage sponsored for given account that is not used yet.
+    pub fn get_storage_sponsorship(&self, account_id: AccountId) -> U128 {
+        U128(
+            self.storage_sponsorships
+                .get(&account_id)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_sponsored_storage() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::SponsorStorage {
+                account_id: accounts(2),
+                amount: U128(to_yocto("0.01")),
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(contract.locked_amount, to_yocto("0.01"));
+
+        // Skill tags are paid from the sponsorship without any deposit.
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.set_skill_tags(vec!["rust".to_string()]);
+        let remaining = contract.get_storage_sponsorship(accounts(2)).0;
+        assert!(remaining < to_yocto("0.01"));
+        assert_eq!(contract.locked_amount, remaining);
+
+        // Released storage returns to the sponsorship.
+        contract.set_skill_tags(vec![]);
+        assert_eq!(
+            contract.get_storage_sponsorship(accounts(2)).0,
+            to_yocto("0.01")
+        );
+    }
+}
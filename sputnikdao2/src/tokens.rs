@@ -0,0 +1,208 @@
+//! Cached metadata of fungible tokens, used to catch amounts given in wrong decimals.
+
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::{log, near_bindgen, serde_json, AccountId, Gas, Promise};
+
+use crate::types::{convert_old_to_new_token, require, OldAccountId};
+use crate::*;
+
+/// Decimals of $NEAR.
+const NEAR_DECIMALS: u8 = 24;
+
+/// Deposit for registering this DAO in the storage of a token, covers storage of a single account in NEP-145 tokens.
+const STORAGE_DEPOSIT: Balance = 1_250_000_000_000_000_000_000;
+
+const GAS_FOR_FT_METADATA: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_STORAGE_DEPOSIT: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_ON_FT_METADATA: Gas = Gas(5_000_000_000_000);
+
+#[ext_contract(ext_ft_metadata)]
+pub trait FungibleTokenMetadataProvider {
+    fn ft_metadata(&self) -> FungibleTokenMetadata;
+}
+
+/// Registers given account in the storage of given token, so it can receive the token.
+pub(crate) fn internal_storage_deposit(token_id: &AccountId, account_id: &AccountId) -> Promise {
+    Promise::new(token_id.clone()).function_call(
+        "storage_deposit".to_string(),
+        serde_json::json!({ "account_id": account_id, "registration_only": true })
+            .to_string()
+            .into_bytes(),
+        STORAGE_DEPOSIT,
+        GAS_FOR_STORAGE_DEPOSIT,
+    )
+}
+
+impl Contract {
+    /// Returns decimals of given token, if known.
+    fn internal_token_decimals(&self, token_id: &OldAccountId) -> Option<u8> {
+        match convert_old_to_new_token(token_id) {
+            None => Some(NEAR_DECIMALS),
+            Some(token_id) => self.token_decimals.get(&token_id),
+        }
+    }
+
+    /// Fails if `amount` of given token is more than `max_whole_token_amount` of the policy.
+    /// If decimals of the token are not registered, only logs a warning.
+    pub(crate) fn internal_check_amount(
+        &self,
+        policy: &Policy,
+        token_id: &OldAccountId,
+        amount: Balance,
+    ) -> Result<(), &'static str> {
+        let max_whole_token_amount = match &policy.max_whole_token_amount {
+            Some(max_whole_token_amount) => max_whole_token_amount.0,
+            None => return Ok(()),
+        };
+        match self.internal_token_decimals(token_id) {
+            Some(decimals) => {
+                let max_amount = 10u128
+                    .checked_pow(decimals as u32)
+                    .and_then(|one_token| one_token.checked_mul(max_whole_token_amount))
+                    .unwrap_or(Balance::MAX);
+                require(amount <= max_amount, "ERR_AMOUNT_TOO_LARGE")
+            }
+            None => {
+                log!("WARN_UNKNOWN_TOKEN_DECIMALS:{}", token_id);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Fetches metadata of given fungible token and caches its decimals for amount validation.
+    pub fn register_token_metadata(&mut self, token_id: AccountId) -> Promise {
+        self.assert_not_read_only();
+        ext_ft_metadata::ft_metadata(token_id.clone(), 0, GAS_FOR_FT_METADATA).then(
+            ext_self::on_ft_metadata(
+                token_id,
+                env::current_account_id(),
+                0,
+                GAS_FOR_ON_FT_METADATA,
+            ),
+        )
+    }
+
+    /// Receiving metadata of the token requested in `register_token_metadata`.
+    #[private]
+    pub fn on_ft_metadata(&mut self, token_id: AccountId) -> u8 {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        let metadata: FungibleTokenMetadata = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                serde_json::from_slice(&value).expect("ERR_INVALID_FT_METADATA")
+            }
+            _ => env::panic_str("ERR_NO_FT_METADATA"),
+        };
+        self.token_decimals.insert(&token_id, &metadata.decimals);
+        metadata.decimals
+    }
+
+    /// Returns tokens added to the whitelist by `AddTokenToWhitelist` proposals.
+    pub fn get_whitelisted_tokens(&self) -> Vec<AccountId> {
+        self.token_whitelist.to_vec()
+    }
+
+    /// Returns decimals of given token, if registered. Can be "" for $NEAR.
+    pub fn get_token_decimals(&self, token_id: OldAccountId) -> Option<u8> {
+        self.internal_token_decimals(&token_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::Config;
+
+    use super::*;
+
+    fn contract_with_max_amount(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().max_whole_token_amount = Some(U128(1_000_000));
+        Contract::new(Config::test_config(), policy)
+    }
+
+    fn transfer(contract: &mut Contract, token_id: &AccountId, amount: Balance) -> u64 {
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: token_id.to_string(),
+                receiver_id: accounts(2),
+                amount: U128(amount),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        })
+    }
+
+    #[test]
+    fn test_register_token_metadata() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_max_amount(&mut context);
+        assert_eq!(contract.get_token_decimals(accounts(3).to_string()), None);
+        let metadata = FungibleTokenMetadata {
+            spec: "ft-1.0.0".to_string(),
+            name: "Test".to_string(),
+            symbol: "TEST".to_string(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 6,
+        };
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .current_account_id(accounts(0))
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                serde_json::to_vec(&metadata).unwrap()
+            )],
+        );
+        assert_eq!(contract.on_ft_metadata(accounts(3)), 6);
+        assert_eq!(
+            contract.get_token_decimals(accounts(3).to_string()),
+            Some(6)
+        );
+        assert_eq!(
+            contract.get_token_decimals(OLD_BASE_TOKEN.to_string()),
+            Some(24)
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        transfer(&mut contract, &accounts(3), 1_000_000 * 10u128.pow(6));
+        // Unknown token is not validated.
+        transfer(&mut contract, &accounts(4), to_yocto("1000000000"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_AMOUNT_TOO_LARGE")]
+    fn test_amount_in_wrong_decimals() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_max_amount(&mut context);
+        contract.token_decimals.insert(&accounts(3), &6);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        // 1 token with 24 decimals, given for a token with 6 decimals.
+        transfer(&mut contract, &accounts(3), to_yocto("1"));
+    }
+}
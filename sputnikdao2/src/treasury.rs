@@ -0,0 +1,515 @@
+//! Registry of tokens held by the DAO, and valuation of the treasury in a reference token, using prices reported
+//! by the oracle of the policy.
+
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{log, near_bindgen, serde_json, AccountId, Gas, Promise, PromiseOrValue};
+
+use crate::types::OldAccountId;
+use crate::*;
+
+const GAS_FOR_FT_BALANCE_OF: Gas = Gas(5_000_000_000_000);
+const GAS_FOR_ON_FT_BALANCE: Gas = Gas(5_000_000_000_000);
+
+/// Token held by the DAO, see `get_treasury`.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryToken {
+    /// Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    /// Available $NEAR, or balance of the fungible token cached by `refresh_token_balance` plus deposits since.
+    /// None until the balance is fetched.
+    pub balance: Option<U128>,
+    /// If the token was added by an `AddTokenToWhitelist` proposal. $NEAR is always whitelisted.
+    pub whitelisted: bool,
+}
+
+/// Oracle trusted to report prices of tokens.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceOracle {
+    pub oracle_id: AccountId,
+    /// Prices reported longer ago than this are flagged as stale.
+    pub max_price_age: U64,
+}
+
+/// Limit on concentration of the treasury in a single token, checked when `Swap` proposals are executed.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct DiversificationLimit {
+    /// Token holdings are valued in. Can be "" for $NEAR.
+    pub reference_token: OldAccountId,
+    /// Max share of the treasury value in a single token that is not whitelisted, in basis points.
+    pub max_concentration_bps: u32,
+}
+
+/// Price of `token_id` in `reference_token`: `10^decimals` units of the token are worth `multiplier` units
+/// of the reference token. Both tokens can be "" for $NEAR.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReportedPrice {
+    pub token_id: OldAccountId,
+    pub reference_token: OldAccountId,
+    pub multiplier: U128,
+    pub decimals: u8,
+}
+
+/// Latest price stored for a pair of tokens.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenPrice {
+    pub multiplier: U128,
+    pub decimals: u8,
+    pub timestamp: U64,
+}
+
+/// Single holding of the treasury, valued in the reference token.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct HoldingValue {
+    /// Can be "" for $NEAR.
+    pub token_id: OldAccountId,
+    pub amount: U128,
+    /// None if there is no price for this token.
+    pub value: Option<U128>,
+    /// True if there is no price, or it's older than `max_price_age`.
+    pub stale: bool,
+}
+
+/// Holdings of the treasury and their total value in the reference token.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TreasuryValuation {
+    pub reference_token: OldAccountId,
+    pub holdings: Vec<HoldingValue>,
+    /// Sum of known values of holdings.
+    pub total: U128,
+    /// True if value of any holding is stale or unknown.
+    pub stale: bool,
+}
+
+impl Contract {
+    fn internal_holding_value(
+        &self,
+        policy: &Policy,
+        token_id: OldAccountId,
+        amount: Balance,
+        reference_token: &OldAccountId,
+    ) -> HoldingValue {
+        if &token_id == reference_token {
+            return HoldingValue {
+                token_id,
+                amount: U128(amount),
+                value: Some(U128(amount)),
+                stale: false,
+            };
+        }
+        let price = self
+            .token_prices
+            .get(&(token_id.clone(), reference_token.clone()));
+        let max_price_age = policy
+            .price_oracle
+            .as_ref()
+            .map(|oracle| oracle.max_price_age.0)
+            .unwrap_or_default();
+        match price {
+            Some(price) => HoldingValue {
+                token_id,
+                amount: U128(amount),
+                value: Some(U128(
+                    amount.saturating_mul(price.multiplier.0) / 10u128.pow(price.decimals as u32),
+                )),
+                stale: env::block_timestamp() > price.timestamp.0 + max_price_age,
+            },
+            None => HoldingValue {
+                token_id,
+                amount: U128(amount),
+                value: None,
+                stale: true,
+            },
+        }
+    }
+
+    /// Checks that after swapping `amount_in` of `token_in` to `token_out`, share of `token_out` in the treasury value
+    /// stays within `diversification_limit` of the policy. Whitelisted tokens are not limited.
+    /// Swapped value is assumed to be preserved, and fails the check if any price is stale or unknown.
+    pub(crate) fn internal_check_diversification(
+        &self,
+        policy: &Policy,
+        token_in: &AccountId,
+        amount_in: Balance,
+        token_out: &AccountId,
+    ) -> bool {
+        let limit = match &policy.diversification_limit {
+            Some(limit) => limit,
+            None => return true,
+        };
+        if self.token_whitelist.contains(token_out) {
+            return true;
+        }
+        let valuation = self.get_treasury_valuation(limit.reference_token.clone());
+        let swapped = self.internal_holding_value(
+            policy,
+            token_in.to_string(),
+            amount_in,
+            &limit.reference_token,
+        );
+        if valuation.stale || swapped.stale {
+            return false;
+        }
+        let held_value = valuation
+            .holdings
+            .iter()
+            .find(|holding| holding.token_id == token_out.as_str())
+            .map(|holding| holding.value.unwrap().0)
+            .unwrap_or_default();
+        let concentration = (held_value + swapped.value.unwrap().0).saturating_mul(10_000);
+        concentration
+            <= valuation
+                .total
+                .0
+                .saturating_mul(limit.max_concentration_bps as u128)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Stores prices reported by the oracle of the policy.
+    pub fn report_prices(&mut self, prices: Vec<ReportedPrice>) {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let oracle = policy.price_oracle.expect("ERR_NO_PRICE_ORACLE");
+        assert_eq!(
+            env::predecessor_account_id(),
+            oracle.oracle_id,
+            "ERR_NOT_PRICE_ORACLE"
+        );
+        for price in prices {
+            self.token_prices.insert(
+                &(price.token_id, price.reference_token),
+                &TokenPrice {
+                    multiplier: price.multiplier,
+                    decimals: price.decimals,
+                    timestamp: U64::from(env::block_timestamp()),
+                },
+            );
+        }
+    }
+
+    /// Fetches balance of this DAO in given fungible token and caches it for the treasury valuation.
+    /// Only for whitelisted or registered tokens, so tokens without price can't make the valuation stale.
+    pub fn refresh_token_balance(&mut self, token_id: AccountId) -> Promise {
+        self.assert_not_read_only();
+        assert!(
+            self.token_whitelist.contains(&token_id) || self.treasury_tokens.contains(&token_id),
+            "ERR_TOKEN_NOT_REGISTERED"
+        );
+        ext_fungible_token::ft_balance_of(
+            env::current_account_id(),
+            token_id.clone(),
+            0,
+            GAS_FOR_FT_BALANCE_OF,
+        )
+        .then(ext_self::on_ft_balance(
+            token_id,
+            env::current_account_id(),
+            0,
+            GAS_FOR_ON_FT_BALANCE,
+        ))
+    }
+
+    /// Receiving balance of the token requested in `refresh_token_balance`.
+    #[private]
+    pub fn on_ft_balance(&mut self, token_id: AccountId) -> U128 {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        let balance: U128 = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                serde_json::from_slice(&value).expect("ERR_INVALID_FT_BALANCE")
+            }
+            _ => env::panic_str("ERR_NO_FT_BALANCE"),
+        };
+        self.token_balances.insert(&token_id, &balance.0);
+        self.treasury_tokens.insert(&token_id);
+        balance
+    }
+
+    /// Returns available $NEAR and fungible tokens of the treasury with their cached balances.
+    /// Tokens are registered by deposits with `ft_transfer_call`, `AddTokenToWhitelist` proposals and
+    /// `refresh_token_balance`.
+    pub fn get_treasury(&self) -> Vec<TreasuryToken> {
+        let mut tokens = vec![TreasuryToken {
+            token_id: OLD_BASE_TOKEN.to_string(),
+            balance: Some(self.get_available_amount()),
+            whitelisted: true,
+        }];
+        let whitelisted = self
+            .token_whitelist
+            .iter()
+            .filter(|token_id| !self.treasury_tokens.contains(token_id));
+        for token_id in self.treasury_tokens.iter().chain(whitelisted) {
+            tokens.push(TreasuryToken {
+                balance: self.token_balances.get(&token_id).map(U128),
+                whitelisted: self.token_whitelist.contains(&token_id),
+                token_id: token_id.to_string(),
+            });
+        }
+        tokens
+    }
+
+    /// Returns available $NEAR and cached balances of fungible tokens, valued in `reference_token` at latest prices.
+    pub fn get_treasury_valuation(&self, reference_token: OldAccountId) -> TreasuryValuation {
+        let policy = self.policy.get().unwrap().to_policy();
+        let mut holdings = vec![self.internal_holding_value(
+            &policy,
+            OLD_BASE_TOKEN.to_string(),
+            self.get_available_amount().0,
+            &reference_token,
+        )];
+        for (token_id, amount) in self.token_balances.iter() {
+            holdings.push(self.internal_holding_value(
+                &policy,
+                token_id.to_string(),
+                amount,
+                &reference_token,
+            ));
+        }
+        TreasuryValuation {
+            reference_token,
+            total: U128(
+                holdings
+                    .iter()
+                    .filter_map(|holding| holding.value.map(|value| value.0))
+                    .sum(),
+            ),
+            stale: holdings.iter().any(|holding| holding.stale),
+            holdings,
+        }
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    /// Adds the deposit to the cached balance of the token if any. Registers whitelisted tokens in the treasury,
+    /// others are kept but not registered, so deposits of arbitrary tokens can't grow the registry.
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.assert_not_read_only();
+        assert!(msg.is_empty(), "ERR_INVALID_MESSAGE");
+        let token_id = env::predecessor_account_id();
+        if self.token_whitelist.contains(&token_id) {
+            self.treasury_tokens.insert(&token_id);
+        }
+        if let Some(balance) = self.token_balances.get(&token_id) {
+            self.token_balances.insert(&token_id, &(balance + amount.0));
+        }
+        log!("Deposit of {} {} from {}", amount.0, token_id, sender_id);
+        PromiseOrValue::Value(U128(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_treasury_valuation() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().price_oracle = Some(PriceOracle {
+            oracle_id: accounts(2),
+            max_price_age: U64(1_000),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.token_balances.insert(&accounts(3), &2_000_000);
+        contract.token_balances.insert(&accounts(4), &5);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.report_prices(vec![ReportedPrice {
+            token_id: OLD_BASE_TOKEN.to_string(),
+            reference_token: accounts(3).to_string(),
+            multiplier: U128(3_000_000),
+            decimals: 24,
+        }]);
+        let near_amount = contract.get_available_amount().0;
+        let valuation = contract.get_treasury_valuation(accounts(3).to_string());
+        assert_eq!(
+            valuation.holdings[0].value,
+            Some(U128(near_amount * 3_000_000 / 10u128.pow(24)))
+        );
+        assert_eq!(valuation.holdings[1].value, Some(U128(2_000_000)));
+        assert_eq!(valuation.holdings[2].value, None);
+        assert!(valuation.stale);
+        assert_eq!(
+            valuation.total.0,
+            near_amount * 3_000_000 / 10u128.pow(24) + 2_000_000
+        );
+
+        testing_env!(context.block_timestamp(1_001).build());
+        let valuation = contract.get_treasury_valuation(accounts(3).to_string());
+        assert!(valuation.holdings[0].stale);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_PRICE_ORACLE")]
+    fn test_report_prices_not_oracle() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().price_oracle = Some(PriceOracle {
+            oracle_id: accounts(2),
+            max_price_age: U64(1_000),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.report_prices(vec![]);
+    }
+
+    #[test]
+    fn test_diversification_limit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.price_oracle = Some(PriceOracle {
+            oracle_id: accounts(2),
+            max_price_age: U64(1_000),
+        });
+        policy_mut.diversification_limit = Some(DiversificationLimit {
+            reference_token: accounts(3).to_string(),
+            max_concentration_bps: 5_000,
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        contract.token_balances.insert(&accounts(3), &1_000);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        let price = |token_id: String, multiplier| ReportedPrice {
+            token_id,
+            reference_token: accounts(3).to_string(),
+            multiplier: U128(multiplier),
+            decimals: 0,
+        };
+        contract.report_prices(vec![
+            price(OLD_BASE_TOKEN.to_string(), 0),
+            price(accounts(4).to_string(), 1),
+        ]);
+
+        let mut swap = |contract: &mut Contract, amount_in| {
+            testing_env!(context
+                .predecessor_account_id(accounts(1))
+                .attached_deposit(to_yocto("1"))
+                .build());
+            let id = contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Swap {
+                    exchange_id: accounts(5),
+                    token_in: accounts(3),
+                    amount_in: U128(amount_in),
+                    token_out: accounts(4),
+                    msg: "{}".to_string(),
+                },
+                idempotency_key: None,
+                depends_on: None,
+            });
+            contract.act_proposal(id, Action::VoteApprove, None);
+            contract.get_proposal(id).proposal.status
+        };
+        // 60% of the treasury would be in the swapped token.
+        assert_eq!(swap(&mut contract, 600), ProposalStatus::Failed);
+        assert_eq!(swap(&mut contract, 400), ProposalStatus::Approved);
+
+        contract.token_whitelist.insert(&accounts(4));
+        assert_eq!(swap(&mut contract, 600), ProposalStatus::Approved);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOKEN_NOT_REGISTERED")]
+    fn test_refresh_unknown_token_balance() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.token_whitelist.insert(&accounts(4));
+        contract.refresh_token_balance(accounts(4));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.refresh_token_balance(accounts(3));
+    }
+
+    #[test]
+    fn test_treasury_registry() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.token_whitelist.insert(&accounts(3));
+        contract.token_whitelist.insert(&accounts(4));
+        let deposit =
+            |context: &mut VMContextBuilder, contract: &mut Contract, token_id, amount| {
+                testing_env!(context.predecessor_account_id(token_id).build());
+                contract.ft_on_transfer(accounts(2), U128(amount), String::new());
+            };
+        deposit(&mut context, &mut contract, accounts(3), 100);
+        // Deposits of tokens that are not whitelisted are not registered.
+        deposit(&mut context, &mut contract, accounts(5), 100);
+        assert_eq!(contract.treasury_tokens.len(), 1);
+        let treasury = contract.get_treasury();
+        assert_eq!(treasury[0].token_id, OLD_BASE_TOKEN.to_string());
+        assert_eq!(
+            treasury[1..],
+            [
+                TreasuryToken {
+                    token_id: accounts(3).to_string(),
+                    balance: None,
+                    whitelisted: true,
+                },
+                TreasuryToken {
+                    token_id: accounts(4).to_string(),
+                    balance: None,
+                    whitelisted: true,
+                },
+            ]
+        );
+
+        // Registered tokens stay after they are removed from the whitelist, deposits are added to the cached balance.
+        contract.token_whitelist.remove(&accounts(3));
+        contract.token_balances.insert(&accounts(3), &1_000);
+        deposit(&mut context, &mut contract, accounts(3), 100);
+        assert_eq!(
+            contract.get_treasury()[1],
+            TreasuryToken {
+                token_id: accounts(3).to_string(),
+                balance: Some(U128(1_100)),
+                whitelisted: false,
+            }
+        );
+    }
+}
@@ -1,8 +1,10 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::json_types::Base64VecU8;
+use near_sdk::json_types::{Base64VecU8, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{AccountId, Balance, Gas};
 
+use crate::proposals::Vote;
+
 /// Account ID used for $NEAR in near-sdk v3.
 /// Need to keep it around for backward compatibility.
 pub const OLD_BASE_TOKEN: &str = "";
@@ -28,6 +30,54 @@ pub struct Config {
     /// Generic metadata. Can be used by specific UI to store additional data.
     /// This is not used by anything in the contract.
     pub metadata: Base64VecU8,
+    /// Structured metadata, so explorers can render the DAO from on-chain data.
+    #[serde(default)]
+    pub dao_metadata: DaoMetadata,
+}
+
+/// Structured metadata of the DAO. This is not used by anything in the contract.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DaoMetadata {
+    /// Links to website, socials, forum, etc.
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Hash of the logo, e.g. in the blob store or IPFS.
+    #[serde(default)]
+    pub logo_hash: Option<String>,
+    /// Reference to the legal entity behind the DAO.
+    #[serde(default)]
+    pub legal_entity: Option<String>,
+    /// Timezone to display periods in, e.g. "Europe/Berlin".
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// Partial update of `DaoMetadata`. Fields that are None are left unchanged.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DaoMetadataUpdate {
+    pub links: Option<Vec<String>>,
+    pub logo_hash: Option<String>,
+    pub legal_entity: Option<String>,
+    pub timezone: Option<String>,
+}
+
+impl DaoMetadata {
+    pub fn update(&mut self, update: &DaoMetadataUpdate) {
+        if let Some(links) = &update.links {
+            self.links = links.clone();
+        }
+        if let Some(logo_hash) = &update.logo_hash {
+            self.logo_hash = Some(logo_hash.clone());
+        }
+        if let Some(legal_entity) = &update.legal_entity {
+            self.legal_entity = Some(legal_entity.clone());
+        }
+        if let Some(timezone) = &update.timezone {
+            self.timezone = Some(timezone.clone());
+        }
+    }
 }
 
 #[cfg(test)]
@@ -37,6 +87,7 @@ impl Config {
             name: "Test".to_string(),
             purpose: "to test".to_string(),
             metadata: Base64VecU8(vec![]),
+            dao_metadata: DaoMetadata::default(),
         }
     }
 }
@@ -55,16 +106,46 @@ pub enum Action {
     VoteReject,
     /// Vote to remove given proposal or bounty (because it's spam).
     VoteRemove,
+    /// Vote to abstain on given proposal: counts toward the quorum but not the approval ratio.
+    VoteAbstain,
     /// Finalize proposal, called when it's expired to return the funds
     /// (or in the future can be used for early proposal closure).
     Finalize,
     /// Move a proposal to the hub to shift into another DAO.
     MoveToHub,
+    /// Vote to revoke execution of approved proposal in the grace period after approval.
+    Revoke,
+    /// Execute approved proposal queued by the revoke grace period, the execution delay or an execution window,
+    /// once it ends.
+    Execute,
+    /// Cancel approved proposal before it's executed. Only allowed for the guardian role of the policy.
+    Veto,
+    /// Withdraw own proposal while it's in progress and has no votes, returning the bond.
+    /// Allowed for the proposer without permission.
+    Withdraw,
+    /// Replace previous vote of the caller with given vote while the proposal is in progress.
+    /// Allowed by the permission to cast the new vote.
+    ChangeVote(Vote),
+    /// Extend voting on given proposal in progress by `extra_time`, in `period_unit` of the policy,
+    /// e.g. for a proposal close to the quorum that is about to expire.
+    ExtendVoting { extra_time: U64 },
+    /// Remove given proposal as spam, slashing its bond as set by `bond_slash_on_spam` of the policy.
+    MarkSpam,
+    /// Pause the DAO, see `pause`. Not an action on proposals, allowed by permissions like `*:Pause`.
+    Pause,
+    /// Unpause the DAO, see `unpause`. Not an action on proposals, allowed by permissions like `*:Unpause`.
+    Unpause,
+    /// Rebalance the treasury, see `rebalance`. Not an action on proposals, allowed by permissions like `*:Rebalance`.
+    Rebalance,
 }
 
 impl Action {
     pub fn to_policy_label(&self) -> String {
-        format!("{:?}", self)
+        match self {
+            Action::ChangeVote(vote) => Action::from(vote.clone()).to_policy_label(),
+            Action::ExtendVoting { .. } => "ExtendVoting".to_string(),
+            _ => format!("{:?}", self),
+        }
     }
 }
 
@@ -81,3 +162,12 @@ pub fn convert_old_to_new_token(old_account_id: &OldAccountId) -> Option<Account
     }
     Some(AccountId::new_unchecked(old_account_id.clone()))
 }
+
+/// Returns given error if the condition doesn't hold, for checks that views report instead of panicking.
+pub(crate) fn require(condition: bool, err: &'static str) -> Result<(), &'static str> {
+    if condition {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
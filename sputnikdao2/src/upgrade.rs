@@ -1,11 +1,12 @@
 //! Logic to upgrade Sputnik contracts.
 
 use near_sdk::serde_json::json;
-use near_sdk::Gas;
+use near_sdk::{log, Gas, IntoStorageKey};
 
 use crate::*;
 
 const FACTORY_KEY: &[u8; 7] = b"FACTORY";
+const READ_ONLY_KEY: &[u8; 9] = b"READ_ONLY";
 const ERR_MUST_BE_SELF_OR_FACTORY: &str = "ERR_MUST_BE_SELF_OR_FACTORY";
 const UPDATE_GAS_LEFTOVER: Gas = Gas(10_000_000_000_000);
 const FACTORY_UPDATE_GAS_LEFTOVER: Gas = Gas(15_000_000_000_000);
@@ -51,6 +52,66 @@ pub(crate) fn internal_set_factory_info(factory_info: &FactoryInfo) {
     );
 }
 
+/// Returns true if `migrate` found state this code can't read, so the DAO is in read-only mode.
+/// By design not using contract STATE, like the factory info.
+pub(crate) fn internal_is_read_only() -> bool {
+    env::storage_has_key(READ_ONLY_KEY)
+}
+
+fn internal_set_read_only(read_only: bool) {
+    if read_only {
+        env::storage_write(READ_ONLY_KEY, &[]);
+    } else {
+        env::storage_remove(READ_ONLY_KEY);
+    }
+}
+
+/// Returns true if value stored under given key, if any, deserializes with the layout of this code.
+fn is_compatible<T: BorshDeserialize>(key: &[u8]) -> bool {
+    match env::storage_read(key) {
+        Some(value) => T::try_from_slice(&value).is_ok(),
+        None => true,
+    }
+}
+
+/// Storage key of given item of a collection with given prefix.
+fn item_key(prefix: StorageKeys, id: u64) -> Vec<u8> {
+    [prefix.into_storage_key(), id.try_to_vec().unwrap()].concat()
+}
+
+impl Contract {
+    /// Checks that parts of the state loaded lazily (config, policy, latest proposal and bounty)
+    /// have the layout of this code. Called by `migrate`, which puts the DAO in read-only mode otherwise,
+    /// until an `UpgradeSelf` proposal deploys code that reads the state and migrates it again.
+    pub(crate) fn internal_check_state_compatible(&self) {
+        let mut compatible = is_compatible::<Config>(&StorageKeys::Config.into_storage_key())
+            && is_compatible::<VersionedPolicy>(&StorageKeys::Policy.into_storage_key());
+        if let Some(id) = self.last_proposal_id.checked_sub(1) {
+            compatible &= is_compatible::<VersionedProposal>(&item_key(StorageKeys::Proposals, id));
+        }
+        if let Some(id) = self.last_bounty_id.checked_sub(1) {
+            compatible &= is_compatible::<VersionedBounty>(&item_key(StorageKeys::Bounties, id));
+        }
+        if !compatible {
+            log!("ERR_INCOMPATIBLE_STATE: read-only until migrated again");
+        }
+        internal_set_read_only(!compatible);
+    }
+
+    /// Checks that the DAO is not in read-only mode.
+    pub(crate) fn assert_not_read_only(&self) {
+        assert!(!internal_is_read_only(), "ERR_READ_ONLY");
+    }
+
+    /// Checks that the DAO is not in read-only mode, unless given proposal kind upgrades it.
+    pub(crate) fn assert_not_read_only_unless_upgrade(&self, kind: &ProposalKind) {
+        assert!(
+            !internal_is_read_only() || matches!(kind, ProposalKind::UpgradeSelf { .. }),
+            "ERR_READ_ONLY"
+        );
+    }
+}
+
 /// Function that receives new contract, updates and calls migration.
 /// Two options who call it:
 ///  - current account, in case of fetching contract code from factory;
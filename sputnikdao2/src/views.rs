@@ -1,7 +1,12 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
 
 use std::cmp::min;
+use std::collections::HashMap;
 
+use crate::applicants::is_applicant;
+use crate::policy::UserInfo;
+use crate::proposals::{BondState, Vote, PROPOSAL_KIND_LABELS};
 use crate::*;
 
 /// This is format of output via JSON for the proposal.
@@ -14,6 +19,22 @@ pub struct ProposalOutput {
     pub proposal: Proposal,
 }
 
+/// Timestamps of the lifecycle of a proposal, computed with the current policy.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalTimeline {
+    pub submission_time: U64,
+    /// Start of voting, in `period_unit` of the policy.
+    pub voting_opens: U64,
+    /// End of voting, including extensions, in `period_unit` of the policy.
+    pub voting_closes: U64,
+    /// End of the revoke grace period or the execution delay after approval, if the policy has them,
+    /// in `period_unit` of the policy.
+    pub timelock_ends: Option<U64>,
+    pub executed_at: Option<U64>,
+}
+
 /// This is format of output via JSON for the bounty.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -24,6 +45,94 @@ pub struct BountyOutput {
     pub bounty: Bounty,
 }
 
+/// Information about a kind of proposal under the current policy.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalKindInfo {
+    /// Label of the proposal kind, as used in the permissions.
+    pub label: String,
+    /// Permission required to add this kind of proposal.
+    pub required_permission: String,
+    /// Roles that are allowed to add this kind of proposal.
+    pub proposer_roles: Vec<String>,
+    /// Roles that are allowed to vote to approve this kind of proposal.
+    pub voter_roles: Vec<String>,
+    /// Bond that must be attached when adding this kind of proposal, by roles without one in `role_bonds`.
+    pub bond: U128,
+    /// Bonds of proposer roles with their own bond for this kind of proposal, see `proposal_bonds` of the policy.
+    pub role_bonds: HashMap<String, U128>,
+    /// Voting period of this kind of proposal.
+    pub proposal_period: U64,
+    /// If this kind of proposal can be both added and approved under the current policy.
+    pub enabled: bool,
+}
+
+/// Single hypothetical vote used by `simulate_policy`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SimulatedVote {
+    /// Account that casts the vote. Roles of the account are resolved using simulated policy.
+    pub account_id: AccountId,
+    /// Token weight of the account. Used for token weighted vote policies and `RoleKind::Member`.
+    pub weight: U128,
+    pub vote: Vote,
+}
+
+/// Hypothetical voting scenario to evaluate against a policy.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteScenario {
+    /// Kind of proposal that is voted on. Defines which vote policy applies.
+    pub kind: ProposalKind,
+    /// Votes in the order they are cast.
+    pub votes: Vec<SimulatedVote>,
+    /// Total token weight to use. If not given, current total delegation of this DAO is used.
+    pub total_supply: Option<U128>,
+}
+
+/// Result of a dry run of `add_proposal`, see `simulate_proposal`.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ValidationResult {
+    /// If `add_proposal` would add the proposal.
+    pub valid: bool,
+    /// Errors `add_proposal` would fail with, e.g. `ERR_PERMISSION_DENIED`.
+    pub errors: Vec<String>,
+    /// Bond to attach, unless it's fronted from the allowance of a role of the proposer.
+    pub bond: U128,
+    /// If the proposal would wait for a sponsor before voting starts.
+    pub awaits_sponsor: bool,
+}
+
+impl Contract {
+    /// Whether individual votes of proposals in progress are hidden from views by the policy.
+    pub(crate) fn internal_hide_votes(&self) -> bool {
+        self.policy
+            .get()
+            .unwrap()
+            .to_policy()
+            .hide_votes_until_finalized
+    }
+
+    /// Proposal output with individual votes, their weights and delegates, and poll votes removed
+    /// if `hide_votes` and the proposal is still in progress.
+    pub(crate) fn proposal_output(
+        id: u64,
+        proposal: VersionedProposal,
+        hide_votes: bool,
+    ) -> ProposalOutput {
+        let mut proposal: Proposal = proposal.into();
+        if hide_votes && proposal.status == ProposalStatus::InProgress {
+            proposal.votes.clear();
+            proposal.vote_weights.clear();
+            proposal.delegated_votes.clear();
+            proposal.poll_votes.clear();
+        }
+        ProposalOutput { id, proposal }
+    }
+}
+
 #[near_bindgen]
 impl Contract {
     /// Returns semver of this contract.
@@ -36,11 +145,52 @@ impl Contract {
         self.config.get().unwrap().clone()
     }
 
+    /// Returns amount of proposal bonds currently fronted by the DAO for members of given role.
+    pub fn get_fronted_bonds(&self, role: String) -> U128 {
+        U128(self.fronted_bonds.get(&role).unwrap_or_default())
+    }
+
+    /// Returns structured metadata of the DAO.
+    pub fn get_dao_metadata(&self) -> DaoMetadata {
+        self.config.get().unwrap().dao_metadata
+    }
+
     /// Returns policy of this contract.
     pub fn get_policy(&self) -> Policy {
         self.policy.get().unwrap().to_policy().clone()
     }
 
+    /// Returns all kinds of proposals with permissions and parameters required under the current policy.
+    pub fn get_proposal_kinds(&self) -> Vec<ProposalKindInfo> {
+        let policy = self.policy.get().unwrap().to_policy();
+        PROPOSAL_KIND_LABELS
+            .iter()
+            .map(|label| {
+                let proposer_roles = policy.roles_with_permission(label, &Action::AddProposal);
+                let voter_roles = policy.roles_with_permission(label, &Action::VoteApprove);
+                let role_bonds = proposer_roles
+                    .iter()
+                    .filter(|role| policy.proposal_bonds.contains_key(*role))
+                    .map(|role| (role.clone(), U128(policy.role_proposal_bond(role, label))))
+                    .collect();
+                ProposalKindInfo {
+                    label: label.to_string(),
+                    required_permission: format!(
+                        "{}:{}",
+                        label,
+                        Action::AddProposal.to_policy_label()
+                    ),
+                    enabled: !proposer_roles.is_empty() && !voter_roles.is_empty(),
+                    proposer_roles,
+                    voter_roles,
+                    bond: policy.proposal_bond,
+                    role_bonds,
+                    proposal_period: U64(policy.proposal_period_of(label)),
+                }
+            })
+            .collect()
+    }
+
     /// Returns staking contract if available. Otherwise returns empty.
     pub fn get_staking_contract(self) -> String {
         self.staking_id.map(String::from).unwrap_or_default()
@@ -59,7 +209,17 @@ impl Contract {
 
     /// Returns available amount of NEAR that can be spent (outside of amount for storage and bonds).
     pub fn get_available_amount(&self) -> U128 {
-        U128(env::account_balance() - self.get_locked_storage_amount().0 - self.locked_amount)
+        U128(
+            env::account_balance()
+                - self.get_locked_storage_amount().0
+                - self.locked_amount
+                - self.burned_amount,
+        )
+    }
+
+    /// Returns amount of NEAR of burned proposal bonds, which can't be spent.
+    pub fn get_burned_amount(&self) -> U128 {
+        U128(self.burned_amount)
     }
 
     /// Returns total delegated stake.
@@ -72,6 +232,11 @@ impl Contract {
         U128(self.delegations.get(&account_id).unwrap_or_default())
     }
 
+    /// Returns delegated stake to given account at the end of the block before given block height.
+    pub fn delegation_balance_before(&self, account_id: AccountId, block_height: U64) -> U128 {
+        U128(self.internal_delegation_before(&account_id, block_height.0))
+    }
+
     /// Combines balance and total amount for calling from external contracts.
     pub fn delegation_balance_ratio(&self, account_id: AccountId) -> (U128, U128) {
         (
@@ -87,12 +252,12 @@ impl Contract {
 
     /// Get proposals in paginated view.
     pub fn get_proposals(&self, from_index: u64, limit: u64) -> Vec<ProposalOutput> {
+        let hide_votes = self.internal_hide_votes();
         (from_index..min(self.last_proposal_id, from_index + limit))
             .filter_map(|id| {
-                self.proposals.get(&id).map(|proposal| ProposalOutput {
-                    id,
-                    proposal: proposal.into(),
-                })
+                self.proposals
+                    .get(&id)
+                    .map(|proposal| Self::proposal_output(id, proposal, hide_votes))
             })
             .collect()
     }
@@ -100,12 +265,86 @@ impl Contract {
     /// Get specific proposal.
     pub fn get_proposal(&self, id: u64) -> ProposalOutput {
         let proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL");
-        ProposalOutput {
-            id,
-            proposal: proposal.into(),
+        Self::proposal_output(id, proposal, self.internal_hide_votes())
+    }
+
+    /// Get proposals of the kind with given hash, see `ProposalKind::hash`.
+    pub fn find_proposal_by_hash(&self, hash: Base58CryptoHash) -> Vec<ProposalOutput> {
+        let hide_votes = self.internal_hide_votes();
+        self.proposals_by_hash
+            .all(&hash.into())
+            .into_iter()
+            .filter_map(|id| {
+                self.proposals
+                    .get(&id)
+                    .map(|proposal| Self::proposal_output(id, proposal, hide_votes))
+            })
+            .collect()
+    }
+
+    /// Get proposals transferring to, calling or sending chain messages through given account in paginated view.
+    /// `from_index` and `limit` paginate over the proposals of this account, not over all proposals.
+    /// Proposals are in order of creation.
+    pub fn get_proposals_targeting(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<ProposalOutput> {
+        let hide_votes = self.internal_hide_votes();
+        // Edits move proposals within the index, so it's sorted by id before paginating.
+        let mut ids = self.proposals_by_receiver.all(&account_id);
+        ids.sort_unstable();
+        ids.into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|id| {
+                self.proposals
+                    .get(&id)
+                    .map(|proposal| Self::proposal_output(id, proposal, hide_votes))
+            })
+            .collect()
+    }
+
+    /// Get timestamps of the lifecycle of given proposal.
+    pub fn get_proposal_timeline(&self, id: u64) -> ProposalTimeline {
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        ProposalTimeline {
+            submission_time: proposal.submission_time,
+            voting_opens: match policy.period_unit {
+                PeriodUnit::Nanoseconds => proposal.submission_time,
+                PeriodUnit::Blocks => proposal.submission_block,
+            },
+            voting_closes: U64(policy.voting_end(&proposal)),
+            timelock_ends: proposal.execution_after,
+            executed_at: proposal.executed_at,
         }
     }
 
+    /// Get outcome of the last execution of given `FunctionCall` or `MultiFunctionCall` proposal.
+    pub fn get_proposal_execution_result(&self, id: u64) -> Option<ExecutionResult> {
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        proposal.execution_result
+    }
+
+    /// Get weight required from each role to decide on given proposal with the current policy.
+    pub fn get_proposal_thresholds(&self, id: u64) -> Vec<RoleThreshold> {
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let policy = self.policy.get().unwrap().to_policy();
+        policy
+            .roles
+            .iter()
+            .filter_map(|role| {
+                policy.proposal_role_threshold(
+                    role,
+                    &proposal,
+                    self.internal_total_weight(&proposal),
+                )
+            })
+            .collect()
+    }
+
     /// Get given bounty by id.
     pub fn get_bounty(&self, id: u64) -> BountyOutput {
         let bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY");
@@ -134,11 +373,150 @@ impl Contract {
 
     /// Get bounty claims for given user.
     pub fn get_bounty_claims(&self, account_id: AccountId) -> Vec<BountyClaim> {
-        self.bounty_claimers.get(&account_id).unwrap_or_default()
+        self.internal_bounty_claims(&account_id).unwrap_or_default()
     }
 
     /// Returns number of claims per given bounty.
     pub fn get_bounty_number_of_claims(&self, id: u64) -> u32 {
         self.bounty_claims_count.get(&id).unwrap_or_default()
     }
+
+    /// Evaluates given voting `scenario` against given `policy` without changing any state.
+    /// Votes are applied in order the same way `act_proposal` does, until the proposal is decided.
+    /// Votes from accounts that are not allowed to vote on this kind of proposal are ignored.
+    pub fn simulate_policy(
+        &self,
+        policy: VersionedPolicy,
+        scenario: VoteScenario,
+    ) -> ProposalStatus {
+        let policy = policy.upgrade().to_policy();
+        let total_supply = scenario
+            .total_supply
+            .map(|total_supply| total_supply.0)
+            .unwrap_or(self.total_delegation_amount);
+        let tier_vote_policy = policy.transfer_tier_vote_policy(&scenario.kind);
+        let mut proposal = Proposal {
+            proposer: env::current_account_id(),
+            description: String::new(),
+            kind_hash: scenario.kind.hash().into(),
+            kind: scenario.kind,
+            status: ProposalStatus::InProgress,
+            vote_counts: Default::default(),
+            votes: Default::default(),
+            submission_time: U64::from(env::block_timestamp()),
+            submission_block: U64::from(env::block_height()),
+            voting_extension: U64(0),
+            auto_extensions: 0,
+            bond: U128(0),
+            bond_state: BondState::Locked,
+            encrypted_details: None,
+            executed_at: None,
+            execution_after: None,
+            revoke_counts: Default::default(),
+            revoked_by: vec![],
+            bond_fronted_by: None,
+            execution_started_at: None,
+            reconciled: false,
+            payout_balance_before: None,
+            finalized_by: None,
+            reviewer: None,
+            weight_snapshot: None,
+            tombstone: None,
+            signals: HashMap::default(),
+            balance_snapshot: false,
+            commitments: HashMap::default(),
+            poll_votes: HashMap::default(),
+            poll_tallies: vec![],
+            vote_weights: HashMap::default(),
+            execution_result: None,
+            provider_snapshot: None,
+            depends_on: None,
+            sponsor: None,
+            reviewed_by: None,
+            committee_budget: None,
+            tier_vote_policy,
+            delegated_votes: HashMap::default(),
+        };
+        for simulated in scenario.votes {
+            if proposal.status != ProposalStatus::InProgress {
+                break;
+            }
+            let action = Action::from(simulated.vote.clone());
+            let user = UserInfo {
+                account_id: simulated.account_id.clone(),
+                amount: simulated.weight.0,
+            };
+            let (roles, allowed) = policy.can_execute_action(user, &proposal.kind, &action);
+            if !allowed || proposal.votes.contains_key(&simulated.account_id) {
+                continue;
+            }
+            proposal.update_votes(
+                &simulated.account_id,
+                &roles,
+                simulated.vote,
+                &policy,
+                simulated.weight.0,
+                &[],
+            );
+            proposal.status = policy.proposal_status(&proposal, roles, total_supply);
+        }
+        proposal.status
+    }
+
+    /// Returns true if given account is allowed to execute given action on proposals with given kind label,
+    /// e.g. `transfer` and `VoteApprove`, under the current policy.
+    pub fn check_permission(
+        &self,
+        account_id: AccountId,
+        proposal_kind_label: String,
+        action: Action,
+    ) -> bool {
+        let policy = self.policy.get().unwrap().to_policy();
+        let user = UserInfo {
+            amount: self.get_user_weight(&account_id),
+            account_id,
+        };
+        policy.has_permission(user, &proposal_kind_label, &action)
+    }
+
+    /// Dry run of `add_proposal` of given proposal by given account, so the bond isn't attached in vain.
+    pub fn simulate_proposal(
+        &self,
+        account_id: AccountId,
+        proposal: ProposalInput,
+    ) -> ValidationResult {
+        let policy = self.policy.get().unwrap().to_policy();
+        let user = || UserInfo {
+            amount: self.get_user_weight(&account_id),
+            account_id: account_id.clone(),
+        };
+        let mut errors = vec![];
+        if let Err(err) = self.internal_check_proposal(&policy, &proposal.kind) {
+            errors.push(err.to_string());
+        }
+        if self.paused {
+            errors.push("ERR_PAUSED".to_string());
+        }
+        if matches!(proposal.depends_on, Some(id) if id >= self.last_proposal_id) {
+            errors.push("ERR_INVALID_DEPENDENCY".to_string());
+        }
+        if !policy
+            .can_execute_action(user(), &proposal.kind, &Action::AddProposal)
+            .1
+        {
+            errors.push("ERR_PERMISSION_DENIED".to_string());
+        }
+        if self.internal_is_duplicate(&policy, &proposal.kind) {
+            errors.push("ERR_DUPLICATE_PROPOSAL".to_string());
+        }
+        if self.internal_is_rate_limited(&policy, &account_id) {
+            errors.push("ERR_APPLICANT_RATE_LIMITED".to_string());
+        }
+        ValidationResult {
+            valid: errors.is_empty(),
+            errors,
+            bond: U128(policy.proposal_bond_of(user(), &proposal.kind)),
+            awaits_sponsor: is_applicant(&policy, &account_id),
+        }
+    }
 }
@@ -0,0 +1,180 @@
+//! Delegation of voting between members: a member who delegates to another one doesn't need to vote,
+//! the delegate votes for both in the roles they share. Delegation chains are resolved at vote time,
+//! and members who voted themselves keep their own vote.
+
+use near_sdk::{near_bindgen, AccountId};
+
+use crate::policy::UserInfo;
+use crate::*;
+
+impl Contract {
+    /// Returns accounts who delegated voting to given account, directly or through other delegates,
+    /// and haven't voted on given proposal, with their weight on it.
+    pub(crate) fn internal_resolve_delegators(
+        &self,
+        proposal_id: u64,
+        proposal: &Proposal,
+        account_id: &AccountId,
+    ) -> Vec<UserInfo> {
+        let mut delegators = vec![];
+        let mut pending = self.vote_delegators.get(account_id).unwrap_or_default();
+        while let Some(delegator) = pending.pop() {
+            // Delegators who voted themselves already voted for their own delegators.
+            if proposal.votes.contains_key(&delegator) {
+                continue;
+            }
+            pending.extend(self.vote_delegators.get(&delegator).unwrap_or_default());
+            delegators.push(UserInfo {
+                amount: self.internal_vote_weight(proposal_id, proposal, &delegator),
+                account_id: delegator,
+            });
+        }
+        delegators
+    }
+
+    fn internal_undelegate_voting(&mut self, account_id: &AccountId) -> Option<AccountId> {
+        let delegate_id = self.vote_delegations.remove(account_id)?;
+        let mut delegators = self.vote_delegators.get(&delegate_id).unwrap_or_default();
+        delegators.retain(|delegator| delegator != account_id);
+        if delegators.is_empty() {
+            self.vote_delegators.remove(&delegate_id);
+        } else {
+            self.vote_delegators.insert(&delegate_id, &delegators);
+        }
+        Some(delegate_id)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Delegates voting of the caller to given account, replacing the previous delegate.
+    /// Both must be members of a group role, and the delegation can't form a cycle.
+    pub fn delegate_voting(&mut self, account_id: AccountId) {
+        self.assert_not_read_only();
+        let policy = self.policy.get().unwrap().to_policy();
+        let sender_id = env::predecessor_account_id();
+        assert!(
+            policy.is_member(&sender_id) && policy.is_member(&account_id),
+            "ERR_NOT_MEMBER"
+        );
+        let mut delegate_id = Some(account_id.clone());
+        while let Some(current) = delegate_id {
+            assert_ne!(current, sender_id, "ERR_DELEGATION_CYCLE");
+            delegate_id = self.vote_delegations.get(&current);
+        }
+        self.internal_undelegate_voting(&sender_id);
+        self.vote_delegations.insert(&sender_id, &account_id);
+        let mut delegators = self.vote_delegators.get(&account_id).unwrap_or_default();
+        delegators.push(sender_id);
+        self.vote_delegators.insert(&account_id, &delegators);
+    }
+
+    /// Removes delegation of voting of the caller.
+    pub fn undelegate_voting(&mut self) {
+        self.assert_not_read_only();
+        self.internal_undelegate_voting(&env::predecessor_account_id())
+            .expect("ERR_NOT_DELEGATED");
+    }
+
+    /// Returns account given account delegated voting to.
+    pub fn get_voting_delegate(&self, account_id: AccountId) -> Option<AccountId> {
+        self.vote_delegations.get(&account_id)
+    }
+
+    /// Returns accounts who directly delegated voting to given account.
+    pub fn get_voting_delegators(&self, account_id: AccountId) -> Vec<AccountId> {
+        self.vote_delegators.get(&account_id).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn contract_with_council(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3), accounts(4)]);
+        Contract::new(Config::test_config(), policy)
+    }
+
+    #[test]
+    fn test_delegated_vote() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_council(&mut context);
+        // accounts(1) -> accounts(2) -> accounts(3)
+        contract.delegate_voting(accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate_voting(accounts(3));
+        assert_eq!(contract.get_voting_delegate(accounts(1)), Some(accounts(2)));
+        assert_eq!(
+            contract.get_voting_delegators(accounts(3)),
+            vec![accounts(2)]
+        );
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.vote_counts["council"][0], 3);
+        assert_eq!(proposal.status, ProposalStatus::Approved);
+    }
+
+    #[test]
+    fn test_change_delegated_vote() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_council(&mut context);
+        contract.delegate_voting(accounts(2));
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+            idempotency_key: None,
+            depends_on: None,
+        });
+        contract.act_proposal(id, Action::VoteReject, None);
+        // Vote cast for the delegator moves along with the vote of the delegate.
+        contract.act_proposal(id, Action::ChangeVote(Vote::Approve), None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert_eq!(proposal.vote_counts["council"], [2, 0, 0, 0]);
+        assert_eq!(proposal.votes[&accounts(1)], Vote::Approve);
+        assert_eq!(proposal.delegated_votes[&accounts(1)], accounts(2));
+    }
+
+    #[test]
+    fn test_undelegate_voting() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_council(&mut context);
+        contract.delegate_voting(accounts(2));
+        contract.undelegate_voting();
+        assert_eq!(contract.get_voting_delegate(accounts(1)), None);
+        assert!(contract.get_voting_delegators(accounts(2)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DELEGATION_CYCLE")]
+    fn test_delegation_cycle() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = contract_with_council(&mut context);
+        contract.delegate_voting(accounts(2));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate_voting(accounts(1));
+    }
+}
@@ -0,0 +1,239 @@
+//! Voting power sourced from an external contract implementing `VoteWeightProvider`, e.g. a staking farm,
+//! LP positions or an SBT registry. Proposals added while the policy has a `vote_weight_provider` are weighted
+//! by it at the block of their submission, and weights are cached per proposal once fetched.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{near_bindgen, serde_json, AccountId, Gas, Promise};
+
+use crate::*;
+
+const GAS_FOR_GET_WEIGHT: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_ON_WEIGHT: Gas = Gas(5_000_000_000_000);
+
+#[ext_contract(ext_vote_weight_provider)]
+pub trait VoteWeightProvider {
+    /// Returns voting power of given account at `snapshot_ref`, the block height of the proposal submission.
+    fn get_weight(&self, account_id: AccountId, snapshot_ref: U64) -> U128;
+    /// Returns total voting power at `snapshot_ref`, used as total supply of the proposal.
+    fn get_total_weight(&self, snapshot_ref: U64) -> U128;
+}
+
+/// Provider of vote weights of a proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProviderSnapshot {
+    pub provider_id: AccountId,
+    pub snapshot_ref: U64,
+    /// Total weight returned by the provider, None until fetched.
+    pub total_weight: Option<U128>,
+}
+
+/// Parses weight returned by the provider, None if the call failed.
+fn provider_weight() -> Option<U128> {
+    assert_eq!(
+        env::promise_results_count(),
+        1,
+        "ERR_UNEXPECTED_CALLBACK_PROMISES"
+    );
+    match env::promise_result(0) {
+        PromiseResult::Successful(value) => serde_json::from_slice(&value).ok(),
+        _ => None,
+    }
+}
+
+/// Requests total weight of given snapshot from its provider for given proposal.
+fn internal_fetch_total_weight(snapshot: &ProviderSnapshot, proposal_id: u64) -> Promise {
+    ext_vote_weight_provider::get_total_weight(
+        snapshot.snapshot_ref,
+        snapshot.provider_id.clone(),
+        0,
+        GAS_FOR_GET_WEIGHT,
+    )
+    .then(ext_self::on_provider_total_weight(
+        proposal_id,
+        env::current_account_id(),
+        0,
+        GAS_FOR_ON_WEIGHT,
+    ))
+}
+
+impl Contract {
+    /// Snapshot of the weight provider of the policy for proposal added now, requesting its total weight.
+    pub(crate) fn internal_provider_snapshot(
+        &self,
+        policy: &Policy,
+        proposal_id: u64,
+    ) -> Option<ProviderSnapshot> {
+        let snapshot = ProviderSnapshot {
+            provider_id: policy.vote_weight_provider.clone()?,
+            snapshot_ref: U64(env::block_height()),
+            total_weight: None,
+        };
+        internal_fetch_total_weight(&snapshot, proposal_id);
+        Some(snapshot)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Requests weight of the caller on given proposal from its weight provider. Once cached, the weight is used
+    /// for voting on the proposal.
+    pub fn fetch_vote_weight(&mut self, id: u64) -> Promise {
+        self.assert_not_read_only();
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let snapshot = proposal.provider_snapshot.expect("ERR_NO_WEIGHT_PROVIDER");
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.snapshot_weights
+                .get(&(id, account_id.clone()))
+                .is_none(),
+            "ERR_WEIGHT_ALREADY_FETCHED"
+        );
+        ext_vote_weight_provider::get_weight(
+            account_id.clone(),
+            snapshot.snapshot_ref,
+            snapshot.provider_id,
+            0,
+            GAS_FOR_GET_WEIGHT,
+        )
+        .then(ext_self::on_vote_weight(
+            id,
+            account_id,
+            env::current_account_id(),
+            0,
+            GAS_FOR_ON_WEIGHT,
+        ))
+    }
+
+    /// Requests total weight of given proposal again, if the provider failed to return it when it was added.
+    pub fn fetch_total_weight(&mut self, id: u64) -> Promise {
+        self.assert_not_read_only();
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let snapshot = proposal.provider_snapshot.expect("ERR_NO_WEIGHT_PROVIDER");
+        assert!(
+            snapshot.total_weight.is_none(),
+            "ERR_WEIGHT_ALREADY_FETCHED"
+        );
+        internal_fetch_total_weight(&snapshot, id)
+    }
+
+    /// Receiving weight of the voter requested in `fetch_vote_weight`.
+    #[private]
+    pub fn on_vote_weight(&mut self, proposal_id: u64, account_id: AccountId) -> U128 {
+        let weight = provider_weight().expect("ERR_NO_PROVIDER_WEIGHT");
+        self.snapshot_weights
+            .insert(&(proposal_id, account_id), &weight.0);
+        weight
+    }
+
+    /// Receiving total weight of the proposal requested when it was added.
+    #[private]
+    pub fn on_provider_total_weight(&mut self, proposal_id: u64) -> U128 {
+        let weight = provider_weight().expect("ERR_NO_PROVIDER_WEIGHT");
+        let mut proposal: Proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect("ERR_NO_PROPOSAL")
+            .into();
+        if let Some(snapshot) = proposal.provider_snapshot.as_mut() {
+            snapshot.total_weight = Some(weight);
+        }
+        self.proposals
+            .insert(&proposal_id, &VersionedProposal::V2(proposal));
+        weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+    use near_sdk_sim::to_yocto;
+
+    use crate::policy::{WeightKind, WeightOrRatio};
+    use crate::proposals::ProposalStatus;
+    use crate::{Config, RoleKind, RolePermission, VotePolicy};
+
+    use super::*;
+
+    fn callback_env(context: &mut VMContextBuilder, value: &str) {
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(value.as_bytes().to_vec())],
+        );
+    }
+
+    #[test]
+    fn test_vote_with_provider_weight() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        let policy_mut = policy.to_policy_mut();
+        policy_mut.vote_weight_provider = Some(accounts(4));
+        // Stakers of the farm, matched by their provided weight.
+        policy_mut.roles.push(RolePermission {
+            name: "stakers".to_string(),
+            kind: RoleKind::Member(U128(1)),
+            permissions: vec!["transfer:VoteApprove".to_string()]
+                .into_iter()
+                .collect(),
+            vote_policy: vec![(
+                "transfer".to_string(),
+                VotePolicy {
+                    weight_kind: WeightKind::TokenWeight,
+                    quorum: U128(0),
+                    threshold: WeightOrRatio::Ratio(1, 2),
+                    weight_decay: 0,
+                    participation_quorum: None,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+                category: None,
+                batch: None,
+                recurrence: None,
+            },
+            idempotency_key: None,
+            depends_on: None,
+        });
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+        callback_env(&mut context, "\"100\"");
+        contract.on_provider_total_weight(id);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.fetch_vote_weight(id);
+        callback_env(&mut context, "\"60\"");
+        contract.on_vote_weight(id, accounts(2));
+        assert_eq!(
+            contract.get_snapshot_weight(id, accounts(2)),
+            Some(U128(60))
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+}
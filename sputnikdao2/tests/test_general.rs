@@ -8,9 +8,9 @@ use near_sdk_sim::{call, init_simulator, to_yocto, view};
 use crate::utils::*;
 use sputnik_staking::User;
 use sputnikdao2::{
-    default_policy, Action, BountyClaim, BountyOutput, Config, Policy, Proposal, ProposalInput,
-    ProposalKind, ProposalOutput, ProposalStatus, RoleKind, RolePermission, VersionedPolicy,
-    VotePolicy,
+    default_policy, Action, BountyClaim, BountyOutput, Config, PeriodUnit, Policy, Proposal,
+    ProposalInput, ProposalKind, ProposalOutput, ProposalStatus, RoleKind, RolePermission,
+    VersionedPolicy, VotePolicy,
 };
 
 mod utils;
@@ -38,6 +38,7 @@ fn test_large_policy() {
         name: "testdao".to_string(),
         purpose: "to test".to_string(),
         metadata: Base64VecU8(vec![]),
+        dao_metadata: Default::default(),
     };
     let mut policy = default_policy(vec![root.account_id()]);
     const NO_OF_COUNCILS: u32 = 10;
@@ -121,6 +122,48 @@ fn test_multi_council() {
         proposal_period: U64::from(1_000_000_000 * 60 * 60 * 24 * 7),
         bounty_bond: U128(10u128.pow(24)),
         bounty_forgiveness_period: U64::from(1_000_000_000 * 60 * 60 * 24),
+        vote_extension: None,
+        reject_duplicate_proposals: false,
+        max_whole_token_amount: None,
+        seat_nft: None,
+        spending_categories: None,
+        revoke_grace: None,
+        bond_allowances: HashMap::new(),
+        proposal_bonds: HashMap::new(),
+        bounty_marketplaces: vec![],
+        hide_votes_until_finalized: false,
+        finalizer_reward: None,
+        reviewer_assignment: None,
+        max_open_proposals: None,
+        price_oracle: None,
+        period_unit: PeriodUnit::Nanoseconds,
+        snapshot_committer_role: None,
+        evm_bridge: None,
+        chain_message_bridges: vec![],
+        checkpoint_schedules: vec![],
+        execution_windows: vec![],
+        diversification_limit: None,
+        temperature_checks: HashMap::default(),
+        forfeited_bond_share: 0,
+        rebalancing: None,
+        commit_reveal: None,
+        onboarding: None,
+        transfer_batch_window: None,
+        proposal_periods: HashMap::default(),
+        execution_delay: None,
+        guardian_role: None,
+        vote_weight_provider: None,
+        applicants: None,
+        bond_slash_on_reject: None,
+        bond_slash_on_spam: None,
+        bounty_challenge_period: None,
+        bounty_auto_extension: None,
+        bounty_dispute_period: None,
+        bounty_forfeiture: None,
+        member_expiry: HashMap::default(),
+        committees: vec![],
+        max_members: HashMap::new(),
+        transfer_tiers: vec![],
     };
     add_proposal(
         &root,
@@ -130,6 +173,8 @@ fn test_multi_council() {
             kind: ProposalKind::ChangePolicy {
                 policy: VersionedPolicy::Current(new_policy.clone()),
             },
+            idempotency_key: None,
+            depends_on: None,
         },
     )
     .assert_success();
@@ -224,7 +269,7 @@ fn test_bounty_workflow() {
 
     call!(
         user2,
-        dao.bounty_done(bounty_id, None, "Bounty is done".to_string()),
+        dao.bounty_done(bounty_id, None, "Bounty is done".to_string(), None),
         deposit = to_yocto("1")
     )
     .assert_success();
@@ -308,6 +353,8 @@ fn test_create_dao_and_use_token() {
             kind: ProposalKind::SetStakingContract {
                 staking_id: "staking".parse().unwrap(),
             },
+            idempotency_key: None,
+            depends_on: None,
         },
     )
     .assert_success();
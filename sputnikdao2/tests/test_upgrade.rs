@@ -33,6 +33,7 @@ fn test_upgrade_using_factory() {
         name: "testdao".to_string(),
         purpose: "to test".to_string(),
         metadata: Base64VecU8(vec![]),
+        dao_metadata: Default::default(),
     };
     let policy = VersionedPolicy::Default(vec![root.account_id()]);
     let params = json!({ "config": config, "policy": policy })
@@ -71,7 +72,9 @@ fn test_upgrade_using_factory() {
             "add_proposal",
             &json!({ "proposal": ProposalInput {
                 description: "proposal to test".to_string(),
-                kind: ProposalKind::UpgradeSelf { hash }
+                kind: ProposalKind::UpgradeSelf { hash },
+                idempotency_key: None,
+                depends_on: None,
             }})
             .to_string()
             .into_bytes(),
@@ -138,6 +141,8 @@ fn test_upgrade_other() {
                 method_name: "upgrade".to_string(),
                 hash,
             },
+            idempotency_key: None,
+            depends_on: None,
         },
     )
     .assert_success();
@@ -51,6 +51,7 @@ pub fn setup_dao() -> (UserAccount, Contract) {
         name: "test".to_string(),
         purpose: "to test".to_string(),
         metadata: Base64VecU8(vec![]),
+        dao_metadata: Default::default(),
     };
     let dao = deploy!(
         contract: DAOContract,
@@ -107,6 +108,8 @@ pub fn add_member_proposal(
                 member_id: member_id,
                 role: "council".to_string(),
             },
+            idempotency_key: None,
+            depends_on: None,
         },
     )
 }
@@ -129,7 +132,12 @@ pub fn add_transfer_proposal(
                 receiver_id,
                 amount: U128(amount),
                 msg,
+                category: None,
+                batch: None,
+                recurrence: None,
             },
+            idempotency_key: None,
+            depends_on: None,
         },
     )
 }
@@ -147,8 +155,15 @@ pub fn add_bounty_proposal(root: &UserAccount, dao: &Contract) -> ExecutionResul
                     amount: U128(to_yocto("10")),
                     times: 3,
                     max_deadline: U64(env::block_timestamp() + 10_000_000_000),
+                    budget: None,
+                    one_claim_per_account: false,
+                    required_tags: vec![],
+                    milestones: vec![],
+                    requires_assignment: false,
                 },
             },
+            idempotency_key: None,
+            depends_on: None,
         },
     )
 }